@@ -1,5 +1,6 @@
-use noise::Noise;
+use noise::{bounds, Noise};
 use super::TupleUtil;
+use super::blend::hermite_3rd_order_blend;
 
 #[derive(Debug, Clone)]
 pub struct Add<N1, N2>
@@ -21,6 +22,75 @@ where
     right_noise: N2,
 }
 
+#[derive(Debug, Clone)]
+pub struct Subtract<N1, N2>
+where
+    N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    left_noise: N1,
+    right_noise: N2,
+}
+
+#[derive(Debug, Clone)]
+pub struct Divide<N1, N2>
+where
+    N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    left_noise: N1,
+    right_noise: N2,
+}
+
+#[derive(Debug, Clone)]
+pub struct Min<N1, N2>
+where
+    N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    left_noise: N1,
+    right_noise: N2,
+}
+
+#[derive(Debug, Clone)]
+pub struct Max<N1, N2>
+where
+    N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    left_noise: N1,
+    right_noise: N2,
+}
+
+/// Raises the left noise to the power of the right noise.
+#[derive(Debug, Clone)]
+pub struct Power<N1, N2>
+where
+    N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    left_noise: N1,
+    right_noise: N2,
+}
+
+/// Sums any number of noises of the same type, e.g. a `Vec<DynNoise2d>`.
+#[derive(Debug, Clone)]
+pub struct Sum<N>
+where
+    N: Noise,
+{
+    sources: Vec<N>,
+}
+
+/// Multiplies any number of noises of the same type, e.g. a `Vec<DynNoise2d>`.
+#[derive(Debug, Clone)]
+pub struct Product<N>
+where
+    N: Noise,
+{
+    sources: Vec<N>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Combine<N1, N2, F>
 where
@@ -44,6 +114,22 @@ where
     right_noise: N2,
     criteria: N3,
     threshold: f64,
+    upper_bound: f64,
+    edge_falloff: f64,
+}
+
+/// Chooses between `sources` by which band between consecutive `boundaries` the criteria
+/// falls in. Source `i` is used below `boundaries[i]` and at or above `boundaries[i - 1]`.
+#[derive(Debug, Clone)]
+pub struct MultiSelect<N, C>
+where
+    N: Noise,
+    C: Noise<IndexType = N::IndexType, DimType = N::DimType>,
+{
+    sources: Vec<N>,
+    criteria: C,
+    boundaries: Vec<f64>,
+    falloffs: Vec<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -102,6 +188,13 @@ where
             self.right_noise.value_at(pos),
         )
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        let f = &self.combiner;
+        f(
+            self.left_noise.value_at_lod(pos.clone(), footprint),
+            self.right_noise.value_at_lod(pos, footprint),
+        )
+    }
     fn frequency(&self) -> Self::DimType {
         self.left_noise
             .frequency()
@@ -142,6 +235,13 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         self.left_noise.value_at(pos.clone()) + self.right_noise.value_at(pos)
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.left_noise.value_at_lod(pos.clone(), footprint)
+            + self.right_noise.value_at_lod(pos, footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::add(self.left_noise.output_bounds(), self.right_noise.output_bounds())
+    }
     fn frequency(&self) -> Self::DimType {
         self.left_noise
             .frequency()
@@ -182,6 +282,258 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         self.left_noise.value_at(pos.clone()) * self.right_noise.value_at(pos)
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.left_noise.value_at_lod(pos.clone(), footprint)
+            * self.right_noise.value_at_lod(pos, footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::mul(self.left_noise.output_bounds(), self.right_noise.output_bounds())
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.left_noise
+            .frequency()
+            .max(&self.right_noise.frequency())
+    }
+}
+
+impl<N1, N2> Subtract<N1, N2>
+where
+    N1: Noise,
+    N1::DimType: TupleUtil<f64>,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    pub fn new(left_noise: N1, right_noise: N2) -> Subtract<N1, N2> {
+        Subtract {
+            left_noise,
+            right_noise,
+        }
+    }
+
+    pub fn left_noise(&self) -> &N1 {
+        &self.left_noise
+    }
+    pub fn right_noise(&self) -> &N2 {
+        &self.right_noise
+    }
+}
+
+impl<N1, N2> Noise for Subtract<N1, N2>
+where
+    N1: Noise,
+    N1::DimType: TupleUtil<f64>,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    type IndexType = N1::IndexType;
+    type DimType = N1::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.left_noise.value_at(pos.clone()) - self.right_noise.value_at(pos)
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.left_noise.value_at_lod(pos.clone(), footprint)
+            - self.right_noise.value_at_lod(pos, footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::sub(self.left_noise.output_bounds(), self.right_noise.output_bounds())
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.left_noise
+            .frequency()
+            .max(&self.right_noise.frequency())
+    }
+}
+
+impl<N1, N2> Divide<N1, N2>
+where
+    N1: Noise,
+    N1::DimType: TupleUtil<f64>,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    pub fn new(left_noise: N1, right_noise: N2) -> Divide<N1, N2> {
+        Divide {
+            left_noise,
+            right_noise,
+        }
+    }
+
+    pub fn left_noise(&self) -> &N1 {
+        &self.left_noise
+    }
+    pub fn right_noise(&self) -> &N2 {
+        &self.right_noise
+    }
+}
+
+impl<N1, N2> Noise for Divide<N1, N2>
+where
+    N1: Noise,
+    N1::DimType: TupleUtil<f64>,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    type IndexType = N1::IndexType;
+    type DimType = N1::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.left_noise.value_at(pos.clone()) / self.right_noise.value_at(pos)
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.left_noise.value_at_lod(pos.clone(), footprint)
+            / self.right_noise.value_at_lod(pos, footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::div(self.left_noise.output_bounds(), self.right_noise.output_bounds())
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.left_noise
+            .frequency()
+            .max(&self.right_noise.frequency())
+    }
+}
+
+impl<N1, N2> Min<N1, N2>
+where
+    N1: Noise,
+    N1::DimType: TupleUtil<f64>,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    pub fn new(left_noise: N1, right_noise: N2) -> Min<N1, N2> {
+        Min {
+            left_noise,
+            right_noise,
+        }
+    }
+
+    pub fn left_noise(&self) -> &N1 {
+        &self.left_noise
+    }
+    pub fn right_noise(&self) -> &N2 {
+        &self.right_noise
+    }
+}
+
+impl<N1, N2> Noise for Min<N1, N2>
+where
+    N1: Noise,
+    N1::DimType: TupleUtil<f64>,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    type IndexType = N1::IndexType;
+    type DimType = N1::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        f64::min(
+            self.left_noise.value_at(pos.clone()),
+            self.right_noise.value_at(pos),
+        )
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        f64::min(
+            self.left_noise.value_at_lod(pos.clone(), footprint),
+            self.right_noise.value_at_lod(pos, footprint),
+        )
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::min(self.left_noise.output_bounds(), self.right_noise.output_bounds())
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.left_noise
+            .frequency()
+            .max(&self.right_noise.frequency())
+    }
+}
+
+impl<N1, N2> Max<N1, N2>
+where
+    N1: Noise,
+    N1::DimType: TupleUtil<f64>,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    pub fn new(left_noise: N1, right_noise: N2) -> Max<N1, N2> {
+        Max {
+            left_noise,
+            right_noise,
+        }
+    }
+
+    pub fn left_noise(&self) -> &N1 {
+        &self.left_noise
+    }
+    pub fn right_noise(&self) -> &N2 {
+        &self.right_noise
+    }
+}
+
+impl<N1, N2> Noise for Max<N1, N2>
+where
+    N1: Noise,
+    N1::DimType: TupleUtil<f64>,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    type IndexType = N1::IndexType;
+    type DimType = N1::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        f64::max(
+            self.left_noise.value_at(pos.clone()),
+            self.right_noise.value_at(pos),
+        )
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        f64::max(
+            self.left_noise.value_at_lod(pos.clone(), footprint),
+            self.right_noise.value_at_lod(pos, footprint),
+        )
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::max(self.left_noise.output_bounds(), self.right_noise.output_bounds())
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.left_noise
+            .frequency()
+            .max(&self.right_noise.frequency())
+    }
+}
+
+impl<N1, N2> Power<N1, N2>
+where
+    N1: Noise,
+    N1::DimType: TupleUtil<f64>,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    pub fn new(left_noise: N1, right_noise: N2) -> Power<N1, N2> {
+        Power {
+            left_noise,
+            right_noise,
+        }
+    }
+
+    pub fn left_noise(&self) -> &N1 {
+        &self.left_noise
+    }
+    pub fn right_noise(&self) -> &N2 {
+        &self.right_noise
+    }
+}
+
+impl<N1, N2> Noise for Power<N1, N2>
+where
+    N1: Noise,
+    N1::DimType: TupleUtil<f64>,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+{
+    type IndexType = N1::IndexType;
+    type DimType = N1::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.left_noise
+            .value_at(pos.clone())
+            .powf(self.right_noise.value_at(pos))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.left_noise
+            .value_at_lod(pos.clone(), footprint)
+            .powf(self.right_noise.value_at_lod(pos, footprint))
+    }
     fn frequency(&self) -> Self::DimType {
         self.left_noise
             .frequency()
@@ -189,6 +541,98 @@ where
     }
 }
 
+impl<N> Sum<N>
+where
+    N: Noise,
+{
+    pub fn new(sources: Vec<N>) -> Sum<N> {
+        Sum { sources }
+    }
+
+    pub fn sources(&self) -> &[N] {
+        &self.sources
+    }
+    pub fn sources_mut(&mut self) -> &mut Vec<N> {
+        &mut self.sources
+    }
+}
+
+impl<N> Noise for Sum<N>
+where
+    N: Noise,
+    N::DimType: TupleUtil<f64>,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.sources
+            .iter()
+            .fold(0.0, |v, s| v + s.value_at(pos.clone()))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.sources
+            .iter()
+            .fold(0.0, |v, s| v + s.value_at_lod(pos.clone(), footprint))
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        self.sources
+            .iter()
+            .fold((0.0, 0.0), |b, s| bounds::add(b, s.output_bounds()))
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.sources
+            .iter()
+            .fold(N::DimType::saturate(0.0), |f, s| f.max(&s.frequency()))
+    }
+}
+
+impl<N> Product<N>
+where
+    N: Noise,
+{
+    pub fn new(sources: Vec<N>) -> Product<N> {
+        Product { sources }
+    }
+
+    pub fn sources(&self) -> &[N] {
+        &self.sources
+    }
+    pub fn sources_mut(&mut self) -> &mut Vec<N> {
+        &mut self.sources
+    }
+}
+
+impl<N> Noise for Product<N>
+where
+    N: Noise,
+    N::DimType: TupleUtil<f64>,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.sources
+            .iter()
+            .fold(1.0, |v, s| v * s.value_at(pos.clone()))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.sources
+            .iter()
+            .fold(1.0, |v, s| v * s.value_at_lod(pos.clone(), footprint))
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        self.sources
+            .iter()
+            .fold((1.0, 1.0), |b, s| bounds::mul(b, s.output_bounds()))
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.sources
+            .iter()
+            .fold(N::DimType::saturate(0.0), |f, s| f.max(&s.frequency()))
+    }
+}
+
 impl<N1, N2, N3> Select<N1, N2, N3>
 where
     N1: Noise,
@@ -206,6 +650,28 @@ where
             right_noise,
             criteria,
             threshold,
+            upper_bound: f64::INFINITY,
+            edge_falloff: 0.0,
+        }
+    }
+
+    /// Selects the left noise only while the criteria is within `(lower, upper]`.
+    pub fn with_bounds(self, lower: f64, upper: f64) -> Select<N1, N2, N3> {
+        assert!(lower < upper);
+        Select {
+            threshold: lower,
+            upper_bound: upper,
+            ..self
+        }
+    }
+
+    /// Blends between the noises with a hermite curve when the criteria is within `falloff`
+    /// of a bound, rather than switching abruptly.
+    pub fn with_edge_falloff(self, falloff: f64) -> Select<N1, N2, N3> {
+        assert!(falloff >= 0.0);
+        Select {
+            edge_falloff: falloff,
+            ..self
         }
     }
 
@@ -221,6 +687,45 @@ where
     pub fn threshold(&self) -> f64 {
         self.threshold
     }
+    pub fn bounds(&self) -> (f64, f64) {
+        (self.threshold, self.upper_bound)
+    }
+    pub fn edge_falloff(&self) -> f64 {
+        self.edge_falloff
+    }
+}
+
+/// The value `Select` gives a point whose criteria is `criteria`, evaluating only the sources
+/// it needs.
+pub fn select_value<L, R>(
+    criteria: f64,
+    (lower, upper): (f64, f64),
+    edge_falloff: f64,
+    left: L,
+    right: R,
+) -> f64
+where
+    L: FnOnce() -> f64,
+    R: FnOnce() -> f64,
+{
+    let falloff = edge_falloff.min((upper - lower) / 2.0);
+    if falloff > 0.0 {
+        if criteria < lower - falloff || criteria > upper + falloff {
+            right()
+        } else if criteria < lower + falloff {
+            let t = (criteria - (lower - falloff)) / (2.0 * falloff);
+            hermite_3rd_order_blend(right(), left(), t)
+        } else if criteria > upper - falloff {
+            let t = (criteria - (upper - falloff)) / (2.0 * falloff);
+            hermite_3rd_order_blend(left(), right(), t)
+        } else {
+            left()
+        }
+    } else if criteria > lower && criteria <= upper {
+        left()
+    } else {
+        right()
+    }
 }
 
 impl<N1, N2, N3> Noise for Select<N1, N2, N3>
@@ -234,12 +739,26 @@ where
     type DimType = N1::DimType;
 
     fn value_at(&self, pos: Self::IndexType) -> f64 {
-        let criteria = self.criteria.value_at(pos.clone());
-        if criteria > self.threshold {
-            self.left_noise.value_at(pos)
-        } else {
-            self.right_noise.value_at(pos)
-        }
+        select_value(
+            self.criteria.value_at(pos.clone()),
+            (self.threshold, self.upper_bound),
+            self.edge_falloff,
+            || self.left_noise.value_at(pos.clone()),
+            || self.right_noise.value_at(pos.clone()),
+        )
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        select_value(
+            self.criteria.value_at_lod(pos.clone(), footprint),
+            (self.threshold, self.upper_bound),
+            self.edge_falloff,
+            || self.left_noise.value_at_lod(pos.clone(), footprint),
+            || self.right_noise.value_at_lod(pos.clone(), footprint),
+        )
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        //Edge falloff blends between the sources, so stays within both.
+        bounds::hull(self.left_noise.output_bounds(), self.right_noise.output_bounds())
     }
     fn frequency(&self) -> Self::DimType {
         self.left_noise
@@ -248,6 +767,122 @@ where
     }
 }
 
+impl<N, C> MultiSelect<N, C>
+where
+    N: Noise,
+    C: Noise<IndexType = N::IndexType, DimType = N::DimType>,
+{
+    pub fn new(criteria: C, sources: Vec<N>, boundaries: Vec<f64>) -> MultiSelect<N, C> {
+        assert!(!sources.is_empty());
+        assert_eq!(sources.len(), boundaries.len() + 1);
+        assert!(boundaries.windows(2).all(|w| w[0] < w[1]));
+
+        let falloffs = vec![0.0; boundaries.len()];
+        MultiSelect {
+            sources,
+            criteria,
+            boundaries,
+            falloffs,
+        }
+    }
+
+    /// Uses the same edge falloff for every boundary.
+    pub fn with_falloff(self, falloff: f64) -> MultiSelect<N, C> {
+        let falloffs = vec![falloff; self.boundaries.len()];
+        self.with_falloffs(falloffs)
+    }
+
+    /// Sets the edge falloff of each boundary. The transitions of adjacent boundaries must
+    /// not overlap.
+    pub fn with_falloffs(self, falloffs: Vec<f64>) -> MultiSelect<N, C> {
+        assert_eq!(falloffs.len(), self.boundaries.len());
+        assert!(falloffs.iter().all(|&f| f >= 0.0));
+        for i in 1..self.boundaries.len() {
+            assert!(
+                self.boundaries[i - 1] + falloffs[i - 1] <= self.boundaries[i] - falloffs[i],
+                "Edge falloffs of adjacent boundaries overlap."
+            );
+        }
+        MultiSelect { falloffs, ..self }
+    }
+
+    pub fn sources(&self) -> &[N] {
+        &self.sources
+    }
+    pub fn criteria(&self) -> &C {
+        &self.criteria
+    }
+    pub fn boundaries(&self) -> &[f64] {
+        &self.boundaries
+    }
+    pub fn falloffs(&self) -> &[f64] {
+        &self.falloffs
+    }
+
+    /// The value for `criteria`, with `sample` evaluating a source at the position.
+    fn select_with<F>(&self, criteria: f64, sample: F) -> f64
+    where
+        F: Fn(&N) -> f64,
+    {
+        let band = self.boundaries
+            .iter()
+            .take_while(|&&b| b <= criteria)
+            .count();
+
+        if band > 0 && criteria - self.boundaries[band - 1] < self.falloffs[band - 1] {
+            self.blend_at(band - 1, criteria, sample)
+        } else if band < self.boundaries.len()
+            && self.boundaries[band] - criteria < self.falloffs[band]
+        {
+            self.blend_at(band, criteria, sample)
+        } else {
+            sample(&self.sources[band])
+        }
+    }
+
+    fn blend_at<F>(&self, boundary: usize, criteria: f64, sample: F) -> f64
+    where
+        F: Fn(&N) -> f64,
+    {
+        let falloff = self.falloffs[boundary];
+        let t = (criteria - (self.boundaries[boundary] - falloff)) / (2.0 * falloff);
+        let low = sample(&self.sources[boundary]);
+        let high = sample(&self.sources[boundary + 1]);
+        hermite_3rd_order_blend(low, high, t)
+    }
+}
+
+impl<N, C> Noise for MultiSelect<N, C>
+where
+    N: Noise,
+    N::DimType: TupleUtil<f64>,
+    C: Noise<IndexType = N::IndexType, DimType = N::DimType>,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        let criteria = self.criteria.value_at(pos.clone());
+        self.select_with(criteria, |source| source.value_at(pos.clone()))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        let criteria = self.criteria.value_at_lod(pos.clone(), footprint);
+        self.select_with(criteria, |source| source.value_at_lod(pos.clone(), footprint))
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        self.sources
+            .iter()
+            .map(Noise::output_bounds)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), bounds::hull)
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.sources
+            .iter()
+            .skip(1)
+            .fold(self.sources[0].frequency(), |f, s| f.max(&s.frequency()))
+    }
+}
+
 impl<N1, N2, N3, F> Blend<N1, N2, N3, F>
 where
     N1: Noise,
@@ -297,9 +932,142 @@ where
         let f = &self.blend_fn;
         f(val1, val2, criteria)
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        let val1 = self.left_noise.value_at_lod(pos.clone(), footprint);
+        let val2 = self.right_noise.value_at_lod(pos.clone(), footprint);
+        let criteria = self.criteria.value_at_lod(pos, footprint);
+
+        let f = &self.blend_fn;
+        f(val1, val2, criteria)
+    }
     fn frequency(&self) -> Self::DimType {
         self.left_noise
             .frequency()
             .max(&self.right_noise.frequency())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f64;
+
+    use adapter::{Constant, FunctionValue, NoiseExt};
+    use noise::{bounds, Noise};
+    use super::{MultiSelect, Product, Select, Sum};
+
+    fn constant(value: f64) -> Constant<f64, f64> {
+        Constant::new(value)
+    }
+
+    #[test]
+    fn binary_adapters_values_and_bounds() {
+        let ramp = FunctionValue::<f64, f64, _>::new(|&x: &f64| x);
+        let (a, b) = (constant(3.0), constant(-2.0));
+        assert_eq!(a.clone().divide(b.clone()).value_at(0.0), -1.5);
+        assert_eq!(a.clone().min_with(b.clone()).value_at(0.0), -2.0);
+        assert_eq!(a.clone().max_with(b.clone()).value_at(0.0), 3.0);
+        assert_eq!(a.clone().min_with(b.clone()).output_bounds(), (-2.0, -2.0));
+        assert_eq!(a.clone().max_with(b.clone()).output_bounds(), (3.0, 3.0));
+        assert_eq!(a.clone().divide(b.clone()).output_bounds(), (-1.5, -1.5));
+        for i in -10..10 {
+            let x = f64::from(i) * 0.5;
+            assert_eq!(ramp.clone().min_with(constant(1.0)).value_at(x), x.min(1.0));
+            assert_eq!(ramp.clone().max_with(constant(1.0)).value_at(x), x.max(1.0));
+        }
+
+        //Division by zero follows IEEE 754 and leaves the bounds open.
+        let by_zero = a.clone().divide(constant(0.0));
+        assert_eq!(by_zero.value_at(0.0), f64::INFINITY);
+        assert_eq!(by_zero.output_bounds(), bounds::UNBOUNDED);
+        assert!(constant(0.0).divide(constant(0.0)).value_at(0.0).is_nan());
+        assert_eq!(a.clone().divide(constant(-0.0)).value_at(0.0), f64::NEG_INFINITY);
+
+        //Negative bases only have real powers for integer exponents.
+        assert_eq!(constant(2.0).power(constant(3.0)).value_at(0.0), 8.0);
+        assert_eq!(b.clone().power(constant(3.0)).value_at(0.0), -8.0);
+        assert!(b.clone().power(constant(0.5)).value_at(0.0).is_nan());
+        assert_eq!(b.power(constant(3.0)).output_bounds(), bounds::UNBOUNDED);
+    }
+
+    fn assert_continuous<N: Noise<IndexType = f64>>(noise: &N, from: f64, to: f64) {
+        let steps = 4000;
+        let step = (to - from) / f64::from(steps);
+        for i in 0..steps {
+            let x = from + f64::from(i) * step;
+            assert!((noise.value_at(x + step) - noise.value_at(x)).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn select_bounds_and_falloff() {
+        let ramp = FunctionValue::<f64, f64, _>::new(|&x: &f64| x);
+        let select = Select::new(constant(1.0), constant(-1.0), ramp, 0.0);
+        let hard = select.clone().with_bounds(-0.5, 0.5);
+        assert_eq!(hard.value_at(-0.5), -1.0);
+        assert_eq!(hard.value_at(-0.49), 1.0);
+        assert_eq!(hard.value_at(0.5), 1.0);
+        assert_eq!(hard.value_at(0.51), -1.0);
+
+        let soft = hard.with_edge_falloff(0.2);
+        assert_continuous(&soft, -1.0, 1.0);
+        assert_eq!(soft.value_at(-0.71), -1.0);
+        assert_eq!(soft.value_at(0.0), 1.0);
+        assert_eq!(soft.value_at(0.71), -1.0);
+        //Halfway through a transition both sources weigh the same.
+        assert!(soft.value_at(-0.5).abs() < 1e-12);
+        assert!(soft.value_at(0.5).abs() < 1e-12);
+        assert_eq!(soft.output_bounds(), (-1.0, 1.0));
+
+        //The falloff is limited to half the band, so the transitions meet but don't overlap.
+        let narrow = select.with_bounds(0.0, 0.1).with_edge_falloff(1.0);
+        assert_eq!(narrow.edge_falloff(), 1.0);
+        assert_continuous(&narrow, -0.5, 0.6);
+        assert_eq!(narrow.value_at(0.05), 1.0);
+    }
+
+    #[test]
+    fn multi_select_picks_source_per_band() {
+        let ramp = FunctionValue::<f64, f64, _>::new(|&x: &f64| x);
+        let sources = vec![constant(0.0), constant(1.0), constant(2.0)];
+        let select = MultiSelect::new(ramp, sources, vec![0.0, 1.0]);
+        for &(x, expected) in &[(-1.0, 0.0), (-1e-9, 0.0), (0.0, 1.0), (0.5, 1.0), (1.0, 2.0)] {
+            assert_eq!(select.value_at(x), expected);
+        }
+        assert_eq!(select.output_bounds(), (0.0, 2.0));
+
+        let soft = select.with_falloff(0.25);
+        assert_continuous(&soft, -1.0, 2.0);
+        assert_eq!(soft.value_at(-0.26), 0.0);
+        assert!((soft.value_at(0.0) - 0.5).abs() < 1e-12);
+        assert_eq!(soft.value_at(0.5), 1.0);
+        assert!((soft.value_at(1.0) - 1.5).abs() < 1e-12);
+        assert_eq!(soft.value_at(1.26), 2.0);
+    }
+
+    #[test]
+    fn sum_and_product_values_and_bounds() {
+        let sources = vec![constant(2.0), constant(-3.0), constant(0.5)];
+        let sum = Sum::new(sources.clone());
+        let product = Product::new(sources);
+        assert_eq!(sum.value_at(0.0), -0.5);
+        assert_eq!(sum.output_bounds(), (-0.5, -0.5));
+        assert_eq!(product.value_at(0.0), -3.0);
+        assert_eq!(product.output_bounds(), (-3.0, -3.0));
+
+        //Empty sums and products are their identities.
+        assert_eq!(Sum::<Constant<f64, f64>>::new(Vec::new()).value_at(0.0), 0.0);
+        assert_eq!(Product::<Constant<f64, f64>>::new(Vec::new()).value_at(0.0), 1.0);
+
+        let ramp = FunctionValue::<f64, f64, _>::new(|&x: &f64| x);
+        let ranged = vec![ramp.clone().clamp(-1.0, 1.0), ramp.clamp(-2.0, 0.5)];
+        let (sum, product) = (Sum::new(ranged.clone()), Product::new(ranged));
+        assert_eq!(sum.output_bounds(), (-3.0, 1.5));
+        assert_eq!(product.output_bounds(), (-2.0, 2.0));
+        for i in -30..30 {
+            let x = f64::from(i) * 0.1;
+            let (a, b) = (x.clamp(-1.0, 1.0), x.clamp(-2.0, 0.5));
+            assert_eq!(sum.value_at(x), a + b);
+            assert_eq!(product.value_at(x), a * b);
+        }
+    }
+}
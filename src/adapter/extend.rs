@@ -39,6 +39,12 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         self.noise.value_at(pos[0])
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod(pos[0], footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        self.noise.output_bounds()
+    }
     fn frequency(&self) -> Self::DimType {
         (self.noise.width(), 0.0)
     }
@@ -67,6 +73,12 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         self.noise.value_at([pos[0], pos[1]])
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod([pos[0], pos[1]], footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        self.noise.output_bounds()
+    }
     fn frequency(&self) -> Self::DimType {
         (self.noise.width(), self.noise.height(), 1.0)
     }
@@ -1,6 +1,6 @@
-use noise::Noise;
+use noise::{bounds, Noise};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum FilterKind {
     LowPass,
     HighPass,
@@ -41,6 +41,16 @@ where
     pub fn inner_noise(&self) -> &N {
         &self.noise
     }
+
+    fn apply(&self, val: f64) -> f64 {
+        if val < self.low {
+            self.low
+        } else if val > self.high {
+            self.high
+        } else {
+            val
+        }
+    }
 }
 
 impl<N> Noise for Clamp<N>
@@ -51,14 +61,13 @@ where
     type DimType = N::DimType;
 
     fn value_at(&self, pos: Self::IndexType) -> f64 {
-        let val = self.noise.value_at(pos);
-        if val < self.low {
-            self.low
-        } else if val > self.high {
-            self.high
-        } else {
-            val
-        }
+        self.apply(self.noise.value_at(pos))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.apply(self.noise.value_at_lod(pos, footprint))
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::clamp(self.noise.output_bounds(), self.low, self.high)
     }
     fn frequency(&self) -> Self::DimType {
         self.noise.frequency()
@@ -99,19 +108,8 @@ where
     pub fn blend_fn(&self) -> &F {
         &self.blend_fn
     }
-}
-
-impl<N, F> Noise for Filter<N, F>
-where
-    N: Noise,
-    F: Fn(f64, f64, f64) -> f64,
-{
-    type IndexType = N::IndexType;
-    type DimType = N::DimType;
-
-    fn value_at(&self, pos: Self::IndexType) -> f64 {
-        let val = self.noise.value_at(pos);
 
+    fn apply(&self, val: f64) -> f64 {
         let (x1, x2) = match self.kind {
             FilterKind::LowPass => (val, 0.0),
             FilterKind::HighPass => (0.0, val),
@@ -129,6 +127,22 @@ where
             x1
         }
     }
+}
+
+impl<N, F> Noise for Filter<N, F>
+where
+    N: Noise,
+    F: Fn(f64, f64, f64) -> f64,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.apply(self.noise.value_at(pos))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.apply(self.noise.value_at_lod(pos, footprint))
+    }
     fn frequency(&self) -> Self::DimType {
         self.noise.frequency()
     }
@@ -1,5 +1,6 @@
 use std::marker::PhantomData;
-use noise::Noise;
+use grid::Grid2d;
+use noise::{Noise, Point2};
 use noise::TupleUtil;
 
 #[derive(Clone, Debug)]
@@ -50,6 +51,9 @@ where
     fn value_at(&self, _: Self::IndexType) -> f64 {
         self.value
     }
+    fn output_bounds(&self) -> (f64, f64) {
+        (self.value, self.value)
+    }
     fn frequency(&self) -> Self::DimType {
         Self::DimType::saturate(1.0)
     }
@@ -91,3 +95,75 @@ where
         Self::DimType::saturate(1.0)
     }
 }
+
+/// Bilinear interpolation of values sampled on a lattice spanning the rectangle from `min` to
+/// `max`, edges included, so precomputed or simulated maps can be used like any other noise.
+/// Positions outside the rectangle take the value at the nearest edge.
+#[derive(Clone, Debug)]
+pub struct GridNoise {
+    grid: Grid2d<f64>,
+    min: Point2<f64>,
+    max: Point2<f64>,
+    bounds: (f64, f64),
+}
+
+impl GridNoise {
+    pub fn new(grid: Grid2d<f64>, min: Point2<f64>, max: Point2<f64>) -> GridNoise {
+        assert!(grid.width() > 1 && grid.height() > 1, "A grid noise needs two points per axis");
+        let bounds = grid.data().iter().fold(
+            (f64::INFINITY, f64::NEG_INFINITY),
+            |(low, high), &v| (low.min(v), high.max(v)),
+        );
+        GridNoise {
+            grid,
+            min,
+            max,
+            bounds,
+        }
+    }
+
+    pub fn grid(&self) -> &Grid2d<f64> {
+        &self.grid
+    }
+    pub fn min(&self) -> Point2<f64> {
+        self.min
+    }
+    pub fn max(&self) -> Point2<f64> {
+        self.max
+    }
+    pub fn into_grid(self) -> Grid2d<f64> {
+        self.grid
+    }
+}
+
+impl Noise for GridNoise {
+    type IndexType = Point2<f64>;
+    type DimType = (f64, f64);
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        let cells = [self.grid.width() - 1, self.grid.height() - 1];
+        let mut lower = [0; 2];
+        let mut t = [0.0; 2];
+        for axis in 0..2 {
+            let extent = self.max[axis] - self.min[axis];
+            let cell = ((pos[axis] - self.min[axis]) / extent * f64::from(cells[axis]))
+                .clamp(0.0, f64::from(cells[axis]));
+            lower[axis] = (cell.floor() as usize).min(cells[axis] as usize - 1);
+            t[axis] = cell - lower[axis] as f64;
+        }
+        let [x, y] = lower;
+        let bottom = self.grid[(x, y)] + (self.grid[(x + 1, y)] - self.grid[(x, y)]) * t[0];
+        let top = self.grid[(x, y + 1)] + (self.grid[(x + 1, y + 1)] - self.grid[(x, y + 1)]) * t[0];
+        bottom + (top - bottom) * t[1]
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        self.bounds
+    }
+    /// Lattice cells per unit of noise space.
+    fn frequency(&self) -> Self::DimType {
+        (
+            f64::from(self.grid.width() - 1) / (self.max[0] - self.min[0]),
+            f64::from(self.grid.height() - 1) / (self.max[1] - self.min[1]),
+        )
+    }
+}
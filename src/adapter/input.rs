@@ -1,4 +1,4 @@
-use noise::{Noise, PointUtil};
+use noise::{DomainPoint, DomainTransform, Noise, PointUtil, SplitNoise, SplitPoint};
 
 #[derive(Clone, Debug)]
 pub struct ScaleInput<N>
@@ -34,6 +34,15 @@ where
     low: N::IndexType,
     high: N::IndexType,
 }
+#[derive(Clone, Debug)]
+pub struct RotateInput<N>
+where
+    N: Noise,
+    N::IndexType: DomainPoint,
+{
+    noise: N,
+    transform: DomainTransform<N::IndexType>,
+}
 
 impl<N> ScaleInput<N>
 where
@@ -48,6 +57,12 @@ where
     }
 }
 
+/// The largest magnitude among the components of `scale`, which stretches a footprint at most
+/// by that much along any axis.
+fn largest_scale<P: PointUtil<f64>>(scale: P) -> f64 {
+    scale.fold(0.0, |largest, s| largest.max(s.abs()))
+}
+
 impl<N> Noise for ScaleInput<N>
 where
     N: Noise,
@@ -60,12 +75,38 @@ where
         let scaled_pos = pos.apply(self.scale.clone(), |x, y| x * y);
         self.noise.value_at(scaled_pos)
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        let scaled_pos = pos.apply(self.scale.clone(), |x, y| x * y);
+        self.noise
+            .value_at_lod(scaled_pos, footprint * largest_scale(self.scale.clone()))
+    }
 
+    fn output_bounds(&self) -> (f64, f64) {
+        self.noise.output_bounds()
+    }
     fn frequency(&self) -> N::DimType {
         self.noise.frequency()
     }
 }
 
+impl<N> SplitNoise for ScaleInput<N>
+where
+    N: SplitNoise,
+    N::IndexType: PointUtil<f64>,
+{
+    type SplitIndex = N::SplitIndex;
+
+    fn value_at_split(&self, pos: Self::SplitIndex) -> f64 {
+        self.noise.value_at_split(pos.scaled(self.scale.clone()))
+    }
+    fn value_at_split_lod(&self, pos: Self::SplitIndex, footprint: f64) -> f64 {
+        self.noise.value_at_split_lod(
+            pos.scaled(self.scale.clone()),
+            footprint * largest_scale(self.scale.clone()),
+        )
+    }
+}
+
 impl<N> ShiftInput<N>
 where
     N: Noise,
@@ -91,12 +132,35 @@ where
         let shifted_pos = pos.apply(self.shift.clone(), |x, y| x + y);
         self.noise.value_at(shifted_pos)
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        let shifted_pos = pos.apply(self.shift.clone(), |x, y| x + y);
+        self.noise.value_at_lod(shifted_pos, footprint)
+    }
 
+    fn output_bounds(&self) -> (f64, f64) {
+        self.noise.output_bounds()
+    }
     fn frequency(&self) -> N::DimType {
         self.noise.frequency()
     }
 }
 
+impl<N> SplitNoise for ShiftInput<N>
+where
+    N: SplitNoise,
+    N::IndexType: PointUtil<f64>,
+{
+    type SplitIndex = N::SplitIndex;
+
+    fn value_at_split(&self, pos: Self::SplitIndex) -> f64 {
+        self.noise.value_at_split(pos.shifted(self.shift.clone()))
+    }
+    fn value_at_split_lod(&self, pos: Self::SplitIndex, footprint: f64) -> f64 {
+        self.noise
+            .value_at_split_lod(pos.shifted(self.shift.clone()), footprint)
+    }
+}
+
 impl<N> ClampInput<N>
 where
     N: Noise,
@@ -113,6 +177,24 @@ where
     pub fn inner_noise(&self) -> &N {
         &self.noise
     }
+
+    fn clamp(&self, pos: N::IndexType) -> N::IndexType {
+        pos.apply(self.low.clone(), |x, y| if x < y { y } else { x })
+            .apply(self.high.clone(), |x, y| if x > y { y } else { x })
+    }
+
+    fn clamp_split<S>(&self, pos: S) -> S
+    where
+        S: SplitPoint<Point = N::IndexType>,
+    {
+        //Axes within the range keep their exact split coordinate, scaled by one, and clamped
+        //axes are zeroed and shifted onto their bound.
+        let approx = pos.to_point();
+        let clamped = self.clamp(approx.clone());
+        let inside = approx.apply(clamped.clone(), |x, c| if x == c { 1.0 } else { 0.0 });
+        let bound = clamped.apply(inside.clone(), |c, i| c * (1.0 - i));
+        pos.scaled(inside).shifted(bound)
+    }
 }
 
 impl<N> Noise for ClampInput<N>
@@ -124,16 +206,36 @@ where
     type DimType = N::DimType;
 
     fn value_at(&self, pos: Self::IndexType) -> f64 {
-        let clamped_pos = pos.apply(self.low.clone(), |x, y| if x < y { y } else { x })
-            .apply(self.high.clone(), |x, y| if x > y { y } else { x });
-        self.noise.value_at(clamped_pos)
+        self.noise.value_at(self.clamp(pos))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod(self.clamp(pos), footprint)
     }
 
+    fn output_bounds(&self) -> (f64, f64) {
+        self.noise.output_bounds()
+    }
     fn frequency(&self) -> N::DimType {
         self.noise.frequency()
     }
 }
 
+impl<N> SplitNoise for ClampInput<N>
+where
+    N: SplitNoise,
+    N::IndexType: PointUtil<f64>,
+{
+    type SplitIndex = N::SplitIndex;
+
+    fn value_at_split(&self, pos: Self::SplitIndex) -> f64 {
+        self.noise.value_at_split(self.clamp_split(pos))
+    }
+    fn value_at_split_lod(&self, pos: Self::SplitIndex, footprint: f64) -> f64 {
+        self.noise
+            .value_at_split_lod(self.clamp_split(pos), footprint)
+    }
+}
+
 impl<N> WrapInput<N>
 where
     N: Noise,
@@ -150,6 +252,28 @@ where
     pub fn inner_noise(&self) -> &N {
         &self.noise
     }
+
+    fn wrap(&self, pos: N::IndexType) -> N::IndexType {
+        pos.apply_3(self.low.clone(), self.high.clone(), |x, low, high| {
+            let range = high - low;
+            let scaled = x - low;
+            let mut wrapped = scaled % range;
+            if wrapped < 0.0 {
+                wrapped += range;
+            }
+            wrapped + low
+        })
+    }
+
+    fn wrap_split<S>(&self, pos: S) -> S
+    where
+        S: SplitPoint<Point = N::IndexType>,
+    {
+        //The remainder of the cells is exact, and leaves a small position to wrap as usual.
+        let range = self.high.clone().apply(self.low.clone(), |h, l| h - l);
+        let cells = pos.cells().apply(range, |c, r| c % r);
+        S::from_point(self.wrap(cells.apply(pos.offsets(), |c, o| c + o)))
+    }
 }
 
 impl<N> Noise for WrapInput<N>
@@ -161,19 +285,170 @@ where
     type DimType = N::DimType;
 
     fn value_at(&self, pos: Self::IndexType) -> f64 {
-        let wrapped_pos = pos.apply_3(self.low.clone(), self.high.clone(), |x, low, high| {
-            let range = high - low;
-            let scaled = x - low;
-            let mut wrapped = scaled % range;
-            if wrapped < 0.0 {
-                wrapped += range;
-            }
-            wrapped + low
-        });
-        self.noise.value_at(wrapped_pos)
+        self.noise.value_at(self.wrap(pos))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod(self.wrap(pos), footprint)
     }
 
+    fn output_bounds(&self) -> (f64, f64) {
+        self.noise.output_bounds()
+    }
     fn frequency(&self) -> N::DimType {
         self.noise.frequency()
     }
 }
+
+impl<N> SplitNoise for WrapInput<N>
+where
+    N: SplitNoise,
+    N::IndexType: PointUtil<f64>,
+{
+    type SplitIndex = N::SplitIndex;
+
+    fn value_at_split(&self, pos: Self::SplitIndex) -> f64 {
+        self.noise.value_at_split(self.wrap_split(pos))
+    }
+    fn value_at_split_lod(&self, pos: Self::SplitIndex, footprint: f64) -> f64 {
+        self.noise
+            .value_at_split_lod(self.wrap_split(pos), footprint)
+    }
+}
+
+impl<N> RotateInput<N>
+where
+    N: Noise,
+    N::IndexType: DomainPoint,
+{
+    pub fn new(noise: N, transform: DomainTransform<N::IndexType>) -> RotateInput<N> {
+        RotateInput { noise, transform }
+    }
+
+    pub fn inner_noise(&self) -> &N {
+        &self.noise
+    }
+    pub fn domain_transform(&self) -> &DomainTransform<N::IndexType> {
+        &self.transform
+    }
+}
+
+impl<N> Noise for RotateInput<N>
+where
+    N: Noise,
+    N::IndexType: DomainPoint,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.noise.value_at(self.transform.apply(pos))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod(self.transform.apply(pos), footprint)
+    }
+
+    fn output_bounds(&self) -> (f64, f64) {
+        self.noise.output_bounds()
+    }
+    fn frequency(&self) -> N::DimType {
+        self.noise.frequency()
+    }
+}
+
+impl<N> SplitNoise for RotateInput<N>
+where
+    N: SplitNoise,
+    N::IndexType: DomainPoint,
+{
+    type SplitIndex = N::SplitIndex;
+
+    fn value_at_split(&self, pos: Self::SplitIndex) -> f64 {
+        self.noise.value_at_split(self.transform.apply_split(pos))
+    }
+    fn value_at_split_lod(&self, pos: Self::SplitIndex, footprint: f64) -> f64 {
+        self.noise
+            .value_at_split_lod(self.transform.apply_split(pos), footprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RotateInput;
+    use adapter::NoiseExt;
+    use noise::fbm::Fbm2d;
+    use noise::{seeded_rng, Noise, OctaveRotation, Point2, SplitCoord, SplitNoise, SplitPoint};
+
+    #[test]
+    fn footprints_are_forwarded_and_scaled() {
+        let fbm = Fbm2d::new(seeded_rng(4))
+            .with_frequency((4.0, 4.0))
+            .with_num_octaves(6);
+        let pos = [0.3, 0.7];
+        let footprint = 0.02;
+        assert!((fbm.value_at_lod(pos, footprint) - fbm.value_at(pos)).abs() > 1e-6);
+
+        //Input space is stretched by the scale, and so is the footprint within the source.
+        let scaled = (&fbm).scale_input([2.0, -0.5]);
+        assert_eq!(
+            scaled.value_at_lod(pos, footprint),
+            fbm.value_at_lod([0.6, -0.35], footprint * 2.0)
+        );
+        let split = SplitPoint::from_point(pos);
+        let expected = fbm.value_at_lod([0.6, -0.35], footprint * 2.0);
+        assert!((scaled.value_at_split_lod(split, footprint) - expected).abs() < 1e-12);
+
+        let transform = OctaveRotation::<Point2<f64>>::Seeded(3).octave_transform(1);
+        let rotated = RotateInput::new(&fbm, transform.clone());
+        let expected = fbm.value_at_lod(transform.apply(pos), footprint);
+        assert!((rotated.value_at_split_lod(split, footprint) - expected).abs() < 1e-12);
+        assert!((rotated.value_at_split(split) - fbm.value_at(transform.apply(pos))).abs() < 1e-12);
+
+        let chain = (&fbm)
+            .negate()
+            .clamp(-0.3, 0.3)
+            .terrace(vec![-1.0, 0.0, 1.0])
+            .multiply((&fbm).abs())
+            .select(&fbm, (&fbm).wrap_input([0.0, 0.0], [1.0, 1.0]), 0.0);
+        let expected = {
+            let v = fbm.value_at_lod(pos, footprint);
+            let clamped = (-v).clamp(-0.3, 0.3);
+            let terraced = if clamped < 0.0 {
+                (clamped + 1.0).powi(2) - 1.0
+            } else {
+                clamped * clamped
+            };
+            if v > 0.0 {
+                terraced * v.abs()
+            } else {
+                v
+            }
+        };
+        assert!((chain.value_at_lod(pos, footprint) - expected).abs() < 1e-12);
+        assert_eq!(chain.value_at_lod(pos, 0.0), chain.value_at(pos));
+    }
+
+    #[test]
+    fn clamp_and_wrap_stay_exact_at_split_coordinates() {
+        let fbm = Fbm2d::new(seeded_rng(5)).with_frequency((4.0, 4.0));
+        let near = [0.3, 0.7];
+        let far = [
+            SplitCoord::new(3_000_000_000_000, 0.3),
+            SplitCoord::new(-3_000_000_000_000, 0.7),
+        ];
+
+        //Three trillion is a whole number of periods of 1.5, so the far point wraps onto `near`.
+        let wrapped = (&fbm).wrap_input([0.0, 0.0], [1.5, 1.5]);
+        assert!((wrapped.value_at_split(far) - fbm.value_at(near)).abs() < 1e-12);
+        let split = SplitPoint::from_point([1.8, -0.8]);
+        assert!((wrapped.value_at_split(split) - wrapped.value_at([1.8, -0.8])).abs() < 1e-12);
+
+        let clamped = (&fbm).clamp_input([0.0, 0.0], [1.0, 1.0]);
+        assert!((clamped.value_at_split(far) - fbm.value_at([1.0, 0.0])).abs() < 1e-12);
+        let inside = [SplitCoord::new(0, 0.3), SplitCoord::new(0, 0.7)];
+        assert_eq!(clamped.value_at_split(inside), fbm.value_at_split(inside));
+        assert_eq!(
+            clamped.value_at_split_lod(inside, 0.01),
+            fbm.value_at_split_lod(inside, 0.01)
+        );
+    }
+}
@@ -4,25 +4,38 @@ pub mod extend;
 pub mod filter;
 pub mod generate;
 pub mod input;
+#[macro_use]
+mod ops;
+pub mod remap;
 pub mod scale;
 pub mod slice;
 pub mod transform;
 
-pub use self::combine::{Add, Blend, Combine, Multiply, Select};
+pub use self::combine::{Add, Blend, Combine, Divide, Max, Min, Multiply, MultiSelect, Power,
+                        Product, Select, Subtract, Sum};
 pub use self::extend::{Extension2d, Extension3d};
 pub use self::filter::{Clamp, Filter, FilterKind};
-pub use self::generate::{Constant, FunctionValue};
-pub use self::input::{ClampInput, ScaleInput, ShiftInput, WrapInput};
-pub use self::scale::{Scale, WithRange};
+pub use self::generate::{Constant, FunctionValue, GridNoise};
+pub use self::input::{ClampInput, RotateInput, ScaleInput, ShiftInput, WrapInput};
+pub use self::remap::{Curve, CurveKind, Terrace};
+pub use self::scale::{InvertRange, Normalize, Scale, ScaleBias, WithRange};
 pub use self::slice::{Slice1d, Slice2d};
-pub use self::transform::{Negate, Transform};
+pub use self::transform::{Abs, Exponent, Negate, Transform};
 
-use super::noise::{Noise, PointUtil, TupleUtil};
+use super::noise::{DomainPoint, DomainTransform, DynNoise, Noise, PointUtil, TupleUtil};
 
 pub trait NoiseExt: Noise + Sized
 where
     Self::IndexType: PointUtil<f64>,
 {
+    /// Erases the type of this noise so it can be chosen and stored at runtime.
+    fn into_dyn(self) -> DynNoise<Self::IndexType, Self::DimType>
+    where
+        Self: Send + Sync + 'static,
+    {
+        DynNoise::new(self)
+    }
+
     fn scale(self, amplitude: f64) -> Scale<Self> {
         Scale::new(self, amplitude)
     }
@@ -56,6 +69,14 @@ where
         Select::new(self, right_noise, criteria, threshold)
     }
 
+    /// Uses `self` as the criteria to choose between `sources`, see `MultiSelect`.
+    fn multi_select<N2>(self, sources: Vec<N2>, boundaries: Vec<f64>) -> MultiSelect<N2, Self>
+    where
+        N2: Noise<IndexType = Self::IndexType, DimType = Self::DimType>,
+    {
+        MultiSelect::new(self, sources, boundaries)
+    }
+
     fn blend<N2, N3, F>(self, right_noise: N2, criteria: N3, blend_fn: F) -> Blend<Self, N2, N3, F>
     where
         Self::DimType: TupleUtil<f64>,
@@ -82,10 +103,70 @@ where
         Multiply::new(self, right_noise)
     }
 
+    fn subtract<N2>(self, right_noise: N2) -> Subtract<Self, N2>
+    where
+        Self::DimType: TupleUtil<f64>,
+        N2: Noise<IndexType = Self::IndexType, DimType = Self::DimType>,
+    {
+        Subtract::new(self, right_noise)
+    }
+
+    fn divide<N2>(self, right_noise: N2) -> Divide<Self, N2>
+    where
+        Self::DimType: TupleUtil<f64>,
+        N2: Noise<IndexType = Self::IndexType, DimType = Self::DimType>,
+    {
+        Divide::new(self, right_noise)
+    }
+
+    fn min_with<N2>(self, right_noise: N2) -> Min<Self, N2>
+    where
+        Self::DimType: TupleUtil<f64>,
+        N2: Noise<IndexType = Self::IndexType, DimType = Self::DimType>,
+    {
+        Min::new(self, right_noise)
+    }
+
+    fn max_with<N2>(self, right_noise: N2) -> Max<Self, N2>
+    where
+        Self::DimType: TupleUtil<f64>,
+        N2: Noise<IndexType = Self::IndexType, DimType = Self::DimType>,
+    {
+        Max::new(self, right_noise)
+    }
+
+    fn power<N2>(self, right_noise: N2) -> Power<Self, N2>
+    where
+        Self::DimType: TupleUtil<f64>,
+        N2: Noise<IndexType = Self::IndexType, DimType = Self::DimType>,
+    {
+        Power::new(self, right_noise)
+    }
+
     fn negate(self) -> Negate<Self> {
         Negate::new(self)
     }
 
+    fn abs(self) -> Abs<Self> {
+        Abs::new(self)
+    }
+
+    fn exponent(self, exponent: f64) -> Exponent<Self> {
+        Exponent::new(self, exponent)
+    }
+
+    fn scale_bias(self, scale: f64, bias: f64) -> ScaleBias<Self> {
+        ScaleBias::new(self, scale, bias)
+    }
+
+    fn invert_range(self, min: f64, max: f64) -> InvertRange<Self> {
+        InvertRange::new(self, min, max)
+    }
+
+    fn normalize(self, low: f64, high: f64) -> Normalize<Self> {
+        Normalize::new(self, low, high)
+    }
+
     fn clamp(self, low: f64, high: f64) -> Clamp<Self> {
         Clamp::new(self, low, high)
     }
@@ -97,6 +178,14 @@ where
         Filter::new(self, start, end, kind, blend_fn)
     }
 
+    fn terrace(self, points: Vec<f64>) -> Terrace<Self> {
+        Terrace::new(self, points)
+    }
+
+    fn curve(self, points: Vec<(f64, f64)>, kind: CurveKind) -> Curve<Self> {
+        Curve::new(self, points, kind)
+    }
+
     fn scale_input(self, scale: Self::IndexType) -> ScaleInput<Self> {
         ScaleInput::new(self, scale)
     }
@@ -109,6 +198,12 @@ where
     fn wrap_input(self, low: Self::IndexType, high: Self::IndexType) -> WrapInput<Self> {
         WrapInput::new(self, low, high)
     }
+    fn rotate_input(self, transform: DomainTransform<Self::IndexType>) -> RotateInput<Self>
+    where
+        Self::IndexType: DomainPoint,
+    {
+        RotateInput::new(self, transform)
+    }
 }
 
 impl<N> NoiseExt for N
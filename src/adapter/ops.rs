@@ -0,0 +1,204 @@
+//! `std::ops` implementations so noise graphs can be written as arithmetic expressions,
+//! e.g. `a * 0.5 + b`.
+
+use rand::Rng;
+
+use adapter::*;
+use gradient::GradientProvider;
+use interpolate::InterpolationFunction;
+use noise::fbm::{Fbm1d, Fbm2d, Fbm3d};
+use noise::{DomainPoint, DynNoise, Noise, Noise1d, Noise2d, Noise3d, Octave, OctaveNoise, Perlin1d,
+            Perlin2d, Perlin3d, Point1, Point2, Point3, TupleUtil, Worley2d};
+
+/// Implements `std::ops` arithmetic for a noise type, building the matching adapters. Used by
+/// the noise types of other modules too, so it only refers to items by absolute paths.
+#[macro_export]
+macro_rules! impl_noise_ops {
+    ($name:ident<$($gen:ident),*> where $($bound:tt)*) => {
+        impl_noise_ops!(@impl $name [$($gen),*] [$($bound)*,]);
+    };
+    //Types without generic parameters.
+    ($name:ident) => {
+        impl_noise_ops!(@impl $name [] []);
+    };
+    (@impl $name:ident [$($gen:ident),*] [$($bound:tt)*]) => {
+        impl<$($gen,)* Rhs> ::std::ops::Add<Rhs> for $name<$($gen),*>
+        where
+            $($bound)*
+            $name<$($gen),*>: $crate::noise::Noise,
+            <$name<$($gen),*> as $crate::noise::Noise>::DimType: $crate::noise::TupleUtil<f64>,
+            Rhs: $crate::noise::Noise<
+                IndexType = <$name<$($gen),*> as $crate::noise::Noise>::IndexType,
+                DimType = <$name<$($gen),*> as $crate::noise::Noise>::DimType,
+            >,
+        {
+            type Output = $crate::adapter::Add<Self, Rhs>;
+
+            fn add(self, rhs: Rhs) -> Self::Output {
+                $crate::adapter::Add::new(self, rhs)
+            }
+        }
+
+        impl<$($gen,)* Rhs> ::std::ops::Sub<Rhs> for $name<$($gen),*>
+        where
+            $($bound)*
+            $name<$($gen),*>: $crate::noise::Noise,
+            <$name<$($gen),*> as $crate::noise::Noise>::DimType: $crate::noise::TupleUtil<f64>,
+            Rhs: $crate::noise::Noise<
+                IndexType = <$name<$($gen),*> as $crate::noise::Noise>::IndexType,
+                DimType = <$name<$($gen),*> as $crate::noise::Noise>::DimType,
+            >,
+        {
+            type Output = $crate::adapter::Subtract<Self, Rhs>;
+
+            fn sub(self, rhs: Rhs) -> Self::Output {
+                $crate::adapter::Subtract::new(self, rhs)
+            }
+        }
+
+        impl<$($gen,)* Rhs> ::std::ops::Mul<Rhs> for $name<$($gen),*>
+        where
+            $($bound)*
+            $name<$($gen),*>: $crate::noise::Noise,
+            <$name<$($gen),*> as $crate::noise::Noise>::DimType: $crate::noise::TupleUtil<f64>,
+            Rhs: $crate::noise::Noise<
+                IndexType = <$name<$($gen),*> as $crate::noise::Noise>::IndexType,
+                DimType = <$name<$($gen),*> as $crate::noise::Noise>::DimType,
+            >,
+        {
+            type Output = $crate::adapter::Multiply<Self, Rhs>;
+
+            fn mul(self, rhs: Rhs) -> Self::Output {
+                $crate::adapter::Multiply::new(self, rhs)
+            }
+        }
+
+        impl<$($gen),*> ::std::ops::Add<f64> for $name<$($gen),*>
+        where
+            $($bound)*
+            $name<$($gen),*>: $crate::noise::Noise,
+        {
+            type Output = $crate::adapter::ScaleBias<Self>;
+
+            fn add(self, rhs: f64) -> Self::Output {
+                $crate::adapter::ScaleBias::new(self, 1.0, rhs)
+            }
+        }
+
+        impl<$($gen),*> ::std::ops::Sub<f64> for $name<$($gen),*>
+        where
+            $($bound)*
+            $name<$($gen),*>: $crate::noise::Noise,
+        {
+            type Output = $crate::adapter::ScaleBias<Self>;
+
+            fn sub(self, rhs: f64) -> Self::Output {
+                $crate::adapter::ScaleBias::new(self, 1.0, -rhs)
+            }
+        }
+
+        impl<$($gen),*> ::std::ops::Mul<f64> for $name<$($gen),*>
+        where
+            $($bound)*
+            $name<$($gen),*>: $crate::noise::Noise,
+        {
+            type Output = $crate::adapter::Scale<Self>;
+
+            fn mul(self, rhs: f64) -> Self::Output {
+                $crate::adapter::Scale::new(self, rhs)
+            }
+        }
+
+        impl<$($gen),*> ::std::ops::Neg for $name<$($gen),*>
+        where
+            $($bound)*
+            $name<$($gen),*>: $crate::noise::Noise,
+        {
+            type Output = $crate::adapter::Negate<Self>;
+
+            fn neg(self) -> Self::Output {
+                $crate::adapter::Negate::new(self)
+            }
+        }
+    };
+}
+
+impl_noise_ops!(Perlin1d<G, P> where G: GradientProvider<Point1<u32>>, P: InterpolationFunction);
+impl_noise_ops!(Perlin2d<G, P> where G: GradientProvider<Point2<u32>>, P: InterpolationFunction);
+impl_noise_ops!(Perlin3d<G, P> where G: GradientProvider<Point3<u32>>, P: InterpolationFunction);
+impl_noise_ops!(Fbm1d<P, R> where P: InterpolationFunction + Clone, R: Rng + Clone);
+impl_noise_ops!(Fbm2d<P, R> where P: InterpolationFunction + Clone, R: Rng + Clone);
+impl_noise_ops!(Fbm3d<P, R> where P: InterpolationFunction + Clone, R: Rng + Clone);
+impl_noise_ops!(Worley2d);
+impl_noise_ops!(Octave<T> where T: Noise);
+impl_noise_ops!(DynNoise<I, D> where I: Clone);
+impl_noise_ops!(OctaveNoise<T> where T: Noise);
+
+impl_noise_ops!(Add<N1, N2> where N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>);
+impl_noise_ops!(Subtract<N1, N2> where N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>);
+impl_noise_ops!(Multiply<N1, N2> where N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>);
+impl_noise_ops!(Divide<N1, N2> where N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>);
+impl_noise_ops!(Min<N1, N2> where N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>);
+impl_noise_ops!(Max<N1, N2> where N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>);
+impl_noise_ops!(Power<N1, N2> where N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>);
+impl_noise_ops!(Sum<N> where N: Noise);
+impl_noise_ops!(Product<N> where N: Noise);
+impl_noise_ops!(Combine<N1, N2, F> where N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>, F: Fn(f64, f64) -> f64);
+impl_noise_ops!(Select<N1, N2, N3> where N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+    N3: Noise<IndexType = N1::IndexType, DimType = N1::DimType>);
+impl_noise_ops!(MultiSelect<N, C> where N: Noise,
+    C: Noise<IndexType = N::IndexType, DimType = N::DimType>);
+impl_noise_ops!(Blend<N1, N2, N3, F> where N1: Noise,
+    N2: Noise<IndexType = N1::IndexType, DimType = N1::DimType>,
+    N3: Noise<IndexType = N1::IndexType, DimType = N1::DimType>, F: Fn(f64, f64, f64) -> f64);
+
+impl_noise_ops!(Extension2d<N> where N: Noise1d);
+impl_noise_ops!(Extension3d<N> where N: Noise2d);
+impl_noise_ops!(Slice1d<N> where N: Noise2d);
+impl_noise_ops!(Slice2d<N> where N: Noise3d);
+impl_noise_ops!(Clamp<N> where N: Noise);
+impl_noise_ops!(Filter<N, F> where N: Noise, F: Fn(f64, f64, f64) -> f64);
+impl_noise_ops!(Constant<I, D> where I: Clone);
+impl_noise_ops!(FunctionValue<I, D, F> where I: Clone, D: Default + TupleUtil<f64>, F: Fn(&I) -> f64);
+impl_noise_ops!(GridNoise);
+impl_noise_ops!(ScaleInput<N> where N: Noise);
+impl_noise_ops!(ShiftInput<N> where N: Noise);
+impl_noise_ops!(ClampInput<N> where N: Noise);
+impl_noise_ops!(WrapInput<N> where N: Noise);
+impl_noise_ops!(RotateInput<N> where N: Noise, N::IndexType: DomainPoint);
+impl_noise_ops!(Terrace<N> where N: Noise);
+impl_noise_ops!(Curve<N> where N: Noise);
+impl_noise_ops!(Scale<N> where N: Noise);
+impl_noise_ops!(ScaleBias<N> where N: Noise);
+impl_noise_ops!(WithRange<N> where N: Noise);
+impl_noise_ops!(InvertRange<N> where N: Noise);
+impl_noise_ops!(Normalize<N> where N: Noise);
+impl_noise_ops!(Transform<N, F> where N: Noise, F: Fn(&N::IndexType, f64) -> f64);
+impl_noise_ops!(Negate<N> where N: Noise);
+impl_noise_ops!(Abs<N> where N: Noise);
+impl_noise_ops!(Exponent<N> where N: Noise);
+
+#[cfg(test)]
+mod tests {
+    use adapter::Constant;
+    use noise::Noise;
+
+    #[test]
+    fn arithmetic_builds_graph() {
+        let a: Constant<f64, f64> = Constant::new(2.0);
+        let b: Constant<f64, f64> = Constant::new(3.0);
+        let c: Constant<f64, f64> = Constant::new(1.0);
+
+        let graph = -(a * 0.5 + b - c) * 2.0 + 1.0;
+        assert_eq!(graph.value_at(0.0), -5.0);
+    }
+}
@@ -0,0 +1,342 @@
+use std::cmp::Ordering;
+
+use interpolate::Lerp;
+use noise::{bounds, Noise};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CurveKind {
+    /// Fritsch-Carlson monotone cubic. Never overshoots between control points.
+    MonotoneCubic,
+    /// Catmull-Rom spline. Smoother, but may overshoot non-monotone data.
+    CatmullRom,
+}
+
+#[derive(Debug, Clone)]
+pub struct Terrace<N>
+where
+    N: Noise,
+{
+    noise: N,
+    points: Vec<f64>,
+    inverted: bool,
+    smoothness: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Curve<N>
+where
+    N: Noise,
+{
+    noise: N,
+    points: Vec<(f64, f64)>,
+    tangents: Vec<f64>,
+    kind: CurveKind,
+}
+
+fn compare_f64(x: &f64, y: &f64) -> Ordering {
+    x.partial_cmp(y).expect("Control points must not be NaN.")
+}
+
+/// Index `i` of the segment `points[i]..points[i + 1]` containing `x`.
+/// `x` must lie between the first and last points.
+fn find_segment<T, F>(points: &[T], x: f64, key: F) -> usize
+where
+    F: Fn(&T) -> f64,
+{
+    let idx = match points.binary_search_by(|p| compare_f64(&key(p), &x)) {
+        Ok(idx) | Err(idx) => idx,
+    };
+    idx.max(1).min(points.len() - 1) - 1
+}
+
+impl<N> Terrace<N>
+where
+    N: Noise,
+{
+    pub fn new(noise: N, mut points: Vec<f64>) -> Terrace<N> {
+        assert!(points.len() >= 2, "A terrace requires at least two control points.");
+        points.sort_by(compare_f64);
+        assert!(
+            points.windows(2).all(|w| w[0] < w[1]),
+            "Terrace control points must be unique."
+        );
+        Terrace {
+            noise,
+            points,
+            inverted: false,
+            smoothness: 0.0,
+        }
+    }
+
+    /// Inverts the curve of each step, so plateaus form at the top of a step instead of the
+    /// bottom.
+    pub fn with_inversion(self, inverted: bool) -> Terrace<N> {
+        Terrace { inverted, ..self }
+    }
+
+    /// Blends the steps towards the unmodified input. `0.0` gives sharp terraces and `1.0`
+    /// leaves the input unchanged.
+    pub fn with_smoothness(self, smoothness: f64) -> Terrace<N> {
+        assert!((0.0..=1.0).contains(&smoothness));
+        Terrace { smoothness, ..self }
+    }
+
+    pub fn inner_noise(&self) -> &N {
+        &self.noise
+    }
+    pub fn control_points(&self) -> &[f64] {
+        &self.points
+    }
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+    pub fn smoothness(&self) -> f64 {
+        self.smoothness
+    }
+
+    fn apply(&self, val: f64) -> f64 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if val <= first {
+            return first;
+        } else if val >= last {
+            return last;
+        }
+
+        let i = find_segment(&self.points, val, |&p| p);
+        let (mut low, mut high) = (self.points[i], self.points[i + 1]);
+        let mut t = (val - low) / (high - low);
+        if self.inverted {
+            t = 1.0 - t;
+            ::std::mem::swap(&mut low, &mut high);
+        }
+
+        let stepped = Lerp::lerp(t * t, t, self.smoothness);
+        Lerp::lerp(low, high, stepped)
+    }
+}
+
+impl<N> Noise for Terrace<N>
+where
+    N: Noise,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.apply(self.noise.value_at(pos))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.apply(self.noise.value_at_lod(pos, footprint))
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        //Every segment maps onto itself and the mapping is non-decreasing, so the bounds are
+        //the points enclosing the input bounds.
+        let (low, high) = self.noise.output_bounds();
+        let points = &self.points;
+        (
+            points.iter().cloned().rev().find(|&p| p <= low).unwrap_or(points[0]),
+            points.iter().cloned().find(|&p| p >= high).unwrap_or(points[points.len() - 1]),
+        )
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.noise.frequency()
+    }
+}
+
+impl<N> Curve<N>
+where
+    N: Noise,
+{
+    pub fn new(noise: N, mut points: Vec<(f64, f64)>, kind: CurveKind) -> Curve<N> {
+        assert!(points.len() >= 2, "A curve requires at least two control points.");
+        points.sort_by(|a, b| compare_f64(&a.0, &b.0));
+        assert!(
+            points.windows(2).all(|w| w[0].0 < w[1].0),
+            "Curve control points must have unique inputs."
+        );
+
+        let tangents = match kind {
+            CurveKind::MonotoneCubic => Self::monotone_tangents(&points),
+            CurveKind::CatmullRom => Self::catmull_rom_tangents(&points),
+        };
+        Curve {
+            noise,
+            points,
+            tangents,
+            kind,
+        }
+    }
+
+    pub fn inner_noise(&self) -> &N {
+        &self.noise
+    }
+    pub fn control_points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+    pub fn kind(&self) -> CurveKind {
+        self.kind
+    }
+
+    fn secants(points: &[(f64, f64)]) -> Vec<f64> {
+        points
+            .windows(2)
+            .map(|w| (w[1].1 - w[0].1) / (w[1].0 - w[0].0))
+            .collect()
+    }
+
+    fn catmull_rom_tangents(points: &[(f64, f64)]) -> Vec<f64> {
+        let n = points.len();
+        (0..n)
+            .map(|i| {
+                let prev = points[i.max(1) - 1];
+                let next = points[(i + 1).min(n - 1)];
+                (next.1 - prev.1) / (next.0 - prev.0)
+            })
+            .collect()
+    }
+
+    fn monotone_tangents(points: &[(f64, f64)]) -> Vec<f64> {
+        let secants = Self::secants(points);
+        let n = points.len();
+        let mut tangents: Vec<f64> = (0..n)
+            .map(|i| {
+                if i == 0 {
+                    secants[0]
+                } else if i == n - 1 {
+                    secants[n - 2]
+                } else if secants[i - 1] * secants[i] <= 0.0 {
+                    0.0
+                } else {
+                    (secants[i - 1] + secants[i]) / 2.0
+                }
+            })
+            .collect();
+
+        for (i, &secant) in secants.iter().enumerate() {
+            if secant == 0.0 {
+                tangents[i] = 0.0;
+                tangents[i + 1] = 0.0;
+                continue;
+            }
+            let alpha = tangents[i] / secant;
+            let beta = tangents[i + 1] / secant;
+            let magnitude = alpha * alpha + beta * beta;
+            if magnitude > 9.0 {
+                let tau = 3.0 / magnitude.sqrt();
+                tangents[i] = tau * alpha * secant;
+                tangents[i + 1] = tau * beta * secant;
+            }
+        }
+        tangents
+    }
+
+    fn apply(&self, val: f64) -> f64 {
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if val <= first.0 {
+            return first.1;
+        } else if val >= last.0 {
+            return last.1;
+        }
+
+        let i = find_segment(&self.points, val, |p| p.0);
+        let ((x0, y0), (x1, y1)) = (self.points[i], self.points[i + 1]);
+        let h = x1 - x0;
+        let t = (val - x0) / h;
+        let (t2, t3) = (t * t, t * t * t);
+
+        let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+        let h10 = t3 - 2.0 * t2 + t;
+        let h01 = -2.0 * t3 + 3.0 * t2;
+        let h11 = t3 - t2;
+
+        h00 * y0 + h10 * h * self.tangents[i] + h01 * y1 + h11 * h * self.tangents[i + 1]
+    }
+}
+
+impl<N> Noise for Curve<N>
+where
+    N: Noise,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.apply(self.noise.value_at(pos))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.apply(self.noise.value_at_lod(pos, footprint))
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        let (low, high) = self.noise.output_bounds();
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        let mut result = (f64::INFINITY, f64::NEG_INFINITY);
+        if low <= first.0 {
+            result = bounds::hull(result, (first.1, first.1));
+        }
+        if high >= last.0 {
+            result = bounds::hull(result, (last.1, last.1));
+        }
+        for (i, w) in self.points.windows(2).enumerate() {
+            let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+            if x1 < low || x0 > high {
+                continue;
+            }
+            //Monotone segments stay between their end points; otherwise the tangent terms
+            //`h10` and `h11` are bounded by 4/27 in magnitude.
+            let overshoot = match self.kind {
+                CurveKind::MonotoneCubic => 0.0,
+                CurveKind::CatmullRom => {
+                    4.0 / 27.0 * (x1 - x0) * (self.tangents[i].abs() + self.tangents[i + 1].abs())
+                }
+            };
+            result = bounds::hull(result, (y0.min(y1) - overshoot, y0.max(y1) + overshoot));
+        }
+        result
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.noise.frequency()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Curve, CurveKind, Terrace};
+    use adapter::Constant;
+    use noise::Noise;
+
+    fn constant(value: f64) -> Constant<f64, f64> {
+        Constant::new(value)
+    }
+
+    #[test]
+    fn terrace_steps() {
+        let points = vec![1.0, -1.0, 0.0];
+        let at = |v| Terrace::new(constant(v), points.clone()).value_at(0.0);
+        assert_eq!(at(-2.0), -1.0);
+        assert_eq!(at(0.0), 0.0);
+        assert_eq!(at(0.5), 0.25);
+        assert_eq!(at(2.0), 1.0);
+
+        let inverted = Terrace::new(constant(0.5), points.clone()).with_inversion(true);
+        assert_eq!(inverted.value_at(0.0), 0.75);
+        let smooth = Terrace::new(constant(0.5), points).with_smoothness(1.0);
+        assert_eq!(smooth.value_at(0.0), 0.5);
+    }
+
+    #[test]
+    fn monotone_curve_does_not_overshoot() {
+        let points = vec![(-1.0, -1.0), (0.0, 0.9), (0.1, 1.0), (1.0, 1.0)];
+        for i in 0..=200 {
+            let x = -1.0 + f64::from(i) * 0.01;
+            let monotone = Curve::new(constant(x), points.clone(), CurveKind::MonotoneCubic);
+            let val = monotone.value_at(0.0);
+            assert!((-1.0..=1.0).contains(&val), "{} -> {}", x, val);
+        }
+
+        let rom = Curve::new(constant(0.0), points, CurveKind::CatmullRom);
+        assert!((rom.value_at(0.0) - 0.9).abs() < 1e-12);
+    }
+}
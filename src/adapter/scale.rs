@@ -1,4 +1,4 @@
-use noise::Noise;
+use noise::{bounds, Noise};
 
 #[derive(Clone, Debug)]
 pub struct Scale<N>
@@ -17,6 +17,39 @@ where
     min: f64,
     max: f64,
 }
+#[derive(Clone, Debug)]
+pub struct ScaleBias<N>
+where
+    N: Noise,
+{
+    noise: N,
+    scale: f64,
+    bias: f64,
+}
+/// Mirrors values within `[min, max]`, so `min` maps to `max` and vice versa.
+#[derive(Clone, Debug)]
+pub struct InvertRange<N>
+where
+    N: Noise,
+{
+    noise: N,
+    min: f64,
+    max: f64,
+}
+/// Maps a measured input range to [-1, 1].
+///
+/// Unlike `WithRange`, which assumes its source is within [-1, 1], the input range is taken
+/// from the source itself, usually through `NoiseStats::normalize`.
+#[derive(Clone, Debug)]
+pub struct Normalize<N>
+where
+    N: Noise,
+{
+    noise: N,
+    low: f64,
+    high: f64,
+    clamped: bool,
+}
 
 impl<N> Scale<N>
 where
@@ -54,6 +87,89 @@ where
     }
 }
 
+impl<N> ScaleBias<N>
+where
+    N: Noise,
+{
+    pub fn new(noise: N, scale: f64, bias: f64) -> ScaleBias<N> {
+        ScaleBias { noise, scale, bias }
+    }
+
+    pub fn inner_noise(&self) -> &N {
+        &self.noise
+    }
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+    pub fn bias(&self) -> f64 {
+        self.bias
+    }
+}
+
+impl<N> InvertRange<N>
+where
+    N: Noise,
+{
+    pub fn new(noise: N, min: f64, max: f64) -> InvertRange<N> {
+        assert!(min < max);
+        InvertRange { noise, min, max }
+    }
+
+    pub fn inner_noise(&self) -> &N {
+        &self.noise
+    }
+    pub fn min(&self) -> f64 {
+        self.min
+    }
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+}
+
+impl<N> Normalize<N>
+where
+    N: Noise,
+{
+    /// An empty input range, where `low == high`, maps every value to 0, the normalized value
+    /// of a constant source.
+    pub fn new(noise: N, low: f64, high: f64) -> Normalize<N> {
+        assert!(low <= high);
+        Normalize {
+            noise,
+            low,
+            high,
+            clamped: false,
+        }
+    }
+
+    /// Whether values outside of the input range are clamped to [-1, 1].
+    pub fn with_clamping(self, clamped: bool) -> Normalize<N> {
+        Normalize { clamped, ..self }
+    }
+
+    pub fn inner_noise(&self) -> &N {
+        &self.noise
+    }
+    pub fn input_range(&self) -> (f64, f64) {
+        (self.low, self.high)
+    }
+    pub fn is_clamped(&self) -> bool {
+        self.clamped
+    }
+
+    fn apply(&self, value: f64) -> f64 {
+        if self.high <= self.low {
+            return 0.0;
+        }
+        let val = (value - self.low) / (self.high - self.low) * 2.0 - 1.0;
+        if self.clamped {
+            val.clamp(-1.0, 1.0)
+        } else {
+            val
+        }
+    }
+}
+
 impl<N> Noise for Scale<N>
 where
     N: Noise,
@@ -64,6 +180,12 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         self.noise.value_at(pos) * self.amplitude
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod(pos, footprint) * self.amplitude
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::affine(self.noise.output_bounds(), self.amplitude, 0.0)
+    }
     fn frequency(&self) -> Self::DimType {
         self.noise.frequency()
     }
@@ -81,7 +203,132 @@ where
 
         self.min + normalized_val * (self.max - self.min)
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        let normalized_val = 0.5 + 0.5 * self.noise.value_at_lod(pos, footprint);
+
+        self.min + normalized_val * (self.max - self.min)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        let half_range = 0.5 * (self.max - self.min);
+        bounds::affine(self.noise.output_bounds(), half_range, self.min + half_range)
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.noise.frequency()
+    }
+}
+
+impl<N> Noise for ScaleBias<N>
+where
+    N: Noise,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.noise.value_at(pos) * self.scale + self.bias
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod(pos, footprint) * self.scale + self.bias
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::affine(self.noise.output_bounds(), self.scale, self.bias)
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.noise.frequency()
+    }
+}
+
+impl<N> Noise for InvertRange<N>
+where
+    N: Noise,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.min + self.max - self.noise.value_at(pos)
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.min + self.max - self.noise.value_at_lod(pos, footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::affine(self.noise.output_bounds(), -1.0, self.min + self.max)
+    }
     fn frequency(&self) -> Self::DimType {
         self.noise.frequency()
     }
 }
+
+impl<N> Noise for Normalize<N>
+where
+    N: Noise,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.apply(self.noise.value_at(pos))
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.apply(self.noise.value_at_lod(pos, footprint))
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        if self.high <= self.low {
+            return (0.0, 0.0);
+        }
+        let scale = 2.0 / (self.high - self.low);
+        let normalized = bounds::affine(self.noise.output_bounds(), scale, -1.0 - self.low * scale);
+        if self.clamped {
+            bounds::clamp(normalized, -1.0, 1.0)
+        } else {
+            normalized
+        }
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.noise.frequency()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use adapter::{Constant, FunctionValue, NoiseExt};
+    use analysis::NoiseStats;
+    use noise::Noise;
+
+    #[test]
+    fn invert_range_mirrors_values() {
+        let constant = |value: f64| Constant::<f64, f64>::new(value);
+        assert_eq!(constant(0.25).invert_range(-1.0, 1.0).value_at(0.0), -0.25);
+        assert_eq!(constant(2.0).invert_range(0.0, 10.0).value_at(0.0), 8.0);
+        assert_eq!(
+            constant(2.0).invert_range(0.0, 10.0).output_bounds(),
+            (8.0, 8.0)
+        );
+    }
+
+    #[test]
+    fn normalize_maps_measured_range() {
+        let ramp = FunctionValue::<f64, f64, _>::new(|&x: &f64| x * 3.0 + 1.0);
+        let stats = NoiseStats::sample_grid(&ramp, 0.0, 1.0, 101);
+        let normalized = stats.normalize(&ramp);
+        assert_eq!(normalized.input_range(), (1.0, 4.0));
+        assert_eq!(normalized.value_at(0.0), -1.0);
+        assert_eq!(normalized.value_at(0.5), 0.0);
+        assert_eq!(normalized.value_at(1.0), 1.0);
+        assert_eq!(normalized.value_at(2.0), 3.0);
+        assert_eq!(normalized.with_clamping(true).value_at(2.0), 1.0);
+
+        let quantiles = stats.normalize_quantiles(&ramp, 0.1);
+        assert!((quantiles.value_at(0.1) + 1.0).abs() < 1e-12);
+        assert_eq!(quantiles.value_at(0.0), -1.0);
+
+        //Constant sources and empty statistics give a degenerate mapping instead of panicking.
+        let constant = Constant::<f64, f64>::new(2.0);
+        let flat = NoiseStats::sample_grid(&constant, 0.0, 1.0, 11).normalize(&constant);
+        assert_eq!(flat.value_at(0.5), 0.0);
+        assert_eq!(flat.output_bounds(), (0.0, 0.0));
+        let empty = NoiseStats::from_values(vec![f64::NAN]).normalize_quantiles(&constant, 0.1);
+        assert_eq!(empty.value_at(0.5), 0.0);
+        assert_eq!(constant.normalize(2.0, 2.0).value_at(0.5), 0.0);
+    }
+}
@@ -36,7 +36,13 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         self.noise.value_at([pos, self.height])
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod([pos, self.height], footprint)
+    }
 
+    fn output_bounds(&self) -> (f64, f64) {
+        self.noise.output_bounds()
+    }
     fn frequency(&self) -> Self::DimType {
         self.noise.frequency().0
     }
@@ -69,7 +75,13 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         self.noise.value_at([pos[0], pos[1], self.depth])
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod([pos[0], pos[1], self.depth], footprint)
+    }
 
+    fn output_bounds(&self) -> (f64, f64) {
+        self.noise.output_bounds()
+    }
     fn frequency(&self) -> Self::DimType {
         (self.noise.frequency().0, self.noise.frequency().1)
     }
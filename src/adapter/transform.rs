@@ -1,4 +1,4 @@
-use noise::Noise;
+use noise::{bounds, Noise};
 
 #[derive(Clone, Debug)]
 pub struct Transform<N, F>
@@ -8,6 +8,7 @@ where
 {
     noise: N,
     transform: F,
+    output_bounds: (f64, f64),
 }
 
 #[derive(Clone, Debug)]
@@ -18,13 +19,44 @@ where
     noise: N,
 }
 
+#[derive(Clone, Debug)]
+pub struct Abs<N>
+where
+    N: Noise,
+{
+    noise: N,
+}
+
+/// Maps the source from [-1, 1] to [0, 1], raises it to `exponent` and maps it back.
+#[derive(Clone, Debug)]
+pub struct Exponent<N>
+where
+    N: Noise,
+{
+    noise: N,
+    exponent: f64,
+}
+
 impl<N, F> Transform<N, F>
 where
     N: Noise,
     F: Fn(&N::IndexType, f64) -> f64,
 {
     pub fn new(noise: N, transform: F) -> Transform<N, F> {
-        Transform { noise, transform }
+        Transform {
+            noise,
+            transform,
+            output_bounds: bounds::UNBOUNDED,
+        }
+    }
+
+    /// Declares the range of the transform function, which can't be derived from it. Unbounded
+    /// by default.
+    pub fn with_output_bounds(self, output_bounds: (f64, f64)) -> Transform<N, F> {
+        Transform {
+            output_bounds,
+            ..self
+        }
     }
 
     pub fn inner_noise(&self) -> &N {
@@ -47,6 +79,13 @@ where
         let f = &self.transform;
         f(&pos, self.noise.value_at(pos.clone()))
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        let f = &self.transform;
+        f(&pos, self.noise.value_at_lod(pos.clone(), footprint))
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        self.output_bounds
+    }
     fn frequency(&self) -> Self::DimType {
         self.noise.frequency()
     }
@@ -75,7 +114,125 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         -self.noise.value_at(pos)
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        -self.noise.value_at_lod(pos, footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::affine(self.noise.output_bounds(), -1.0, 0.0)
+    }
     fn frequency(&self) -> Self::DimType {
         self.noise.frequency()
     }
 }
+
+impl<N> Abs<N>
+where
+    N: Noise,
+{
+    pub fn new(noise: N) -> Abs<N> {
+        Abs { noise }
+    }
+
+    pub fn inner_noise(&self) -> &N {
+        &self.noise
+    }
+}
+
+impl<N> Noise for Abs<N>
+where
+    N: Noise,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.noise.value_at(pos).abs()
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod(pos, footprint).abs()
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::abs(self.noise.output_bounds())
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.noise.frequency()
+    }
+}
+
+impl<N> Exponent<N>
+where
+    N: Noise,
+{
+    pub fn new(noise: N, exponent: f64) -> Exponent<N> {
+        Exponent { noise, exponent }
+    }
+
+    pub fn inner_noise(&self) -> &N {
+        &self.noise
+    }
+    pub fn exponent(&self) -> f64 {
+        self.exponent
+    }
+}
+
+/// Maps `value` to [0, 1], raises it to `exponent` and maps it back, as `Exponent` does.
+pub fn exponent_curve(value: f64, exponent: f64) -> f64 {
+    (0.5 + 0.5 * value).max(0.0).powf(exponent) * 2.0 - 1.0
+}
+
+impl<N> Noise for Exponent<N>
+where
+    N: Noise,
+{
+    type IndexType = N::IndexType;
+    type DimType = N::DimType;
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        exponent_curve(self.noise.value_at(pos), self.exponent)
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        exponent_curve(self.noise.value_at_lod(pos, footprint), self.exponent)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        let (low, high) = bounds::clamp(
+            bounds::affine(self.noise.output_bounds(), 0.5, 0.5),
+            0.0,
+            f64::INFINITY,
+        );
+        //`powf` is increasing for positive exponents and decreasing for negative ones.
+        let powered = if self.exponent >= 0.0 {
+            (low.powf(self.exponent), high.powf(self.exponent))
+        } else {
+            (high.powf(self.exponent), low.powf(self.exponent))
+        };
+        bounds::affine(powered, 2.0, -1.0)
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.noise.frequency()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use adapter::{Constant, NoiseExt};
+    use noise::Noise;
+
+    #[test]
+    fn unary_adapters_values_and_bounds() {
+        let constant = |value: f64| Constant::<f64, f64>::new(value);
+        assert_eq!(constant(-0.75).abs().value_at(0.0), 0.75);
+        assert_eq!(constant(-0.75).abs().output_bounds(), (0.75, 0.75));
+        assert_eq!(constant(0.5).negate().value_at(0.0), -0.5);
+
+        //-1 and 1 are fixed points, 0 maps through 0.5^exponent.
+        for &exponent in &[0.5, 2.0, 3.0] {
+            let exp = |value: f64| constant(value).exponent(exponent).value_at(0.0);
+            assert_eq!(exp(-1.0), -1.0);
+            assert_eq!(exp(1.0), 1.0);
+            assert!((exp(0.0) - (0.5f64.powf(exponent) * 2.0 - 1.0)).abs() < 1e-12);
+        }
+        //Values below -1 are clamped before the power, so they don't produce NaN.
+        assert_eq!(constant(-3.0).exponent(0.5).value_at(0.0), -1.0);
+        assert_eq!(constant(0.0).exponent(2.0).output_bounds(), (-0.5, -0.5));
+    }
+}
@@ -0,0 +1,7 @@
+//! Tools for measuring the output of noise functions.
+
+pub mod spectrum;
+pub mod stats;
+
+pub use self::spectrum::{fft, fft_2d, Complex, PowerSpectrum};
+pub use self::stats::{Histogram, NoiseStats, SamplePoint};
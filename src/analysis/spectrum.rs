@@ -0,0 +1,312 @@
+use std::f64::consts::PI;
+use std::ops::{Add, Mul, Sub};
+
+use grid::Grid2d;
+use noise::Noise2d;
+use render::Region;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+    /// `e^(i * angle)`
+    pub fn from_angle(angle: f64) -> Complex {
+        Complex::new(angle.cos(), angle.sin())
+    }
+    pub fn norm_sqr(&self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// In place radix-2 Cooley-Tukey FFT. The inverse transform is not normalized.
+///
+/// Panics if the length of `data` is not a power of two.
+pub fn fft(data: &mut [Complex], inverse: bool) {
+    let n = data.len();
+    assert!(n.is_power_of_two(), "FFT length must be a power of two");
+
+    //Bit reversal permutation.
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let step = Complex::from_angle(sign * 2.0 * PI / len as f64);
+        for chunk in data.chunks_mut(len) {
+            let (even, odd) = chunk.split_at_mut(len / 2);
+            let mut twiddle = Complex::new(1.0, 0.0);
+            for (e, o) in even.iter_mut().zip(odd.iter_mut()) {
+                let t = *o * twiddle;
+                *o = *e - t;
+                *e = *e + t;
+                twiddle = twiddle * step;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+/// Forward FFT of the rows and then the columns of `grid`.
+pub fn fft_2d(grid: &Grid2d<f64>) -> Grid2d<Complex> {
+    let (width, height) = (grid.width() as usize, grid.height() as usize);
+    let mut data: Vec<Complex> = grid.data().iter().map(|&v| Complex::new(v, 0.0)).collect();
+    for row in data.chunks_mut(width) {
+        fft(row, false);
+    }
+    let mut column = vec![Complex::default(); height];
+    for x in 0..width {
+        for (y, c) in column.iter_mut().enumerate() {
+            *c = data[y * width + x];
+        }
+        fft(&mut column, false);
+        for (y, &c) in column.iter().enumerate() {
+            data[y * width + x] = c;
+        }
+    }
+    Grid2d::with_data(grid.width(), grid.height(), data)
+}
+
+/// Signed frequency index of FFT bin `i` out of `n`.
+fn signed_bin(i: usize, n: usize) -> f64 {
+    if i <= n / 2 {
+        i as f64
+    } else {
+        i as f64 - n as f64
+    }
+}
+
+fn hann(i: usize, n: usize) -> f64 {
+    0.5 - 0.5 * (2.0 * PI * i as f64 / n as f64).cos()
+}
+
+/// Radially averaged power spectrum of a square grid of samples.
+///
+/// Bin `k` holds the mean power of all frequencies whose distance from the origin rounds to `k`
+/// cycles across the grid, up to the Nyquist frequency.
+#[derive(Clone, Debug)]
+pub struct PowerSpectrum {
+    power: Vec<f64>,
+    spacing: f64,
+    size: u32,
+}
+
+impl PowerSpectrum {
+    /// Computes the spectrum of `grid`, whose samples are `spacing` units of noise space apart.
+    ///
+    /// The mean is removed and a Hann window applied first, since noise samples are not periodic
+    /// and the edges would otherwise leak power into every frequency.
+    ///
+    /// Panics unless the grid is square with a power of two size.
+    pub fn from_grid(grid: &Grid2d<f64>, spacing: f64) -> PowerSpectrum {
+        let size = grid.width();
+        assert_eq!(size, grid.height(), "The power spectrum needs a square grid");
+        assert!(
+            size.is_power_of_two(),
+            "The power spectrum needs a power of two size, got {}",
+            size
+        );
+        let n = size as usize;
+
+        let mean = grid.data().iter().sum::<f64>() / grid.size() as f64;
+        let mut windowed = Grid2d::new(size, size);
+        let mut window_power = 0.0;
+        for y in 0..n {
+            for x in 0..n {
+                let w = hann(x, n) * hann(y, n);
+                windowed[(x, y)] = (grid[(x, y)] - mean) * w;
+                window_power += w * w;
+            }
+        }
+        let transformed = fft_2d(&windowed);
+
+        let bins = n / 2 + 1;
+        let mut power = vec![0.0; bins];
+        let mut counts = vec![0usize; bins];
+        for y in 0..n {
+            for x in 0..n {
+                let radius = signed_bin(x, n).hypot(signed_bin(y, n)).round() as usize;
+                if radius < bins {
+                    power[radius] += transformed[(x, y)].norm_sqr() / window_power;
+                    counts[radius] += 1;
+                }
+            }
+        }
+        for (p, &c) in power.iter_mut().zip(counts.iter()) {
+            *p /= c as f64;
+        }
+        PowerSpectrum {
+            power,
+            spacing,
+            size,
+        }
+    }
+
+    /// Samples `noise` over `region` on a `size` x `size` grid and computes its spectrum.
+    ///
+    /// Every sample is taken at full detail, so any power above the Nyquist frequency shows up
+    /// aliased into the spectrum. Panics unless `size` is a power of two.
+    pub fn sample<N>(noise: &N, region: Region, size: u32) -> PowerSpectrum
+    where
+        N: Noise2d,
+    {
+        let mut grid = Grid2d::new(size, size);
+        for y in 0..size {
+            for x in 0..size {
+                grid[(x as usize, y as usize)] = noise.value_at(region.pixel_center(x, y, size, size));
+            }
+        }
+        PowerSpectrum::from_grid(&grid, (region.max[0] - region.min[0]) / f64::from(size))
+    }
+
+    /// Mean power of each radial bin, starting at the zero frequency.
+    pub fn power(&self) -> &[f64] {
+        &self.power
+    }
+
+    /// Frequency of `bin` in cycles per unit of noise space.
+    pub fn frequency(&self, bin: usize) -> f64 {
+        bin as f64 / (f64::from(self.size) * self.spacing)
+    }
+
+    /// Frequency above which the sampling can't resolve the noise; anything there is aliased.
+    pub fn nyquist_frequency(&self) -> f64 {
+        0.5 / self.spacing
+    }
+
+    /// The bin with the most power, not counting the zero frequency. Bins with a NaN power,
+    /// from non-finite samples, are skipped.
+    pub fn peak_bin(&self) -> usize {
+        (1..self.power.len())
+            .filter(|&k| !self.power[k].is_nan())
+            .max_by(|&a, &b| self.power[a].total_cmp(&self.power[b]))
+            .unwrap_or(0)
+    }
+
+    /// Least squares slope of log power over log frequency for the bins in `[first, last]`.
+    ///
+    /// A spectrum falling off as `1/f^β` has a slope of `-β`.
+    pub fn slope(&self, first: usize, last: usize) -> f64 {
+        assert!(first > 0 && first < last, "The slope needs a range of non-zero bins");
+        let points: Vec<(f64, f64)> = (first..=last.min(self.power.len() - 1))
+            .filter(|&k| self.power[k] > 0.0)
+            .map(|k| ((k as f64).ln(), self.power[k].ln()))
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let covariance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+        let variance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.0 - mean_x)).sum();
+        covariance / variance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gradient;
+    use grid::GradientGrid;
+    use noise::fbm::Fbm2d;
+    use noise::{seeded_rng, Perlin2d};
+
+    #[test]
+    fn fft_of_sinusoid() {
+        let n = 64;
+        let mut data: Vec<Complex> = (0..n)
+            .map(|i| Complex::new((2.0 * PI * 5.0 * i as f64 / n as f64).cos(), 0.0))
+            .collect();
+        fft(&mut data, false);
+        for (i, c) in data.iter().enumerate() {
+            let expected = if i == 5 || i == n - 5 { n as f64 / 2.0 } else { 0.0 };
+            assert!((c.re - expected).abs() < 1e-9 && c.im.abs() < 1e-9);
+        }
+
+        fft(&mut data, true);
+        assert!((data[1].re / n as f64 - (2.0 * PI * 5.0 / n as f64).cos()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn perlin_spectrum_is_band_limited() {
+        let perlin = Perlin2d::new(
+            (16.0, 16.0),
+            Grid2d::build_grid(
+                (17, 17),
+                &mut gradient::RandomGradientBuilder2d::new(seeded_rng(5)),
+            ),
+        );
+        let spectrum = PowerSpectrum::sample(&perlin, Region::new([0.0, 0.0], [1.0, 1.0]), 256);
+        //The power concentrates around the lattice frequency and falls off steeply above it.
+        let peak = spectrum.peak_bin();
+        assert!((4..=24).contains(&peak), "peak at bin {}", peak);
+        assert!(spectrum.slope(48, 112) < -6.0, "slope {}", spectrum.slope(48, 112));
+    }
+
+    #[test]
+    fn fbm_spectrum_follows_power_law() {
+        let fbm = Fbm2d::new(seeded_rng(3))
+            .with_frequency((4.0, 4.0))
+            .with_num_octaves(5);
+        let spectrum = PowerSpectrum::sample(&fbm, Region::new([0.0, 0.0], [1.0, 1.0]), 256);
+        //Each octave doubles the frequency and halves the amplitude, spreading a quarter of the
+        //power over four times the area: a slope of -4 in the radially averaged spectrum.
+        let slope = spectrum.slope(6, 64);
+        assert!(slope > -5.0 && slope < -3.0, "slope {}", slope);
+    }
+
+    #[test]
+    fn peak_skips_nan_bins() {
+        let spectrum = PowerSpectrum {
+            power: vec![5.0, 1.0, f64::NAN, 3.0, 2.0],
+            spacing: 1.0,
+            size: 8,
+        };
+        assert_eq!(spectrum.peak_bin(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn rejects_non_power_of_two_sizes() {
+        let fbm = Fbm2d::new(seeded_rng(3));
+        PowerSpectrum::sample(&fbm, Region::new([0.0, 0.0], [1.0, 1.0]), 100);
+    }
+}
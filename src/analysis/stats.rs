@@ -0,0 +1,277 @@
+use std::cmp::Ordering;
+use std::f64;
+
+use rand::Rng;
+
+use adapter::Normalize;
+use noise::{Noise, Point2, Point3};
+
+/// Points that can be laid out on a lattice or drawn at random inside an axis aligned box.
+pub trait SamplePoint: Copy {
+    /// `resolution` points per axis from `min` to `max` inclusive, the first axis varying fastest.
+    fn lattice(min: Self, max: Self, resolution: usize) -> Vec<Self>;
+    fn random_in<R: Rng>(rng: &mut R, min: Self, max: Self) -> Self;
+}
+
+fn lattice_coord(min: f64, max: f64, i: usize, resolution: usize) -> f64 {
+    if resolution < 2 {
+        min
+    } else {
+        min + (max - min) * i as f64 / (resolution - 1) as f64
+    }
+}
+
+fn random_coord<R: Rng>(rng: &mut R, min: f64, max: f64) -> f64 {
+    min + (max - min) * rng.gen::<f64>()
+}
+
+impl SamplePoint for f64 {
+    fn lattice(min: f64, max: f64, resolution: usize) -> Vec<f64> {
+        (0..resolution)
+            .map(|i| lattice_coord(min, max, i, resolution))
+            .collect()
+    }
+    fn random_in<R: Rng>(rng: &mut R, min: f64, max: f64) -> f64 {
+        random_coord(rng, min, max)
+    }
+}
+
+impl SamplePoint for Point2<f64> {
+    fn lattice(min: Self, max: Self, resolution: usize) -> Vec<Self> {
+        let mut points = Vec::with_capacity(resolution * resolution);
+        for y in 0..resolution {
+            for x in 0..resolution {
+                points.push([
+                    lattice_coord(min[0], max[0], x, resolution),
+                    lattice_coord(min[1], max[1], y, resolution),
+                ]);
+            }
+        }
+        points
+    }
+    fn random_in<R: Rng>(rng: &mut R, min: Self, max: Self) -> Self {
+        [
+            random_coord(rng, min[0], max[0]),
+            random_coord(rng, min[1], max[1]),
+        ]
+    }
+}
+
+impl SamplePoint for Point3<f64> {
+    fn lattice(min: Self, max: Self, resolution: usize) -> Vec<Self> {
+        let mut points = Vec::with_capacity(resolution * resolution * resolution);
+        for z in 0..resolution {
+            for y in 0..resolution {
+                for x in 0..resolution {
+                    points.push([
+                        lattice_coord(min[0], max[0], x, resolution),
+                        lattice_coord(min[1], max[1], y, resolution),
+                        lattice_coord(min[2], max[2], z, resolution),
+                    ]);
+                }
+            }
+        }
+        points
+    }
+    fn random_in<R: Rng>(rng: &mut R, min: Self, max: Self) -> Self {
+        [
+            random_coord(rng, min[0], max[0]),
+            random_coord(rng, min[1], max[1]),
+            random_coord(rng, min[2], max[2]),
+        ]
+    }
+}
+
+/// Counts of samples in equally sized bins spanning `[min, max]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub min: f64,
+    pub max: f64,
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn bin_width(&self) -> f64 {
+        (self.max - self.min) / self.counts.len() as f64
+    }
+
+    /// Lower and upper edge of bin `i`.
+    pub fn bin_range(&self, i: usize) -> (f64, f64) {
+        let width = self.bin_width();
+        (self.min + width * i as f64, self.min + width * (i + 1) as f64)
+    }
+}
+
+/// Distribution of the values of a noise function, measured from samples.
+///
+/// Non-finite samples are counted separately and left out of every other statistic.
+#[derive(Clone, Debug)]
+pub struct NoiseStats {
+    sorted: Vec<f64>,
+    non_finite: usize,
+    mean: f64,
+    variance: f64,
+}
+
+impl NoiseStats {
+    pub fn from_values(mut values: Vec<f64>) -> NoiseStats {
+        let total = values.len();
+        values.retain(|v| v.is_finite());
+        let non_finite = total - values.len();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        //Welford's algorithm, which stays accurate for large sample counts.
+        let (mut mean, mut m2) = (0.0, 0.0);
+        for (i, &v) in values.iter().enumerate() {
+            let delta = v - mean;
+            mean += delta / (i + 1) as f64;
+            m2 += delta * (v - mean);
+        }
+        let variance = if values.is_empty() {
+            0.0
+        } else {
+            m2 / values.len() as f64
+        };
+
+        NoiseStats {
+            sorted: values,
+            non_finite,
+            mean,
+            variance,
+        }
+    }
+
+    /// Samples `noise` on a lattice with `resolution` points per axis spanning `[min, max]`.
+    pub fn sample_grid<N>(noise: &N, min: N::IndexType, max: N::IndexType, resolution: usize) -> NoiseStats
+    where
+        N: Noise,
+        N::IndexType: SamplePoint,
+    {
+        NoiseStats::from_values(SamplePoint::lattice(min, max, resolution)
+            .into_iter()
+            .map(|p| noise.value_at(p))
+            .collect())
+    }
+
+    /// Samples `noise` at `count` uniformly distributed points within `[min, max]`.
+    pub fn sample_random<N, R>(
+        noise: &N,
+        min: N::IndexType,
+        max: N::IndexType,
+        count: usize,
+        rng: &mut R,
+    ) -> NoiseStats
+    where
+        N: Noise,
+        N::IndexType: SamplePoint,
+        R: Rng,
+    {
+        NoiseStats::from_values((0..count)
+            .map(|_| noise.value_at(SamplePoint::random_in(rng, min, max)))
+            .collect())
+    }
+
+    /// Number of finite samples.
+    pub fn count(&self) -> usize {
+        self.sorted.len()
+    }
+    pub fn non_finite_count(&self) -> usize {
+        self.non_finite
+    }
+    pub fn min(&self) -> f64 {
+        self.sorted.first().cloned().unwrap_or(f64::NAN)
+    }
+    pub fn max(&self) -> f64 {
+        self.sorted.last().cloned().unwrap_or(f64::NAN)
+    }
+    pub fn range(&self) -> (f64, f64) {
+        (self.min(), self.max())
+    }
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+    pub fn variance(&self) -> f64 {
+        self.variance
+    }
+    pub fn std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    /// The value below which a fraction `q` of the samples lie, interpolating between samples.
+    pub fn quantile(&self, q: f64) -> f64 {
+        assert!((0.0..=1.0).contains(&q), "Quantile must be within [0, 1]");
+        if self.sorted.is_empty() {
+            return f64::NAN;
+        }
+        let pos = q * (self.sorted.len() - 1) as f64;
+        let (i, t) = (pos.floor() as usize, pos.fract());
+        match self.sorted.get(i + 1) {
+            Some(&next) => self.sorted[i] + (next - self.sorted[i]) * t,
+            None => self.sorted[i],
+        }
+    }
+    pub fn percentile(&self, p: f64) -> f64 {
+        self.quantile(p / 100.0)
+    }
+    pub fn median(&self) -> f64 {
+        self.quantile(0.5)
+    }
+
+    /// Maps the measured minimum and maximum of `noise` to [-1, 1].
+    ///
+    /// Without samples, or when every sample is equal, the mapping is degenerate and gives 0.
+    pub fn normalize<N: Noise>(&self, noise: N) -> Normalize<N> {
+        if self.sorted.is_empty() {
+            return Normalize::new(noise, 0.0, 0.0);
+        }
+        Normalize::new(noise, self.min(), self.max())
+    }
+
+    /// Maps the range between the `q` and `1 - q` quantiles to [-1, 1], ignoring outliers.
+    /// Values beyond them are clamped.
+    pub fn normalize_quantiles<N: Noise>(&self, noise: N, q: f64) -> Normalize<N> {
+        assert!(q < 0.5);
+        if self.sorted.is_empty() {
+            return Normalize::new(noise, 0.0, 0.0).with_clamping(true);
+        }
+        Normalize::new(noise, self.quantile(q), self.quantile(1.0 - q)).with_clamping(true)
+    }
+
+    /// A histogram with `bins` bins spanning the measured range.
+    pub fn histogram(&self, bins: usize) -> Histogram {
+        assert!(bins > 0, "A histogram needs at least one bin");
+        let (min, max) = (self.min(), self.max());
+        let mut counts = vec![0; bins];
+        let width = (max - min) / bins as f64;
+        for &v in &self.sorted {
+            let bin = if width > 0.0 {
+                ((v - min) / width) as usize
+            } else {
+                0
+            };
+            counts[bin.min(bins - 1)] += 1;
+        }
+        Histogram { min, max, counts }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapter::FunctionValue;
+
+    #[test]
+    fn grid_statistics() {
+        let noise: FunctionValue<f64, f64, _> = FunctionValue::new(|x: &f64| *x);
+        let stats = NoiseStats::sample_grid(&noise, -1.0, 1.0, 201);
+        assert_eq!(stats.range(), (-1.0, 1.0));
+        assert!(stats.mean().abs() < 1e-12);
+        assert!((stats.percentile(75.0) - 0.5).abs() < 1e-12);
+        //Uniform on [-1, 1] has a variance of 1/3; the lattice adds a small bias.
+        assert!((stats.variance() - 1.0 / 3.0).abs() < 0.01);
+
+        let histogram = stats.histogram(4);
+        assert_eq!(histogram.counts.iter().sum::<usize>(), 201);
+        assert_eq!(histogram.bin_range(1), (-0.5, 0.0));
+    }
+}
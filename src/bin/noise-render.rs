@@ -0,0 +1,181 @@
+//! Renders a noise graph description to an image or raw float file.
+
+extern crate noise_lib;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::process;
+
+use noise_lib::graph::{expr, NodeDesc, Tape};
+use noise_lib::render::{self, ColorGradient, Region};
+
+const USAGE: &str = "Usage: noise-render [options] <graph> <output>
+
+Renders a 2d noise graph. <graph> is a JSON or .ron graph description file, or an
+expression such as 'fbm(freq=0.01, octaves=6) * 0.8' when --expr is given.
+
+Options:
+  --expr                   Treat <graph> as an expression instead of a file
+  --region X0,Y0,X1,Y1     Region of noise space to render (default 0,0,256,256)
+  --size WxH               Output resolution in pixels (default 512x512)
+  --seed N                 Offset the seed of every primitive in the graph
+  --range MIN,MAX          Values mapped to black and white (default -1,1)
+  --format FORMAT          gray8, gray16, color or raw; defaults to raw for
+                           .raw and .f32 outputs and gray8 otherwise
+  -h, --help               Show this message";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Format {
+    Gray8,
+    Gray16,
+    Color,
+    Raw,
+}
+
+struct Options {
+    graph: String,
+    output: String,
+    expression: bool,
+    region: Region,
+    size: (u32, u32),
+    seed: Option<u32>,
+    range: (f64, f64),
+    format: Option<Format>,
+}
+
+fn numbers(arg: &str, value: &str, count: usize) -> Result<Vec<f64>, String> {
+    let values = value
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("{} expects comma separated numbers, got '{}'", arg, value))?;
+    if values.len() != count {
+        return Err(format!("{} expects {} values, got {}", arg, count, values.len()));
+    }
+    Ok(values)
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut options = Options {
+        graph: String::new(),
+        output: String::new(),
+        expression: false,
+        region: Region::new([0.0, 0.0], [256.0, 256.0]),
+        size: (512, 512),
+        seed: None,
+        range: (-1.0, 1.0),
+        format: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(|v| v.as_str())
+                .ok_or_else(|| format!("{} expects a value", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--expr" => options.expression = true,
+            "--region" => {
+                let r = numbers(arg, value()?, 4)?;
+                options.region = Region::new([r[0], r[1]], [r[2], r[3]]);
+            }
+            "--size" => {
+                let v = value()?;
+                let size = v.split('x')
+                    .map(|s| s.parse::<u32>())
+                    .collect::<Result<Vec<_>, _>>();
+                match size.as_ref().map(|s| &s[..]) {
+                    Ok(&[w, h]) if w > 0 && h > 0 => options.size = (w, h),
+                    _ => return Err(format!("--size expects WxH, got '{}'", v)),
+                }
+            }
+            "--seed" => {
+                let v = value()?;
+                options.seed = Some(v.parse()
+                    .map_err(|_| format!("--seed expects a non-negative integer, got '{}'", v))?);
+            }
+            "--range" => {
+                let r = numbers(arg, value()?, 2)?;
+                if r[0] >= r[1] {
+                    return Err("--range expects MIN < MAX".to_string());
+                }
+                options.range = (r[0], r[1]);
+            }
+            "--format" => {
+                options.format = Some(match value()? {
+                    "gray8" => Format::Gray8,
+                    "gray16" => Format::Gray16,
+                    "color" => Format::Color,
+                    "raw" => Format::Raw,
+                    other => return Err(format!("Unknown format '{}'", other)),
+                })
+            }
+            a if a.starts_with("--") => return Err(format!("Unknown option '{}'", a)),
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    if positional.len() != 2 {
+        return Err("Expected a graph and an output path".to_string());
+    }
+    options.output = positional.pop().unwrap();
+    options.graph = positional.pop().unwrap();
+    Ok(options)
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let desc = if options.expression {
+        expr::parse(&options.graph, 2)
+    } else {
+        let text = fs::read_to_string(&options.graph)
+            .map_err(|e| format!("Can't read '{}': {}", options.graph, e))?;
+        if options.graph.ends_with(".ron") {
+            NodeDesc::from_ron_str(&text)
+        } else {
+            NodeDesc::from_json_str(&text)
+        }
+    }.map_err(|e| format!("Invalid graph: {}", e))?;
+    let desc = match options.seed {
+        Some(seed) => desc.with_seed(seed),
+        None => desc,
+    };
+
+    let tape = Tape::compile_2d(&desc).map_err(|e| format!("Invalid graph: {}", e))?;
+    let (width, height) = options.size;
+    let grid = render::sample_tape(&tape, options.region, width, height);
+
+    let format = options.format.unwrap_or_else(|| {
+        if options.output.ends_with(".raw") || options.output.ends_with(".f32") {
+            Format::Raw
+        } else {
+            Format::Gray8
+        }
+    });
+    let file = File::create(&options.output)
+        .map_err(|e| format!("Can't create '{}': {}", options.output, e))?;
+    let out = BufWriter::new(file);
+    match format {
+        Format::Gray8 => render::write_png_gray8(out, &grid, options.range),
+        Format::Gray16 => render::write_png_gray16(out, &grid, options.range),
+        Format::Color => {
+            render::write_png_color(out, &grid, options.range, &ColorGradient::terrain())
+        }
+        Format::Raw => render::write_raw_f32(out, &grid),
+    }.map_err(|e| format!("Can't write '{}': {}", options.output, e))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = parse_args(&args).and_then(|options| run(&options));
+    if let Err(message) = result {
+        eprintln!("error: {}\n\n{}", message, USAGE);
+        process::exit(2);
+    }
+}
@@ -49,6 +49,6 @@ where
     }
 
     fn max_dimensions(&self) -> Option<Self::DimType> {
-        Some(self.len() as u32)
+        (self.len() as u32).checked_sub(1)
     }
 }
@@ -24,6 +24,9 @@ impl PermutationTable {
         &self.table
     }
 
+    /// # Safety
+    ///
+    /// `index` must be less than `self.len()`.
     #[inline]
     pub unsafe fn get_unchecked(&self, index: u32) -> u32 {
         *self.table.get_unchecked(index as usize)
@@ -33,6 +36,10 @@ impl PermutationTable {
     pub fn len(&self) -> usize {
         self.table.len()
     }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
 }
 
 impl Index<u32> for PermutationTable {
@@ -0,0 +1,496 @@
+use std::f64;
+
+use cgmath::{Vector2, Vector3};
+
+use adapter::*;
+use gradient::{PermutedGradientTable, RandomGradientBuilder1d, RandomGradientBuilder2d,
+               RandomGradientBuilder3d};
+use graph::desc::{BinaryOp, Interpolation, MapFn, NodeDesc, RotationDesc};
+use graph::{invalid, ordered, GraphError};
+use interpolate::{Hermite3rdOrderInterpolator, Hermite5thOrderInterpolator, LinearInterpolator};
+use noise::fbm::{Fbm1d, Fbm2d, Fbm3d};
+use noise::{seeded_rng, DomainPoint, DynNoise, DynNoise1d, DynNoise2d, DynNoise3d, Octave,
+            OctaveNoise, OctaveRotation, Perlin1d, Perlin2d, Perlin3d, Point1, Point2, Point3,
+            PointUtil, TupleUtil};
+
+const GRADIENT_TABLE_SIZE: u32 = 256;
+/// Octaves past this are far below `f64` precision and only cost memory and time.
+const MAX_OCTAVES: u32 = 64;
+
+macro_rules! with_interpolation {
+    ($interpolation:expr, $noise:expr) => {
+        match $interpolation {
+            Interpolation::Linear => $noise.with_interpolator(LinearInterpolator::new()).into_dyn(),
+            Interpolation::Hermite3 => $noise
+                .with_interpolator(Hermite3rdOrderInterpolator::new())
+                .into_dyn(),
+            Interpolation::Hermite5 => $noise
+                .with_interpolator(Hermite5thOrderInterpolator::new())
+                .into_dyn(),
+        }
+    };
+}
+
+/// Parameters of an `Fbm` node with per-axis values already expanded.
+struct FbmParams<D> {
+    frequency: D,
+    seed: u32,
+    octaves: u32,
+    frequency_scaling: D,
+    persistance: f64,
+    interpolation: Interpolation,
+    rotation: RotationDesc,
+}
+
+/// The dimension specific parts of building a graph.
+trait GraphDim {
+    type Index: PointUtil<f64> + DomainPoint + Clone + Send + Sync + 'static;
+    type Dim: TupleUtil<f64> + Default + Clone + Send + Sync + 'static;
+    const AXES: usize;
+
+    fn point(values: &[f64]) -> Self::Index;
+    fn dims(values: &[f64]) -> Self::Dim;
+
+    fn perlin(
+        frequency: Self::Dim,
+        seed: u32,
+        interpolation: Interpolation,
+    ) -> DynNoise<Self::Index, Self::Dim>;
+    fn fbm(params: FbmParams<Self::Dim>) -> DynNoise<Self::Index, Self::Dim>;
+    fn slice(source: &NodeDesc, position: f64) -> BuildResult<Self>;
+    fn extend(source: &NodeDesc) -> BuildResult<Self>;
+}
+
+type BuildResult<D> = Result<DynNoise<<D as GraphDim>::Index, <D as GraphDim>::Dim>, GraphError>;
+
+struct Dim1;
+struct Dim2;
+struct Dim3;
+
+fn rotation<I: DomainPoint>(rotation: RotationDesc) -> OctaveRotation<I> {
+    match rotation {
+        RotationDesc::Aligned => OctaveRotation::Aligned,
+        RotationDesc::Seeded(seed) => OctaveRotation::Seeded(seed),
+    }
+}
+
+impl GraphDim for Dim1 {
+    type Index = Point1<f64>;
+    type Dim = f64;
+    const AXES: usize = 1;
+
+    fn point(values: &[f64]) -> Point1<f64> {
+        values[0]
+    }
+    fn dims(values: &[f64]) -> f64 {
+        values[0]
+    }
+
+    fn perlin(frequency: f64, seed: u32, interpolation: Interpolation) -> DynNoise1d {
+        let mut rng = seeded_rng(seed);
+        let mut builder = RandomGradientBuilder1d::new(rng.clone());
+        let table = PermutedGradientTable::new(&mut rng, &mut builder, GRADIENT_TABLE_SIZE);
+        with_interpolation!(interpolation, Perlin1d::new(frequency, table))
+    }
+    fn fbm(p: FbmParams<f64>) -> DynNoise1d {
+        let fbm = Fbm1d::new(seeded_rng(p.seed))
+            .with_frequency(p.frequency)
+            .with_frequency_scaling(p.frequency_scaling)
+            .with_persistance(p.persistance)
+            .with_num_octaves(p.octaves as usize)
+            .with_octave_rotation(rotation(p.rotation));
+        with_interpolation!(p.interpolation, fbm)
+    }
+    fn slice(source: &NodeDesc, position: f64) -> Result<DynNoise1d, GraphError> {
+        Ok(Slice1d::new(build::<Dim2>(source)?, position).into_dyn())
+    }
+    fn extend(_: &NodeDesc) -> Result<DynNoise1d, GraphError> {
+        Err(GraphError::Unsupported {
+            node: "extend".to_string(),
+            message: "there is no lower dimension to extend from 1d".to_string(),
+        })
+    }
+}
+
+impl GraphDim for Dim2 {
+    type Index = Point2<f64>;
+    type Dim = (f64, f64);
+    const AXES: usize = 2;
+
+    fn point(values: &[f64]) -> Point2<f64> {
+        [values[0], values[1]]
+    }
+    fn dims(values: &[f64]) -> (f64, f64) {
+        (values[0], values[1])
+    }
+
+    fn perlin(frequency: (f64, f64), seed: u32, interpolation: Interpolation) -> DynNoise2d {
+        let mut rng = seeded_rng(seed);
+        let mut builder = RandomGradientBuilder2d::new(rng.clone());
+        let table: PermutedGradientTable<Vector2<f64>> =
+            PermutedGradientTable::new(&mut rng, &mut builder, GRADIENT_TABLE_SIZE);
+        with_interpolation!(interpolation, Perlin2d::new(frequency, table))
+    }
+    fn fbm(p: FbmParams<(f64, f64)>) -> DynNoise2d {
+        let fbm = Fbm2d::new(seeded_rng(p.seed))
+            .with_frequency(p.frequency)
+            .with_frequency_scaling(p.frequency_scaling)
+            .with_persistance(p.persistance)
+            .with_num_octaves(p.octaves as usize)
+            .with_octave_rotation(rotation(p.rotation));
+        with_interpolation!(p.interpolation, fbm)
+    }
+    fn slice(source: &NodeDesc, position: f64) -> Result<DynNoise2d, GraphError> {
+        Ok(Slice2d::new(build::<Dim3>(source)?, position).into_dyn())
+    }
+    fn extend(source: &NodeDesc) -> Result<DynNoise2d, GraphError> {
+        Ok(Extension2d::new(build::<Dim1>(source)?).into_dyn())
+    }
+}
+
+impl GraphDim for Dim3 {
+    type Index = Point3<f64>;
+    type Dim = (f64, f64, f64);
+    const AXES: usize = 3;
+
+    fn point(values: &[f64]) -> Point3<f64> {
+        [values[0], values[1], values[2]]
+    }
+    fn dims(values: &[f64]) -> (f64, f64, f64) {
+        (values[0], values[1], values[2])
+    }
+
+    fn perlin(frequency: (f64, f64, f64), seed: u32, interpolation: Interpolation) -> DynNoise3d {
+        let mut rng = seeded_rng(seed);
+        let mut builder = RandomGradientBuilder3d::new(rng.clone());
+        let table: PermutedGradientTable<Vector3<f64>> =
+            PermutedGradientTable::new(&mut rng, &mut builder, GRADIENT_TABLE_SIZE);
+        with_interpolation!(interpolation, Perlin3d::new(frequency, table))
+    }
+    fn fbm(p: FbmParams<(f64, f64, f64)>) -> DynNoise3d {
+        let fbm = Fbm3d::new(seeded_rng(p.seed))
+            .with_frequency(p.frequency)
+            .with_frequency_scaling(p.frequency_scaling)
+            .with_persistance(p.persistance)
+            .with_num_octaves(p.octaves as usize)
+            .with_octave_rotation(rotation(p.rotation));
+        with_interpolation!(p.interpolation, fbm)
+    }
+    fn slice(_: &NodeDesc, _: f64) -> Result<DynNoise3d, GraphError> {
+        Err(GraphError::Unsupported {
+            node: "slice".to_string(),
+            message: "there is no higher dimension to slice into 3d".to_string(),
+        })
+    }
+    fn extend(source: &NodeDesc) -> Result<DynNoise3d, GraphError> {
+        Ok(Extension3d::new(build::<Dim2>(source)?).into_dyn())
+    }
+}
+
+/// Expands a single value to every axis, or checks that there is one value per axis.
+fn axes<D: GraphDim>(node: &str, field: &str, values: &[f64]) -> Result<Vec<f64>, GraphError> {
+    match values.len() {
+        1 => Ok(vec![values[0]; D::AXES]),
+        n if n == D::AXES => Ok(values.to_vec()),
+        _ => Err(invalid(
+            node,
+            field,
+            &format!("expected 1 or {} values", D::AXES),
+        )),
+    }
+}
+
+fn increasing(values: &[f64]) -> bool {
+    values.windows(2).all(|w| w[0] < w[1])
+}
+
+fn non_negative(value: f64) -> bool {
+    value >= 0.0
+}
+
+type BuildAllResult<D> =
+    Result<Vec<DynNoise<<D as GraphDim>::Index, <D as GraphDim>::Dim>>, GraphError>;
+
+fn build_all<D: GraphDim>(sources: &[NodeDesc]) -> BuildAllResult<D> {
+    sources.iter().map(build::<D>).collect()
+}
+
+fn build<D: GraphDim>(node: &NodeDesc) -> BuildResult<D> {
+    let noise = match *node {
+        NodeDesc::Constant(value) => Constant::<D::Index, D::Dim>::new(value).into_dyn(),
+        NodeDesc::Perlin {
+            ref frequency,
+            seed,
+            interpolation,
+        } => D::perlin(
+            D::dims(&axes::<D>("perlin", "frequency", frequency)?),
+            seed,
+            interpolation,
+        ),
+        NodeDesc::Fbm {
+            ref frequency,
+            seed,
+            octaves,
+            ref frequency_scaling,
+            persistance,
+            interpolation,
+            rotation,
+        } => {
+            if octaves > MAX_OCTAVES {
+                return Err(invalid(
+                    "fbm",
+                    "octaves",
+                    &format!("must be at most {}", MAX_OCTAVES),
+                ));
+            }
+            D::fbm(FbmParams {
+                frequency: D::dims(&axes::<D>("fbm", "frequency", frequency)?),
+                seed,
+                octaves,
+                frequency_scaling: D::dims(&axes::<D>(
+                    "fbm",
+                    "frequency_scaling",
+                    frequency_scaling,
+                )?),
+                persistance,
+                interpolation,
+                rotation,
+            })
+        }
+        NodeDesc::Octaves(ref octaves) => {
+            let octaves = octaves
+                .iter()
+                .map(|&(amplitude, ref source)| Ok(Octave::new(build::<D>(source)?, amplitude)))
+                .collect::<Result<_, GraphError>>()?;
+            OctaveNoise::from_octaves(octaves).into_dyn()
+        }
+        NodeDesc::Scale {
+            ref source,
+            amplitude,
+        } => Scale::new(build::<D>(source)?, amplitude).into_dyn(),
+        NodeDesc::ScaleBias {
+            ref source,
+            scale,
+            bias,
+        } => ScaleBias::new(build::<D>(source)?, scale, bias).into_dyn(),
+        NodeDesc::WithRange {
+            ref source,
+            min,
+            max,
+        } => {
+            ordered("with_range", "min", min, max)?;
+            WithRange::new(build::<D>(source)?, min, max).into_dyn()
+        }
+        NodeDesc::InvertRange {
+            ref source,
+            min,
+            max,
+        } => {
+            ordered("invert_range", "min", min, max)?;
+            InvertRange::new(build::<D>(source)?, min, max).into_dyn()
+        }
+        NodeDesc::Clamp {
+            ref source,
+            low,
+            high,
+        } => {
+            ordered("clamp", "low", low, high)?;
+            Clamp::new(build::<D>(source)?, low, high).into_dyn()
+        }
+        NodeDesc::Filter {
+            ref source,
+            start,
+            end,
+            kind,
+            blend,
+        } => {
+            ordered("filter", "start", start, end)?;
+            Filter::new(build::<D>(source)?, start, end, kind, blend.blend_fn()).into_dyn()
+        }
+        NodeDesc::Negate(ref source) => Negate::new(build::<D>(source)?).into_dyn(),
+        NodeDesc::Abs(ref source) => Abs::new(build::<D>(source)?).into_dyn(),
+        NodeDesc::Exponent {
+            ref source,
+            exponent,
+        } => Exponent::new(build::<D>(source)?, exponent).into_dyn(),
+        NodeDesc::Terrace {
+            ref source,
+            ref points,
+            inverted,
+            smoothness,
+        } => {
+            let mut sorted = points.clone();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(::std::cmp::Ordering::Equal));
+            if sorted.len() < 2 || !increasing(&sorted) {
+                return Err(invalid("terrace", "points", "expected at least two unique points"));
+            }
+            if !(0.0..=1.0).contains(&smoothness) {
+                return Err(invalid("terrace", "smoothness", "must be within [0, 1]"));
+            }
+            Terrace::new(build::<D>(source)?, points.clone())
+                .with_inversion(inverted)
+                .with_smoothness(smoothness)
+                .into_dyn()
+        }
+        NodeDesc::Curve {
+            ref source,
+            ref points,
+            kind,
+        } => {
+            let mut inputs: Vec<f64> = points.iter().map(|p| p.0).collect();
+            inputs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(::std::cmp::Ordering::Equal));
+            if inputs.len() < 2 || !increasing(&inputs) {
+                return Err(invalid(
+                    "curve",
+                    "points",
+                    "expected at least two points with unique inputs",
+                ));
+            }
+            Curve::new(build::<D>(source)?, points.clone(), kind).into_dyn()
+        }
+        NodeDesc::Map {
+            ref source,
+            function,
+        } => Transform::new(build::<D>(source)?, move |_: &D::Index, v| MapFn::apply(&function, v))
+            .into_dyn(),
+        NodeDesc::Binary {
+            op,
+            ref left,
+            ref right,
+        } => {
+            let (l, r) = (build::<D>(left)?, build::<D>(right)?);
+            match op {
+                BinaryOp::Add => Add::new(l, r).into_dyn(),
+                BinaryOp::Subtract => Subtract::new(l, r).into_dyn(),
+                BinaryOp::Multiply => Multiply::new(l, r).into_dyn(),
+                BinaryOp::Divide => Divide::new(l, r).into_dyn(),
+                BinaryOp::Min => Min::new(l, r).into_dyn(),
+                BinaryOp::Max => Max::new(l, r).into_dyn(),
+                BinaryOp::Power => Power::new(l, r).into_dyn(),
+            }
+        }
+        NodeDesc::Sum(ref sources) => Sum::new(build_all::<D>(sources)?).into_dyn(),
+        NodeDesc::Product(ref sources) => Product::new(build_all::<D>(sources)?).into_dyn(),
+        NodeDesc::Select {
+            ref left,
+            ref right,
+            ref criteria,
+            lower,
+            upper,
+            falloff,
+        } => {
+            ordered("select", "lower", lower, upper)?;
+            if !non_negative(falloff) {
+                return Err(invalid("select", "falloff", "must not be negative"));
+            }
+            Select::new(
+                build::<D>(left)?,
+                build::<D>(right)?,
+                build::<D>(criteria)?,
+                lower,
+            ).with_bounds(lower, upper)
+                .with_edge_falloff(falloff)
+                .into_dyn()
+        }
+        NodeDesc::MultiSelect {
+            ref criteria,
+            ref sources,
+            ref boundaries,
+            ref falloffs,
+        } => {
+            if sources.len() != boundaries.len() + 1 {
+                return Err(invalid(
+                    "multi_select",
+                    "boundaries",
+                    "expected one fewer boundary than sources",
+                ));
+            }
+            if !increasing(boundaries) {
+                return Err(invalid("multi_select", "boundaries", "must be increasing"));
+            }
+            if falloffs.len() != boundaries.len() || !falloffs.iter().all(|&f| non_negative(f))
+                || (1..boundaries.len())
+                    .any(|i| boundaries[i - 1] + falloffs[i - 1] > boundaries[i] - falloffs[i])
+            {
+                return Err(invalid(
+                    "multi_select",
+                    "falloffs",
+                    "expected one non-negative, non-overlapping falloff per boundary",
+                ));
+            }
+            MultiSelect::new(
+                build::<D>(criteria)?,
+                build_all::<D>(sources)?,
+                boundaries.clone(),
+            ).with_falloffs(falloffs.clone())
+                .into_dyn()
+        }
+        NodeDesc::Blend {
+            ref left,
+            ref right,
+            ref criteria,
+            blend,
+        } => Blend::new(
+            build::<D>(left)?,
+            build::<D>(right)?,
+            build::<D>(criteria)?,
+            blend.blend_fn(),
+        ).into_dyn(),
+        NodeDesc::ScaleInput {
+            ref source,
+            ref scale,
+        } => {
+            let scale = D::point(&axes::<D>("scale_input", "scale", scale)?);
+            ScaleInput::new(build::<D>(source)?, scale).into_dyn()
+        }
+        NodeDesc::ShiftInput {
+            ref source,
+            ref shift,
+        } => {
+            let shift = D::point(&axes::<D>("shift_input", "shift", shift)?);
+            ShiftInput::new(build::<D>(source)?, shift).into_dyn()
+        }
+        NodeDesc::ClampInput {
+            ref source,
+            ref low,
+            ref high,
+        } => {
+            let low = axes::<D>("clamp_input", "low", low)?;
+            let high = axes::<D>("clamp_input", "high", high)?;
+            for (&l, &h) in low.iter().zip(high.iter()) {
+                ordered("clamp_input", "low", l, h)?;
+            }
+            ClampInput::new(build::<D>(source)?, D::point(&low), D::point(&high)).into_dyn()
+        }
+        NodeDesc::WrapInput {
+            ref source,
+            ref low,
+            ref high,
+        } => {
+            let low = axes::<D>("wrap_input", "low", low)?;
+            let high = axes::<D>("wrap_input", "high", high)?;
+            for (&l, &h) in low.iter().zip(high.iter()) {
+                ordered("wrap_input", "low", l, h)?;
+            }
+            WrapInput::new(build::<D>(source)?, D::point(&low), D::point(&high)).into_dyn()
+        }
+        NodeDesc::Slice {
+            ref source,
+            position,
+        } => D::slice(source, position)?,
+        NodeDesc::Extend(ref source) => D::extend(source)?,
+    };
+    Ok(noise)
+}
+
+impl NodeDesc {
+    pub fn build_1d(&self) -> Result<DynNoise1d, GraphError> {
+        build::<Dim1>(self)
+    }
+    pub fn build_2d(&self) -> Result<DynNoise2d, GraphError> {
+        build::<Dim2>(self)
+    }
+    pub fn build_3d(&self) -> Result<DynNoise3d, GraphError> {
+        build::<Dim3>(self)
+    }
+}
@@ -0,0 +1,1030 @@
+use std::f64;
+
+use adapter::{blend, CurveKind, FilterKind};
+use graph::json::Value;
+use graph::GraphError;
+
+/// Interpolation curve used by Perlin noise, or blend curve used by `Blend` and `Filter`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    Hermite3,
+    Hermite5,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RotationDesc {
+    Aligned,
+    Seeded(u32),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Min,
+    Max,
+    Power,
+}
+
+/// Pointwise function applied to the value of a `Map` node.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MapFn {
+    Sin,
+    Cos,
+    Tanh,
+    Sqrt,
+    Exp,
+    Floor,
+    Ceil,
+}
+
+/// Data model of a noise graph.
+///
+/// Nodes don't carry a dimension; it is decided by the `build_*d` call on the root node.
+/// `Slice` and `Extend` build their source one dimension higher or lower respectively.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeDesc {
+    Constant(f64),
+    Perlin {
+        /// Either one value used for every axis or one value per axis.
+        frequency: Vec<f64>,
+        seed: u32,
+        interpolation: Interpolation,
+    },
+    Fbm {
+        frequency: Vec<f64>,
+        seed: u32,
+        octaves: u32,
+        frequency_scaling: Vec<f64>,
+        persistance: f64,
+        interpolation: Interpolation,
+        rotation: RotationDesc,
+    },
+    /// `(amplitude, source)` pairs summed like an `OctaveNoise`.
+    Octaves(Vec<(f64, NodeDesc)>),
+
+    Scale {
+        source: Box<NodeDesc>,
+        amplitude: f64,
+    },
+    ScaleBias {
+        source: Box<NodeDesc>,
+        scale: f64,
+        bias: f64,
+    },
+    WithRange {
+        source: Box<NodeDesc>,
+        min: f64,
+        max: f64,
+    },
+    InvertRange {
+        source: Box<NodeDesc>,
+        min: f64,
+        max: f64,
+    },
+    Clamp {
+        source: Box<NodeDesc>,
+        low: f64,
+        high: f64,
+    },
+    Filter {
+        source: Box<NodeDesc>,
+        start: f64,
+        end: f64,
+        kind: FilterKind,
+        blend: Interpolation,
+    },
+    Negate(Box<NodeDesc>),
+    Abs(Box<NodeDesc>),
+    Exponent {
+        source: Box<NodeDesc>,
+        exponent: f64,
+    },
+    Terrace {
+        source: Box<NodeDesc>,
+        points: Vec<f64>,
+        inverted: bool,
+        smoothness: f64,
+    },
+    Curve {
+        source: Box<NodeDesc>,
+        points: Vec<(f64, f64)>,
+        kind: CurveKind,
+    },
+    Map {
+        source: Box<NodeDesc>,
+        function: MapFn,
+    },
+
+    Binary {
+        op: BinaryOp,
+        left: Box<NodeDesc>,
+        right: Box<NodeDesc>,
+    },
+    Sum(Vec<NodeDesc>),
+    Product(Vec<NodeDesc>),
+    Select {
+        left: Box<NodeDesc>,
+        right: Box<NodeDesc>,
+        criteria: Box<NodeDesc>,
+        lower: f64,
+        upper: f64,
+        falloff: f64,
+    },
+    MultiSelect {
+        criteria: Box<NodeDesc>,
+        sources: Vec<NodeDesc>,
+        boundaries: Vec<f64>,
+        falloffs: Vec<f64>,
+    },
+    Blend {
+        left: Box<NodeDesc>,
+        right: Box<NodeDesc>,
+        criteria: Box<NodeDesc>,
+        blend: Interpolation,
+    },
+
+    ScaleInput {
+        source: Box<NodeDesc>,
+        scale: Vec<f64>,
+    },
+    ShiftInput {
+        source: Box<NodeDesc>,
+        shift: Vec<f64>,
+    },
+    ClampInput {
+        source: Box<NodeDesc>,
+        low: Vec<f64>,
+        high: Vec<f64>,
+    },
+    WrapInput {
+        source: Box<NodeDesc>,
+        low: Vec<f64>,
+        high: Vec<f64>,
+    },
+    /// Fixes the last axis of the higher dimensional source at `position`.
+    Slice {
+        source: Box<NodeDesc>,
+        position: f64,
+    },
+    /// Extends a lower dimensional source along a new, ignored axis.
+    Extend(Box<NodeDesc>),
+}
+
+impl Interpolation {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Interpolation::Linear => "linear",
+            Interpolation::Hermite3 => "hermite3",
+            Interpolation::Hermite5 => "hermite5",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Interpolation> {
+        match name {
+            "linear" => Some(Interpolation::Linear),
+            "hermite3" => Some(Interpolation::Hermite3),
+            "hermite5" => Some(Interpolation::Hermite5),
+            _ => None,
+        }
+    }
+
+    pub fn blend_fn(&self) -> fn(f64, f64, f64) -> f64 {
+        match *self {
+            Interpolation::Linear => blend::linear_blend,
+            Interpolation::Hermite3 => blend::hermite_3rd_order_blend,
+            Interpolation::Hermite5 => blend::hermite_5th_order_blend,
+        }
+    }
+}
+
+impl BinaryOp {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            BinaryOp::Add => "add",
+            BinaryOp::Subtract => "subtract",
+            BinaryOp::Multiply => "multiply",
+            BinaryOp::Divide => "divide",
+            BinaryOp::Min => "min",
+            BinaryOp::Max => "max",
+            BinaryOp::Power => "power",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<BinaryOp> {
+        match name {
+            "add" => Some(BinaryOp::Add),
+            "subtract" => Some(BinaryOp::Subtract),
+            "multiply" => Some(BinaryOp::Multiply),
+            "divide" => Some(BinaryOp::Divide),
+            "min" => Some(BinaryOp::Min),
+            "max" => Some(BinaryOp::Max),
+            "power" => Some(BinaryOp::Power),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, left: f64, right: f64) -> f64 {
+        match *self {
+            BinaryOp::Add => left + right,
+            BinaryOp::Subtract => left - right,
+            BinaryOp::Multiply => left * right,
+            BinaryOp::Divide => left / right,
+            BinaryOp::Min => left.min(right),
+            BinaryOp::Max => left.max(right),
+            BinaryOp::Power => left.powf(right),
+        }
+    }
+}
+
+impl MapFn {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            MapFn::Sin => "sin",
+            MapFn::Cos => "cos",
+            MapFn::Tanh => "tanh",
+            MapFn::Sqrt => "sqrt",
+            MapFn::Exp => "exp",
+            MapFn::Floor => "floor",
+            MapFn::Ceil => "ceil",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<MapFn> {
+        match name {
+            "sin" => Some(MapFn::Sin),
+            "cos" => Some(MapFn::Cos),
+            "tanh" => Some(MapFn::Tanh),
+            "sqrt" => Some(MapFn::Sqrt),
+            "exp" => Some(MapFn::Exp),
+            "floor" => Some(MapFn::Floor),
+            "ceil" => Some(MapFn::Ceil),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, value: f64) -> f64 {
+        match *self {
+            MapFn::Sin => value.sin(),
+            MapFn::Cos => value.cos(),
+            MapFn::Tanh => value.tanh(),
+            MapFn::Sqrt => value.sqrt(),
+            MapFn::Exp => value.exp(),
+            MapFn::Floor => value.floor(),
+            MapFn::Ceil => value.ceil(),
+        }
+    }
+}
+
+fn filter_kind_name(kind: FilterKind) -> &'static str {
+    match kind {
+        FilterKind::LowPass => "low_pass",
+        FilterKind::HighPass => "high_pass",
+    }
+}
+
+fn curve_kind_name(kind: CurveKind) -> &'static str {
+    match kind {
+        CurveKind::MonotoneCubic => "monotone_cubic",
+        CurveKind::CatmullRom => "catmull_rom",
+    }
+}
+
+/// Typed access to the fields of a JSON node object.
+struct Fields<'a> {
+    node: &'a str,
+    value: &'a Value,
+}
+
+impl<'a> Fields<'a> {
+    fn invalid(&self, field: &str, message: &str) -> GraphError {
+        GraphError::InvalidField {
+            node: self.node.to_string(),
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    fn get(&self, field: &str) -> Result<&'a Value, GraphError> {
+        self.value.get(field).ok_or_else(|| GraphError::MissingField {
+            node: self.node.to_string(),
+            field: field.to_string(),
+        })
+    }
+
+    fn number(&self, field: &str) -> Result<f64, GraphError> {
+        self.get(field)?
+            .as_f64()
+            .ok_or_else(|| self.invalid(field, "expected a number"))
+    }
+
+    fn number_or(&self, field: &str, default: f64) -> Result<f64, GraphError> {
+        match self.value.get(field) {
+            Some(_) => self.number(field),
+            None => Ok(default),
+        }
+    }
+
+    fn uint(&self, field: &str) -> Result<u32, GraphError> {
+        let n = self.number(field)?;
+        if n >= 0.0 && n <= f64::from(u32::MAX) && n.fract() == 0.0 {
+            Ok(n as u32)
+        } else {
+            Err(self.invalid(field, "expected a non-negative integer"))
+        }
+    }
+
+    fn uint_or(&self, field: &str, default: u32) -> Result<u32, GraphError> {
+        match self.value.get(field) {
+            Some(_) => self.uint(field),
+            None => Ok(default),
+        }
+    }
+
+    fn bool_or(&self, field: &str, default: bool) -> Result<bool, GraphError> {
+        match self.value.get(field) {
+            Some(v) => v.as_bool()
+                .ok_or_else(|| self.invalid(field, "expected a boolean")),
+            None => Ok(default),
+        }
+    }
+
+    fn string(&self, field: &str) -> Result<&'a str, GraphError> {
+        self.get(field)?
+            .as_str()
+            .ok_or_else(|| self.invalid(field, "expected a string"))
+    }
+
+    fn numbers(&self, field: &str) -> Result<Vec<f64>, GraphError> {
+        let value = self.get(field)?;
+        if let Some(n) = value.as_f64() {
+            return Ok(vec![n]);
+        }
+        value
+            .as_array()
+            .and_then(|values| values.iter().map(Value::as_f64).collect())
+            .ok_or_else(|| self.invalid(field, "expected a number or an array of numbers"))
+    }
+
+    fn numbers_or(&self, field: &str, default: Vec<f64>) -> Result<Vec<f64>, GraphError> {
+        match self.value.get(field) {
+            Some(_) => self.numbers(field),
+            None => Ok(default),
+        }
+    }
+
+    fn interpolation_or(
+        &self,
+        field: &str,
+        default: Interpolation,
+    ) -> Result<Interpolation, GraphError> {
+        match self.value.get(field) {
+            Some(_) => Interpolation::from_name(self.string(field)?)
+                .ok_or_else(|| self.invalid(field, "expected linear, hermite3 or hermite5")),
+            None => Ok(default),
+        }
+    }
+
+    fn node(&self, field: &str) -> Result<Box<NodeDesc>, GraphError> {
+        NodeDesc::from_json(self.get(field)?).map(Box::new)
+    }
+
+    fn nodes(&self, field: &str) -> Result<Vec<NodeDesc>, GraphError> {
+        self.get(field)?
+            .as_array()
+            .ok_or_else(|| self.invalid(field, "expected an array of nodes"))?
+            .iter()
+            .map(NodeDesc::from_json)
+            .collect()
+    }
+}
+
+fn node(kind: &str, mut members: Vec<(&str, Value)>) -> Value {
+    let mut object = vec![("type".to_string(), Value::String(kind.to_string()))];
+    object.extend(members.drain(..).map(|(k, v)| (k.to_string(), v)));
+    Value::Object(object)
+}
+
+fn numbers(values: &[f64]) -> Value {
+    if values.len() == 1 {
+        Value::Number(values[0])
+    } else {
+        Value::Array(values.iter().map(|&v| Value::Number(v)).collect())
+    }
+}
+
+fn string(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+impl NodeDesc {
+    /// The nodes this node reads from.
+    pub fn children_mut(&mut self) -> Vec<&mut NodeDesc> {
+        match *self {
+            NodeDesc::Constant(_) | NodeDesc::Perlin { .. } | NodeDesc::Fbm { .. } => vec![],
+            NodeDesc::Octaves(ref mut octaves) => octaves.iter_mut().map(|o| &mut o.1).collect(),
+            NodeDesc::Sum(ref mut sources) | NodeDesc::Product(ref mut sources) => {
+                sources.iter_mut().collect()
+            }
+            NodeDesc::Binary {
+                ref mut left,
+                ref mut right,
+                ..
+            } => vec![&mut **left, &mut **right],
+            NodeDesc::Select {
+                ref mut left,
+                ref mut right,
+                ref mut criteria,
+                ..
+            }
+            | NodeDesc::Blend {
+                ref mut left,
+                ref mut right,
+                ref mut criteria,
+                ..
+            } => vec![&mut **left, &mut **right, &mut **criteria],
+            NodeDesc::MultiSelect {
+                ref mut criteria,
+                ref mut sources,
+                ..
+            } => {
+                let mut children = vec![&mut **criteria];
+                children.extend(sources.iter_mut());
+                children
+            }
+            NodeDesc::Scale { ref mut source, .. }
+            | NodeDesc::ScaleBias { ref mut source, .. }
+            | NodeDesc::WithRange { ref mut source, .. }
+            | NodeDesc::InvertRange { ref mut source, .. }
+            | NodeDesc::Clamp { ref mut source, .. }
+            | NodeDesc::Filter { ref mut source, .. }
+            | NodeDesc::Exponent { ref mut source, .. }
+            | NodeDesc::Terrace { ref mut source, .. }
+            | NodeDesc::Curve { ref mut source, .. }
+            | NodeDesc::Map { ref mut source, .. }
+            | NodeDesc::ScaleInput { ref mut source, .. }
+            | NodeDesc::ShiftInput { ref mut source, .. }
+            | NodeDesc::ClampInput { ref mut source, .. }
+            | NodeDesc::WrapInput { ref mut source, .. }
+            | NodeDesc::Slice { ref mut source, .. }
+            | NodeDesc::Negate(ref mut source)
+            | NodeDesc::Abs(ref mut source)
+            | NodeDesc::Extend(ref mut source) => vec![&mut **source],
+        }
+    }
+
+    /// Offsets the seed of every primitive in the graph by `seed`, so a whole graph can be
+    /// reseeded while its primitives stay distinct from each other.
+    pub fn with_seed(mut self, seed: u32) -> NodeDesc {
+        self.offset_seeds(seed);
+        self
+    }
+
+    fn offset_seeds(&mut self, offset: u32) {
+        match *self {
+            NodeDesc::Perlin { ref mut seed, .. } => *seed = seed.wrapping_add(offset),
+            NodeDesc::Fbm {
+                ref mut seed,
+                ref mut rotation,
+                ..
+            } => {
+                *seed = seed.wrapping_add(offset);
+                if let RotationDesc::Seeded(ref mut rotation_seed) = *rotation {
+                    *rotation_seed = rotation_seed.wrapping_add(offset);
+                }
+            }
+            _ => for child in self.children_mut() {
+                child.offset_seeds(offset);
+            },
+        }
+    }
+
+    pub fn from_json_str(text: &str) -> Result<NodeDesc, GraphError> {
+        NodeDesc::from_json(&Value::parse(text)?)
+    }
+
+    /// Serializes the description as pretty printed JSON.
+    pub fn to_json_string(&self) -> String {
+        self.to_json().to_string_pretty()
+    }
+
+    pub fn from_ron_str(text: &str) -> Result<NodeDesc, GraphError> {
+        NodeDesc::from_json(&Value::parse_ron(text)?)
+    }
+
+    /// Serializes the description as pretty printed RON.
+    pub fn to_ron_string(&self) -> String {
+        self.to_json().to_ron_string()
+    }
+
+    pub fn from_json(value: &Value) -> Result<NodeDesc, GraphError> {
+        let kind = value
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| GraphError::MissingField {
+                node: "node".to_string(),
+                field: "type".to_string(),
+            })?;
+        let f = Fields { node: kind, value };
+
+        if let Some(op) = BinaryOp::from_name(kind) {
+            return Ok(NodeDesc::Binary {
+                op,
+                left: f.node("left")?,
+                right: f.node("right")?,
+            });
+        }
+
+        let desc = match kind {
+            "constant" => NodeDesc::Constant(f.number("value")?),
+            "perlin" => NodeDesc::Perlin {
+                frequency: f.numbers("frequency")?,
+                seed: f.uint_or("seed", 0)?,
+                interpolation: f.interpolation_or("interpolation", Interpolation::Hermite5)?,
+            },
+            "fbm" => NodeDesc::Fbm {
+                frequency: f.numbers_or("frequency", vec![1.0])?,
+                seed: f.uint_or("seed", 0)?,
+                octaves: f.uint_or("octaves", 8)?,
+                frequency_scaling: f.numbers_or("frequency_scaling", vec![2.0])?,
+                persistance: f.number_or("persistance", 2.0)?,
+                interpolation: f.interpolation_or("interpolation", Interpolation::Hermite5)?,
+                rotation: match value.get("rotation") {
+                    None => RotationDesc::Aligned,
+                    Some(Value::String(s)) if s == "aligned" => RotationDesc::Aligned,
+                    Some(r) => match r.get("seeded") {
+                        Some(_) => RotationDesc::Seeded(Fields { node: kind, value: r }.uint("seeded")?),
+                        None => {
+                            return Err(f.invalid("rotation", "expected \"aligned\" or {\"seeded\": n}"))
+                        }
+                    },
+                },
+            },
+            "octaves" => {
+                let octaves = f.get("octaves")?
+                    .as_array()
+                    .ok_or_else(|| f.invalid("octaves", "expected an array"))?;
+                NodeDesc::Octaves(octaves
+                    .iter()
+                    .map(|o| {
+                        let of = Fields { node: kind, value: o };
+                        Ok((of.number("amplitude")?, *of.node("source")?))
+                    })
+                    .collect::<Result<_, GraphError>>()?)
+            }
+            "scale" => NodeDesc::Scale {
+                source: f.node("source")?,
+                amplitude: f.number("amplitude")?,
+            },
+            "scale_bias" => NodeDesc::ScaleBias {
+                source: f.node("source")?,
+                scale: f.number_or("scale", 1.0)?,
+                bias: f.number_or("bias", 0.0)?,
+            },
+            "with_range" => NodeDesc::WithRange {
+                source: f.node("source")?,
+                min: f.number("min")?,
+                max: f.number("max")?,
+            },
+            "invert_range" => NodeDesc::InvertRange {
+                source: f.node("source")?,
+                min: f.number("min")?,
+                max: f.number("max")?,
+            },
+            "clamp" => NodeDesc::Clamp {
+                source: f.node("source")?,
+                low: f.number("low")?,
+                high: f.number("high")?,
+            },
+            "filter" => NodeDesc::Filter {
+                source: f.node("source")?,
+                start: f.number("start")?,
+                end: f.number("end")?,
+                kind: match f.string("kind")? {
+                    "low_pass" => FilterKind::LowPass,
+                    "high_pass" => FilterKind::HighPass,
+                    _ => return Err(f.invalid("kind", "expected low_pass or high_pass")),
+                },
+                blend: f.interpolation_or("blend", Interpolation::Linear)?,
+            },
+            "negate" => NodeDesc::Negate(f.node("source")?),
+            "abs" => NodeDesc::Abs(f.node("source")?),
+            "exponent" => NodeDesc::Exponent {
+                source: f.node("source")?,
+                exponent: f.number("exponent")?,
+            },
+            "terrace" => NodeDesc::Terrace {
+                source: f.node("source")?,
+                points: f.numbers("points")?,
+                inverted: f.bool_or("inverted", false)?,
+                smoothness: f.number_or("smoothness", 0.0)?,
+            },
+            "curve" => NodeDesc::Curve {
+                source: f.node("source")?,
+                points: f.get("points")?
+                    .as_array()
+                    .and_then(|points| {
+                        points
+                            .iter()
+                            .map(|p| match p.as_array() {
+                                Some([x, y]) => Some((x.as_f64()?, y.as_f64()?)),
+                                _ => None,
+                            })
+                            .collect()
+                    })
+                    .ok_or_else(|| f.invalid("points", "expected an array of [in, out] pairs"))?,
+                kind: match f.string("kind")? {
+                    "monotone_cubic" => CurveKind::MonotoneCubic,
+                    "catmull_rom" => CurveKind::CatmullRom,
+                    _ => return Err(f.invalid("kind", "expected monotone_cubic or catmull_rom")),
+                },
+            },
+            "map" => NodeDesc::Map {
+                source: f.node("source")?,
+                function: MapFn::from_name(f.string("function")?)
+                    .ok_or_else(|| f.invalid("function", "unknown function"))?,
+            },
+            "sum" => NodeDesc::Sum(f.nodes("sources")?),
+            "product" => NodeDesc::Product(f.nodes("sources")?),
+            "select" => NodeDesc::Select {
+                left: f.node("left")?,
+                right: f.node("right")?,
+                criteria: f.node("criteria")?,
+                lower: f.number("lower")?,
+                upper: f.number_or("upper", f64::INFINITY)?,
+                falloff: f.number_or("falloff", 0.0)?,
+            },
+            "multi_select" => {
+                let boundaries = f.numbers("boundaries")?;
+                let falloffs = f.numbers_or("falloffs", vec![0.0; boundaries.len()])?;
+                NodeDesc::MultiSelect {
+                    criteria: f.node("criteria")?,
+                    sources: f.nodes("sources")?,
+                    boundaries,
+                    falloffs,
+                }
+            }
+            "blend" => NodeDesc::Blend {
+                left: f.node("left")?,
+                right: f.node("right")?,
+                criteria: f.node("criteria")?,
+                blend: f.interpolation_or("blend", Interpolation::Linear)?,
+            },
+            "scale_input" => NodeDesc::ScaleInput {
+                source: f.node("source")?,
+                scale: f.numbers("scale")?,
+            },
+            "shift_input" => NodeDesc::ShiftInput {
+                source: f.node("source")?,
+                shift: f.numbers("shift")?,
+            },
+            "clamp_input" => NodeDesc::ClampInput {
+                source: f.node("source")?,
+                low: f.numbers("low")?,
+                high: f.numbers("high")?,
+            },
+            "wrap_input" => NodeDesc::WrapInput {
+                source: f.node("source")?,
+                low: f.numbers("low")?,
+                high: f.numbers("high")?,
+            },
+            "slice" => NodeDesc::Slice {
+                source: f.node("source")?,
+                position: f.number("position")?,
+            },
+            "extend" => NodeDesc::Extend(f.node("source")?),
+            _ => return Err(GraphError::UnknownNode(kind.to_string())),
+        };
+        Ok(desc)
+    }
+
+    pub fn to_json(&self) -> Value {
+        match *self {
+            NodeDesc::Constant(value) => node("constant", vec![("value", Value::Number(value))]),
+            NodeDesc::Perlin {
+                ref frequency,
+                seed,
+                interpolation,
+            } => node(
+                "perlin",
+                vec![
+                    ("frequency", numbers(frequency)),
+                    ("seed", Value::Number(f64::from(seed))),
+                    ("interpolation", string(interpolation.name())),
+                ],
+            ),
+            NodeDesc::Fbm {
+                ref frequency,
+                seed,
+                octaves,
+                ref frequency_scaling,
+                persistance,
+                interpolation,
+                rotation,
+            } => node(
+                "fbm",
+                vec![
+                    ("frequency", numbers(frequency)),
+                    ("seed", Value::Number(f64::from(seed))),
+                    ("octaves", Value::Number(f64::from(octaves))),
+                    ("frequency_scaling", numbers(frequency_scaling)),
+                    ("persistance", Value::Number(persistance)),
+                    ("interpolation", string(interpolation.name())),
+                    (
+                        "rotation",
+                        match rotation {
+                            RotationDesc::Aligned => string("aligned"),
+                            RotationDesc::Seeded(seed) => Value::Object(vec![
+                                ("seeded".to_string(), Value::Number(f64::from(seed))),
+                            ]),
+                        },
+                    ),
+                ],
+            ),
+            NodeDesc::Octaves(ref octaves) => node(
+                "octaves",
+                vec![
+                    (
+                        "octaves",
+                        Value::Array(octaves
+                            .iter()
+                            .map(|&(amplitude, ref source)| {
+                                Value::Object(vec![
+                                    ("amplitude".to_string(), Value::Number(amplitude)),
+                                    ("source".to_string(), source.to_json()),
+                                ])
+                            })
+                            .collect()),
+                    ),
+                ],
+            ),
+            NodeDesc::Scale {
+                ref source,
+                amplitude,
+            } => node(
+                "scale",
+                vec![
+                    ("source", source.to_json()),
+                    ("amplitude", Value::Number(amplitude)),
+                ],
+            ),
+            NodeDesc::ScaleBias {
+                ref source,
+                scale,
+                bias,
+            } => node(
+                "scale_bias",
+                vec![
+                    ("source", source.to_json()),
+                    ("scale", Value::Number(scale)),
+                    ("bias", Value::Number(bias)),
+                ],
+            ),
+            NodeDesc::WithRange {
+                ref source,
+                min,
+                max,
+            } => node(
+                "with_range",
+                vec![
+                    ("source", source.to_json()),
+                    ("min", Value::Number(min)),
+                    ("max", Value::Number(max)),
+                ],
+            ),
+            NodeDesc::InvertRange {
+                ref source,
+                min,
+                max,
+            } => node(
+                "invert_range",
+                vec![
+                    ("source", source.to_json()),
+                    ("min", Value::Number(min)),
+                    ("max", Value::Number(max)),
+                ],
+            ),
+            NodeDesc::Clamp {
+                ref source,
+                low,
+                high,
+            } => node(
+                "clamp",
+                vec![
+                    ("source", source.to_json()),
+                    ("low", Value::Number(low)),
+                    ("high", Value::Number(high)),
+                ],
+            ),
+            NodeDesc::Filter {
+                ref source,
+                start,
+                end,
+                kind,
+                blend,
+            } => node(
+                "filter",
+                vec![
+                    ("source", source.to_json()),
+                    ("start", Value::Number(start)),
+                    ("end", Value::Number(end)),
+                    ("kind", string(filter_kind_name(kind))),
+                    ("blend", string(blend.name())),
+                ],
+            ),
+            NodeDesc::Negate(ref source) => node("negate", vec![("source", source.to_json())]),
+            NodeDesc::Abs(ref source) => node("abs", vec![("source", source.to_json())]),
+            NodeDesc::Exponent {
+                ref source,
+                exponent,
+            } => node(
+                "exponent",
+                vec![
+                    ("source", source.to_json()),
+                    ("exponent", Value::Number(exponent)),
+                ],
+            ),
+            NodeDesc::Terrace {
+                ref source,
+                ref points,
+                inverted,
+                smoothness,
+            } => node(
+                "terrace",
+                vec![
+                    ("source", source.to_json()),
+                    (
+                        "points",
+                        Value::Array(points.iter().map(|&p| Value::Number(p)).collect()),
+                    ),
+                    ("inverted", Value::Bool(inverted)),
+                    ("smoothness", Value::Number(smoothness)),
+                ],
+            ),
+            NodeDesc::Curve {
+                ref source,
+                ref points,
+                kind,
+            } => node(
+                "curve",
+                vec![
+                    ("source", source.to_json()),
+                    (
+                        "points",
+                        Value::Array(points
+                            .iter()
+                            .map(|&(x, y)| Value::Array(vec![Value::Number(x), Value::Number(y)]))
+                            .collect()),
+                    ),
+                    ("kind", string(curve_kind_name(kind))),
+                ],
+            ),
+            NodeDesc::Map {
+                ref source,
+                function,
+            } => node(
+                "map",
+                vec![
+                    ("source", source.to_json()),
+                    ("function", string(function.name())),
+                ],
+            ),
+            NodeDesc::Binary {
+                op,
+                ref left,
+                ref right,
+            } => node(
+                op.name(),
+                vec![("left", left.to_json()), ("right", right.to_json())],
+            ),
+            NodeDesc::Sum(ref sources) => node(
+                "sum",
+                vec![
+                    (
+                        "sources",
+                        Value::Array(sources.iter().map(NodeDesc::to_json).collect()),
+                    ),
+                ],
+            ),
+            NodeDesc::Product(ref sources) => node(
+                "product",
+                vec![
+                    (
+                        "sources",
+                        Value::Array(sources.iter().map(NodeDesc::to_json).collect()),
+                    ),
+                ],
+            ),
+            NodeDesc::Select {
+                ref left,
+                ref right,
+                ref criteria,
+                lower,
+                upper,
+                falloff,
+            } => {
+                let mut members = vec![
+                    ("left", left.to_json()),
+                    ("right", right.to_json()),
+                    ("criteria", criteria.to_json()),
+                    ("lower", Value::Number(lower)),
+                ];
+                //The default, unbounded upper edge is left out to keep saved graphs short.
+                if upper != f64::INFINITY {
+                    members.push(("upper", Value::Number(upper)));
+                }
+                members.push(("falloff", Value::Number(falloff)));
+                node("select", members)
+            }
+            NodeDesc::MultiSelect {
+                ref criteria,
+                ref sources,
+                ref boundaries,
+                ref falloffs,
+            } => node(
+                "multi_select",
+                vec![
+                    ("criteria", criteria.to_json()),
+                    (
+                        "sources",
+                        Value::Array(sources.iter().map(NodeDesc::to_json).collect()),
+                    ),
+                    (
+                        "boundaries",
+                        Value::Array(boundaries.iter().map(|&b| Value::Number(b)).collect()),
+                    ),
+                    (
+                        "falloffs",
+                        Value::Array(falloffs.iter().map(|&f| Value::Number(f)).collect()),
+                    ),
+                ],
+            ),
+            NodeDesc::Blend {
+                ref left,
+                ref right,
+                ref criteria,
+                blend,
+            } => node(
+                "blend",
+                vec![
+                    ("left", left.to_json()),
+                    ("right", right.to_json()),
+                    ("criteria", criteria.to_json()),
+                    ("blend", string(blend.name())),
+                ],
+            ),
+            NodeDesc::ScaleInput {
+                ref source,
+                ref scale,
+            } => node(
+                "scale_input",
+                vec![("source", source.to_json()), ("scale", numbers(scale))],
+            ),
+            NodeDesc::ShiftInput {
+                ref source,
+                ref shift,
+            } => node(
+                "shift_input",
+                vec![("source", source.to_json()), ("shift", numbers(shift))],
+            ),
+            NodeDesc::ClampInput {
+                ref source,
+                ref low,
+                ref high,
+            } => node(
+                "clamp_input",
+                vec![
+                    ("source", source.to_json()),
+                    ("low", numbers(low)),
+                    ("high", numbers(high)),
+                ],
+            ),
+            NodeDesc::WrapInput {
+                ref source,
+                ref low,
+                ref high,
+            } => node(
+                "wrap_input",
+                vec![
+                    ("source", source.to_json()),
+                    ("low", numbers(low)),
+                    ("high", numbers(high)),
+                ],
+            ),
+            NodeDesc::Slice {
+                ref source,
+                position,
+            } => node(
+                "slice",
+                vec![
+                    ("source", source.to_json()),
+                    ("position", Value::Number(position)),
+                ],
+            ),
+            NodeDesc::Extend(ref source) => node("extend", vec![("source", source.to_json())]),
+        }
+    }
+}
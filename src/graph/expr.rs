@@ -0,0 +1,845 @@
+//! A small expression language for composing noise, e.g.
+//! `clamp(fbm3(seed=4, octaves=6) * 0.7 + perlin2(freq=8), -1, 1)`.
+//!
+//! Expressions compile to a `NodeDesc`, so they build into the same adapters as JSON graphs.
+//! Arithmetic with a constant operand becomes `Scale`/`ScaleBias`, and constant subexpressions
+//! are folded. `perlin` and `fbm` may carry a dimension suffix; a source with fewer dimensions
+//! than the expression is extended along the ignored axes, one with more is sliced at 0.
+
+use std::f64;
+
+use graph::desc::{BinaryOp, Interpolation, MapFn, NodeDesc, RotationDesc};
+use graph::GraphError;
+use noise::{DynNoise1d, DynNoise2d, DynNoise3d};
+
+/// Parses `text` into the description of a graph with `dims` dimensions.
+pub fn parse(text: &str, dims: usize) -> Result<NodeDesc, GraphError> {
+    if !(1..=3).contains(&dims) {
+        return Err(GraphError::Unsupported {
+            node: "expression".to_string(),
+            message: format!("Expressions support 1 to 3 dimensions, not {}", dims),
+        });
+    }
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        index: 0,
+        depth: 0,
+    };
+    let expr = parser.expression()?;
+    if *parser.peek() != Token::End {
+        return Err(parser.pos().error("Expected an operator or the end of the expression"));
+    }
+    Lowering { dims }.node(&expr)
+}
+
+pub fn compile_1d(text: &str) -> Result<DynNoise1d, GraphError> {
+    parse(text, 1)?.build_1d()
+}
+pub fn compile_2d(text: &str) -> Result<DynNoise2d, GraphError> {
+    parse(text, 2)?.build_2d()
+}
+pub fn compile_3d(text: &str) -> Result<DynNoise3d, GraphError> {
+    parse(text, 3)?.build_3d()
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+impl Position {
+    fn error<S: Into<String>>(self, message: S) -> GraphError {
+        GraphError::Parse {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    LParen,
+    RParen,
+    Comma,
+    Equals,
+    Op(char),
+    End,
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, Position)>, GraphError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = Position { line: 1, column: 1 };
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = pos;
+        let mut len = 1;
+        let token = match c {
+            '\n' => {
+                pos.line += 1;
+                pos.column = 1;
+                i += 1;
+                continue;
+            }
+            c if c.is_whitespace() => None,
+            '(' => Some(Token::LParen),
+            ')' => Some(Token::RParen),
+            ',' => Some(Token::Comma),
+            '=' => Some(Token::Equals),
+            '+' | '-' | '*' | '/' | '^' => Some(Token::Op(c)),
+            c if c.is_ascii_digit() || c == '.' => {
+                len = number_length(&chars[i..]);
+                let literal: String = chars[i..i + len].iter().collect();
+                match literal.parse() {
+                    Ok(n) => Some(Token::Number(n)),
+                    Err(_) => return Err(start.error(format!("Invalid number '{}'", literal))),
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                len = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_')
+                    .count();
+                Some(Token::Ident(chars[i..i + len].iter().collect()))
+            }
+            c => return Err(start.error(format!("Unexpected character '{}'", c))),
+        };
+        if let Some(token) = token {
+            tokens.push((token, start));
+        }
+        i += len;
+        pos.column += len;
+    }
+    tokens.push((Token::End, pos));
+    Ok(tokens)
+}
+
+fn number_length(chars: &[char]) -> usize {
+    let mut len = chars
+        .iter()
+        .take_while(|c| c.is_ascii_digit() || **c == '.')
+        .count();
+    if let Some(&'e') | Some(&'E') = chars.get(len) {
+        let sign = match chars.get(len + 1) {
+            Some(&'+') | Some(&'-') => 1,
+            _ => 0,
+        };
+        let digits = chars[len + 1 + sign..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if digits > 0 {
+            len += 1 + sign + digits;
+        }
+    }
+    len
+}
+
+#[derive(Clone, Debug)]
+enum ExprKind {
+    Number(f64),
+    Ident(String),
+    Call { name: String, args: Vec<Arg> },
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+/// A parsed expression, positioned at its first character.
+#[derive(Clone, Debug)]
+struct Expr {
+    kind: ExprKind,
+    pos: Position,
+}
+
+#[derive(Clone, Debug)]
+struct Arg {
+    name: Option<(String, Position)>,
+    value: Expr,
+}
+
+//Parsing, lowering and dropping expressions all recurse, so deeper nesting is rejected before
+//it can overflow the stack.
+const MAX_DEPTH: usize = 100;
+
+struct Parser {
+    tokens: Vec<(Token, Position)>,
+    index: usize,
+    //Bounds the depth of the expression tree parsed so far.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.index].0
+    }
+    fn pos(&self) -> Position {
+        self.tokens[self.index].1
+    }
+    fn advance(&mut self) -> (Token, Position) {
+        let token = self.tokens[self.index].clone();
+        if token.0 != Token::End {
+            self.index += 1;
+        }
+        token
+    }
+
+    fn nest(&mut self) -> Result<(), GraphError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.pos().error("Expression is nested too deeply"));
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self) -> Result<Expr, GraphError> {
+        //Each operator of a chain deepens the left operand.
+        let depth = self.depth;
+        let mut left = self.term()?;
+        loop {
+            let op = match *self.peek() {
+                Token::Op('+') => BinaryOp::Add,
+                Token::Op('-') => BinaryOp::Subtract,
+                _ => {
+                    self.depth = depth;
+                    return Ok(left);
+                }
+            };
+            self.nest()?;
+            self.advance();
+            left = binary_expr(op, left, self.term()?);
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, GraphError> {
+        //Each operator of a chain deepens the left operand.
+        let depth = self.depth;
+        let mut left = self.unary()?;
+        loop {
+            let op = match *self.peek() {
+                Token::Op('*') => BinaryOp::Multiply,
+                Token::Op('/') => BinaryOp::Divide,
+                _ => {
+                    self.depth = depth;
+                    return Ok(left);
+                }
+            };
+            self.nest()?;
+            self.advance();
+            left = binary_expr(op, left, self.unary()?);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, GraphError> {
+        self.nest()?;
+        let expr = match *self.peek() {
+            Token::Op('-') => {
+                let pos = self.advance().1;
+                Expr {
+                    kind: ExprKind::Neg(Box::new(self.unary()?)),
+                    pos,
+                }
+            }
+            Token::Op('+') => {
+                self.advance();
+                self.unary()?
+            }
+            _ => self.power()?,
+        };
+        self.depth -= 1;
+        Ok(expr)
+    }
+
+    fn power(&mut self) -> Result<Expr, GraphError> {
+        let base = self.primary()?;
+        if *self.peek() == Token::Op('^') {
+            self.advance();
+            //Right associative, and binds tighter than a leading minus: -a^b = -(a^b).
+            let exponent = self.unary()?;
+            return Ok(binary_expr(BinaryOp::Power, base, exponent));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr, GraphError> {
+        let (token, pos) = self.advance();
+        let kind = match token {
+            Token::Number(n) => ExprKind::Number(n),
+            Token::Ident(name) => {
+                if *self.peek() == Token::LParen {
+                    self.advance();
+                    ExprKind::Call {
+                        name,
+                        args: self.arguments()?,
+                    }
+                } else {
+                    ExprKind::Ident(name)
+                }
+            }
+            Token::LParen => {
+                let inner = self.expression()?;
+                self.expect(&Token::RParen, "Expected ')'")?;
+                return Ok(Expr { pos, ..inner });
+            }
+            Token::End => return Err(pos.error("Unexpected end of expression")),
+            _ => return Err(pos.error("Expected a number, a function call or '('")),
+        };
+        Ok(Expr { kind, pos })
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Arg>, GraphError> {
+        let mut args = Vec::new();
+        if *self.peek() == Token::RParen {
+            self.advance();
+            return Ok(args);
+        }
+        loop {
+            let next = self.tokens.get(self.index + 1).map(|t| &t.0);
+            let name = match (self.peek().clone(), next) {
+                (Token::Ident(name), Some(&Token::Equals)) => {
+                    let pos = self.pos();
+                    self.index += 2;
+                    Some((name, pos))
+                }
+                _ => None,
+            };
+            args.push(Arg {
+                name,
+                value: self.expression()?,
+            });
+            match self.advance() {
+                (Token::Comma, _) => continue,
+                (Token::RParen, _) => return Ok(args),
+                (_, pos) => return Err(pos.error("Expected ',' or ')'")),
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: &Token, message: &str) -> Result<(), GraphError> {
+        if self.peek() == expected {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.pos().error(message))
+        }
+    }
+}
+
+fn binary_expr(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+    Expr {
+        pos: left.pos,
+        kind: ExprKind::Binary(op, Box::new(left), Box::new(right)),
+    }
+}
+
+/// Folds constant operands into `Scale` and `ScaleBias` nodes.
+fn binary(op: BinaryOp, left: NodeDesc, right: NodeDesc) -> NodeDesc {
+    match (op, left, right) {
+        (op, NodeDesc::Constant(l), NodeDesc::Constant(r)) => NodeDesc::Constant(op.apply(l, r)),
+        (BinaryOp::Add, n, NodeDesc::Constant(c)) | (BinaryOp::Add, NodeDesc::Constant(c), n) => {
+            NodeDesc::ScaleBias {
+                source: Box::new(n),
+                scale: 1.0,
+                bias: c,
+            }
+        }
+        (BinaryOp::Subtract, n, NodeDesc::Constant(c)) => NodeDesc::ScaleBias {
+            source: Box::new(n),
+            scale: 1.0,
+            bias: -c,
+        },
+        (BinaryOp::Subtract, NodeDesc::Constant(c), n) => NodeDesc::ScaleBias {
+            source: Box::new(n),
+            scale: -1.0,
+            bias: c,
+        },
+        (BinaryOp::Multiply, n, NodeDesc::Constant(c))
+        | (BinaryOp::Multiply, NodeDesc::Constant(c), n) => NodeDesc::Scale {
+            source: Box::new(n),
+            amplitude: c,
+        },
+        (BinaryOp::Divide, n, NodeDesc::Constant(c)) => NodeDesc::Scale {
+            source: Box::new(n),
+            amplitude: 1.0 / c,
+        },
+        (op, left, right) => NodeDesc::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        },
+    }
+}
+
+/// Splits the dimension suffix off `perlin2`, `fbm3` etc.
+fn split_dims(name: &str) -> (&str, Option<usize>) {
+    let (base, suffix) = name.split_at(name.len().saturating_sub(1));
+    match suffix {
+        "1" | "2" | "3" if base == "perlin" || base == "fbm" => {
+            (base, suffix.parse().ok())
+        }
+        _ => (name, None),
+    }
+}
+
+/// The arguments of a call matched against a function's parameters.
+struct Bound<'a> {
+    function: &'a str,
+    call: Position,
+    params: &'static [&'static str],
+    values: Vec<Option<&'a Expr>>,
+}
+
+fn bind<'a>(
+    function: &'a str,
+    call: Position,
+    params: &'static [&'static str],
+    args: &'a [Arg],
+) -> Result<Bound<'a>, GraphError> {
+    let mut values = vec![None; params.len()];
+    let mut next_positional = 0;
+    for arg in args {
+        let (index, pos) = match arg.name {
+            Some((ref name, pos)) => match params.iter().position(|p| p == name) {
+                Some(index) => (index, pos),
+                None => {
+                    return Err(pos.error(format!(
+                        "Unknown argument '{}' for '{}', expected one of: {}",
+                        name,
+                        function,
+                        params.join(", ")
+                    )))
+                }
+            },
+            None => {
+                next_positional += 1;
+                if next_positional > params.len() {
+                    return Err(arg.value.pos.error(format!(
+                        "Too many arguments for '{}', expected at most {}",
+                        function,
+                        params.len()
+                    )));
+                }
+                (next_positional - 1, arg.value.pos)
+            }
+        };
+        if values[index].is_some() {
+            return Err(pos.error(format!("Argument '{}' given more than once", params[index])));
+        }
+        values[index] = Some(&arg.value);
+    }
+    Ok(Bound {
+        function,
+        call,
+        params,
+        values,
+    })
+}
+
+/// Positional arguments of a function that takes any number of them.
+fn variadic<'a>(function: &str, call: Position, args: &'a [Arg]) -> Result<Vec<&'a Expr>, GraphError> {
+    if let Some(&(ref name, pos)) = args.iter().filter_map(|a| a.name.as_ref()).next() {
+        return Err(pos.error(format!("Unknown argument '{}' for '{}'", name, function)));
+    }
+    if args.is_empty() {
+        return Err(call.error(format!("'{}' expects at least one argument", function)));
+    }
+    Ok(args.iter().map(|a| &a.value).collect())
+}
+
+impl<'a> Bound<'a> {
+    fn arg(&self, param: &str) -> Option<&'a Expr> {
+        let index = self.params
+            .iter()
+            .position(|&p| p == param)
+            .expect("Unknown parameter");
+        self.values[index]
+    }
+
+    fn expr(&self, param: &str) -> Result<&'a Expr, GraphError> {
+        self.arg(param).ok_or_else(|| {
+            self.call.error(format!(
+                "Missing argument '{}' for '{}'",
+                param, self.function
+            ))
+        })
+    }
+
+    fn node(&self, l: &Lowering, param: &str) -> Result<Box<NodeDesc>, GraphError> {
+        l.node(self.expr(param)?).map(Box::new)
+    }
+
+    fn number(&self, l: &Lowering, param: &str) -> Result<f64, GraphError> {
+        l.number(self.expr(param)?)
+    }
+
+    fn number_or(&self, l: &Lowering, param: &str, default: f64) -> Result<f64, GraphError> {
+        match self.arg(param) {
+            Some(expr) => l.number(expr),
+            None => Ok(default),
+        }
+    }
+
+    fn check(&self, param: &str, valid: bool, message: &str) -> Result<(), GraphError> {
+        match self.arg(param) {
+            Some(expr) if !valid => Err(expr.pos.error(format!(
+                "Argument '{}' of '{}' {}",
+                param, self.function, message
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn positive_or(&self, l: &Lowering, param: &str, default: f64) -> Result<f64, GraphError> {
+        let value = self.number_or(l, param, default)?;
+        self.check(param, value > 0.0, "must be positive")?;
+        Ok(value)
+    }
+
+    fn uint_or(&self, l: &Lowering, param: &str, default: u32) -> Result<u32, GraphError> {
+        let value = self.number_or(l, param, f64::from(default))?;
+        let valid = value >= 0.0 && value <= f64::from(u32::MAX) && value.fract() == 0.0;
+        self.check(param, valid, "must be a non-negative integer")?;
+        Ok(value as u32)
+    }
+
+    /// Two numbers where the first must be less than the second.
+    fn ordered(
+        &self,
+        l: &Lowering,
+        low: &str,
+        high: &str,
+        defaults: Option<(f64, f64)>,
+    ) -> Result<(f64, f64), GraphError> {
+        let (low_value, high_value) = match defaults {
+            Some((low_default, high_default)) => (
+                self.number_or(l, low, low_default)?,
+                self.number_or(l, high, high_default)?,
+            ),
+            None => (self.number(l, low)?, self.number(l, high)?),
+        };
+        if low_value < high_value {
+            return Ok((low_value, high_value));
+        }
+        let pos = self.arg(low).or_else(|| self.arg(high)).map_or(self.call, |e| e.pos);
+        Err(pos.error(format!(
+            "Argument '{}' of '{}' must be less than '{}'",
+            low, self.function, high
+        )))
+    }
+
+    fn interpolation_or(
+        &self,
+        param: &str,
+        default: Interpolation,
+    ) -> Result<Interpolation, GraphError> {
+        match self.arg(param) {
+            Some(&Expr {
+                kind: ExprKind::Ident(ref name),
+                ..
+            }) if Interpolation::from_name(name).is_some() => {
+                Ok(Interpolation::from_name(name).unwrap())
+            }
+            Some(expr) => Err(expr.pos.error(format!(
+                "Argument '{}' of '{}' must be linear, hermite3 or hermite5",
+                param, self.function
+            ))),
+            None => Ok(default),
+        }
+    }
+}
+
+struct Lowering {
+    dims: usize,
+}
+
+impl Lowering {
+    fn node(&self, expr: &Expr) -> Result<NodeDesc, GraphError> {
+        match expr.kind {
+            ExprKind::Number(n) => Ok(NodeDesc::Constant(n)),
+            ExprKind::Ident(ref name) => Err(expr.pos.error(format!("Unknown identifier '{}'", name))),
+            ExprKind::Neg(ref inner) => Ok(match self.node(inner)? {
+                NodeDesc::Constant(n) => NodeDesc::Constant(-n),
+                node => NodeDesc::Negate(Box::new(node)),
+            }),
+            ExprKind::Binary(op, ref left, ref right) => {
+                Ok(binary(op, self.node(left)?, self.node(right)?))
+            }
+            ExprKind::Call { ref name, ref args } => self.call(expr.pos, name, args),
+        }
+    }
+
+    fn number(&self, expr: &Expr) -> Result<f64, GraphError> {
+        match self.node(expr)? {
+            NodeDesc::Constant(n) => Ok(n),
+            _ => Err(expr.pos.error("Expected a constant number")),
+        }
+    }
+
+    fn numbers(&self, exprs: &[&Expr]) -> Result<Vec<f64>, GraphError> {
+        exprs.iter().map(|e| self.number(e)).collect()
+    }
+
+    fn axes(&self, function: &str, call: Position, exprs: &[&Expr]) -> Result<Vec<f64>, GraphError> {
+        if exprs.len() != 1 && exprs.len() != self.dims {
+            return Err(call.error(format!(
+                "'{}' expects a source and 1 or {} values",
+                function, self.dims
+            )));
+        }
+        self.numbers(exprs)
+    }
+
+    fn call(&self, pos: Position, name: &str, args: &[Arg]) -> Result<NodeDesc, GraphError> {
+        if let Some(function) = MapFn::from_name(name) {
+            let b = bind(name, pos, &["x"], args)?;
+            return Ok(NodeDesc::Map {
+                source: b.node(self, "x")?,
+                function,
+            });
+        }
+
+        let (base, dims) = split_dims(name);
+        let desc = match base {
+            "perlin" => {
+                let b = bind(name, pos, &["freq", "seed", "interp"], args)?;
+                NodeDesc::Perlin {
+                    frequency: vec![b.positive_or(self, "freq", 1.0)?],
+                    seed: b.uint_or(self, "seed", 0)?,
+                    interpolation: b.interpolation_or("interp", Interpolation::Hermite5)?,
+                }
+            }
+            "fbm" => {
+                let b = bind(
+                    name,
+                    pos,
+                    &[
+                        "freq",
+                        "seed",
+                        "octaves",
+                        "lacunarity",
+                        "persistance",
+                        "rotation",
+                        "interp",
+                    ],
+                    args,
+                )?;
+                let octaves = b.uint_or(self, "octaves", 8)?;
+                b.check("octaves", octaves > 0, "must be at least 1")?;
+                NodeDesc::Fbm {
+                    frequency: vec![b.positive_or(self, "freq", 1.0)?],
+                    seed: b.uint_or(self, "seed", 0)?,
+                    octaves,
+                    frequency_scaling: vec![b.positive_or(self, "lacunarity", 2.0)?],
+                    persistance: b.positive_or(self, "persistance", 2.0)?,
+                    interpolation: b.interpolation_or("interp", Interpolation::Hermite5)?,
+                    rotation: match b.arg("rotation") {
+                        Some(_) => RotationDesc::Seeded(b.uint_or(self, "rotation", 0)?),
+                        None => RotationDesc::Aligned,
+                    },
+                }
+            }
+            "clamp" => {
+                let b = bind(name, pos, &["x", "low", "high"], args)?;
+                let (low, high) = b.ordered(self, "low", "high", None)?;
+                NodeDesc::Clamp {
+                    source: b.node(self, "x")?,
+                    low,
+                    high,
+                }
+            }
+            "range" => {
+                let b = bind(name, pos, &["x", "min", "max"], args)?;
+                let (min, max) = b.ordered(self, "min", "max", None)?;
+                NodeDesc::WithRange {
+                    source: b.node(self, "x")?,
+                    min,
+                    max,
+                }
+            }
+            "invert" => {
+                let b = bind(name, pos, &["x", "min", "max"], args)?;
+                let (min, max) = b.ordered(self, "min", "max", Some((-1.0, 1.0)))?;
+                NodeDesc::InvertRange {
+                    source: b.node(self, "x")?,
+                    min,
+                    max,
+                }
+            }
+            "scale_bias" => {
+                let b = bind(name, pos, &["x", "scale", "bias"], args)?;
+                NodeDesc::ScaleBias {
+                    source: b.node(self, "x")?,
+                    scale: b.number(self, "scale")?,
+                    bias: b.number_or(self, "bias", 0.0)?,
+                }
+            }
+            "min" | "max" | "pow" => {
+                let b = bind(name, pos, &["a", "b"], args)?;
+                let op = match base {
+                    "min" => BinaryOp::Min,
+                    "max" => BinaryOp::Max,
+                    _ => BinaryOp::Power,
+                };
+                binary(op, *b.node(self, "a")?, *b.node(self, "b")?)
+            }
+            "abs" => NodeDesc::Abs(bind(name, pos, &["x"], args)?.node(self, "x")?),
+            "exponent" => {
+                let b = bind(name, pos, &["x", "exponent"], args)?;
+                NodeDesc::Exponent {
+                    source: b.node(self, "x")?,
+                    exponent: b.number(self, "exponent")?,
+                }
+            }
+            "select" => {
+                let b = bind(
+                    name,
+                    pos,
+                    &["a", "b", "criteria", "lower", "upper", "falloff"],
+                    args,
+                )?;
+                let lower = b.number(self, "lower")?;
+                let upper = b.number_or(self, "upper", f64::INFINITY)?;
+                b.check("upper", lower < upper, "must be greater than 'lower'")?;
+                let falloff = b.number_or(self, "falloff", 0.0)?;
+                b.check("falloff", falloff >= 0.0, "must not be negative")?;
+                NodeDesc::Select {
+                    left: b.node(self, "a")?,
+                    right: b.node(self, "b")?,
+                    criteria: b.node(self, "criteria")?,
+                    lower,
+                    upper,
+                    falloff,
+                }
+            }
+            "blend" => {
+                let b = bind(name, pos, &["a", "b", "t", "curve"], args)?;
+                NodeDesc::Blend {
+                    left: b.node(self, "a")?,
+                    right: b.node(self, "b")?,
+                    criteria: b.node(self, "t")?,
+                    blend: b.interpolation_or("curve", Interpolation::Linear)?,
+                }
+            }
+            "terrace" => {
+                let exprs = variadic(name, pos, args)?;
+                let points = self.numbers(&exprs[1..])?;
+                if points.len() < 2 {
+                    return Err(pos.error("'terrace' expects a source and at least two points"));
+                }
+                NodeDesc::Terrace {
+                    source: Box::new(self.node(exprs[0])?),
+                    points,
+                    inverted: false,
+                    smoothness: 0.0,
+                }
+            }
+            "sum" | "product" => {
+                let sources = variadic(name, pos, args)?
+                    .into_iter()
+                    .map(|e| self.node(e))
+                    .collect::<Result<_, _>>()?;
+                if base == "sum" {
+                    NodeDesc::Sum(sources)
+                } else {
+                    NodeDesc::Product(sources)
+                }
+            }
+            "scale_input" | "shift_input" => {
+                let exprs = variadic(name, pos, args)?;
+                let source = Box::new(self.node(exprs[0])?);
+                let values = self.axes(name, pos, &exprs[1..])?;
+                if base == "scale_input" {
+                    NodeDesc::ScaleInput {
+                        source,
+                        scale: values,
+                    }
+                } else {
+                    NodeDesc::ShiftInput {
+                        source,
+                        shift: values,
+                    }
+                }
+            }
+            _ => return Err(pos.error(format!("Unknown function '{}'", name))),
+        };
+        Ok(match dims {
+            Some(dims) => self.adapt(desc, dims),
+            None => desc,
+        })
+    }
+
+    /// Extends or slices a source built for `dims` dimensions to the expression's dimension.
+    fn adapt(&self, mut desc: NodeDesc, mut dims: usize) -> NodeDesc {
+        while dims < self.dims {
+            desc = NodeDesc::Extend(Box::new(desc));
+            dims += 1;
+        }
+        while dims > self.dims {
+            desc = NodeDesc::Slice {
+                source: Box::new(desc),
+                position: 0.0,
+            };
+            dims -= 1;
+        }
+        desc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noise::Noise;
+
+    fn error_position(text: &str) -> (usize, usize) {
+        match parse(text, 2) {
+            Err(GraphError::Parse { line, column, .. }) => (line, column),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn folds_constants() {
+        assert_eq!(parse("-2 * (1 + 3) ^ 2 / 4", 1).unwrap(), NodeDesc::Constant(-8.0));
+    }
+
+    #[test]
+    fn compiles_mixed_dimensions() {
+        let text = "clamp(fbm3(seed=4, octaves=6) * 0.7 + perlin2(freq=8), -1, 1)";
+        let noise = compile_2d(text).unwrap();
+        for i in 0..20 {
+            let v = noise.value_at([10.0 + i as f64 * 0.37, 20.0]);
+            assert!((-1.0..=1.0).contains(&v));
+        }
+        assert!(compile_3d(text).is_ok());
+    }
+
+    #[test]
+    fn reports_columns() {
+        assert_eq!(error_position("abs(fbm4(seed=1))"), (1, 5));
+        assert_eq!(error_position("fbm(octaves=0)"), (1, 13));
+        assert_eq!(error_position("perlin(fre=2)"), (1, 8));
+        assert_eq!(error_position("clamp(perlin(), 1,\n 0)"), (1, 17));
+        assert_eq!(error_position("perlin() + "), (1, 12));
+    }
+
+    #[test]
+    fn rejects_deep_nesting_and_bad_dimensions() {
+        for &(open, close) in &[("(", ")"), ("abs(", ")"), ("-", ""), ("1+", "")] {
+            let text = format!("{}1{}", open.repeat(100_000), close.repeat(100_000));
+            match parse(&text, 1) {
+                Err(GraphError::Parse { line, column, .. }) => {
+                    assert_eq!(line, 1);
+                    assert!(column > MAX_DEPTH / 2 && column < MAX_DEPTH * open.len() + 2);
+                }
+                other => panic!("expected a parse error, got {:?}", other),
+            }
+        }
+        let nested = format!("{}1{}", "(".repeat(50), ")".repeat(50));
+        assert_eq!(parse(&nested, 1).unwrap(), NodeDesc::Constant(1.0));
+        assert!(parse("1", 0).is_err() && parse("1", 4).is_err());
+    }
+}
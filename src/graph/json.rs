@@ -0,0 +1,231 @@
+//! The document model of noise graph descriptions, read and written as JSON or RON through serde.
+
+use std::f64;
+use std::fmt;
+
+use ron;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeMap, SerializeSeq, Serializer};
+use serde_json;
+
+use graph::GraphError;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// Non-finite numbers are written as the strings "inf", "-inf" and "NaN", which neither
+    /// format can otherwise represent portably.
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// Members are kept in document order so saved files are stable.
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn parse(text: &str) -> Result<Value, GraphError> {
+        serde_json::from_str(text).map_err(|e| {
+            //serde_json appends the position to its messages, which `GraphError` reports itself.
+            let message = e.to_string();
+            GraphError::Parse {
+                line: e.line(),
+                column: e.column(),
+                message: message.split(" at line ").next().unwrap_or("").to_string(),
+            }
+        })
+    }
+
+    pub fn parse_ron(text: &str) -> Result<Value, GraphError> {
+        ron::from_str(text).map_err(|e: ron::error::SpannedError| GraphError::Parse {
+            line: e.position.line,
+            column: e.position.col,
+            message: e.code.to_string(),
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match *self {
+            Value::Object(ref members) => members.iter().find(|m| m.0 == key).map(|m| &m.1),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Number(n) => Some(n),
+            Value::String(ref s) => match s.as_str() {
+                "inf" => Some(f64::INFINITY),
+                "-inf" => Some(f64::NEG_INFINITY),
+                "NaN" => Some(f64::NAN),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match *self {
+            Value::Array(ref values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Formats the value as JSON with two space indentation.
+    pub fn to_string_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Values always serialize")
+    }
+
+    /// Formats the value as pretty printed RON.
+    pub fn to_ron_string(&self) -> String {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::new())
+            .expect("Values always serialize")
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&text)
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Value::Null => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(b),
+            Value::Number(n) if n.is_finite() => serializer.serialize_f64(n),
+            Value::Number(n) if n.is_nan() => serializer.serialize_str("NaN"),
+            Value::Number(n) if n > 0.0 => serializer.serialize_str("inf"),
+            Value::Number(_) => serializer.serialize_str("-inf"),
+            Value::String(ref s) => serializer.serialize_str(s),
+            Value::Array(ref values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for v in values {
+                    seq.serialize_element(v)?;
+                }
+                seq.end()
+            }
+            Value::Object(ref members) => {
+                let mut map = serializer.serialize_map(Some(members.len()))?;
+                for (key, v) in members {
+                    map.serialize_entry(key, v)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number, string, boolean, array or object")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<Value, E> {
+        Ok(Value::Number(n as f64))
+    }
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Value, E> {
+        Ok(Value::Number(n as f64))
+    }
+    fn visit_f64<E: de::Error>(self, n: f64) -> Result<Value, E> {
+        Ok(Value::Number(n))
+    }
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::String(s.to_string()))
+    }
+    fn visit_string<E: de::Error>(self, s: String) -> Result<Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = Vec::new();
+        while let Some(v) = seq.next_element()? {
+            values.push(v);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut members: Vec<(String, Value)> = Vec::new();
+        while let Some((key, v)) = map.next_entry::<String, Value>()? {
+            if members.iter().any(|m| m.0 == key) {
+                return Err(de::Error::custom(format!("duplicate key '{}'", key)));
+            }
+            members.push((key, v));
+        }
+        Ok(Value::Object(members))
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_strings_and_rejects_deep_nesting() {
+        let value = Value::parse(r#"{"name": "\ud83c\udf0b \u00e9", "n": [1, -2.5e3]}"#).unwrap();
+        assert_eq!(value.get("name").and_then(Value::as_str), Some("\u{1f30b} \u{e9}"));
+        assert_eq!(
+            value.get("n"),
+            Some(&Value::Array(vec![Value::Number(1.0), Value::Number(-2500.0)]))
+        );
+        assert!(Value::parse(r#"{"a": 1, "a": 2}"#).is_err());
+
+        let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(Value::parse(&deep).is_err());
+        assert!(Value::parse_ron(&deep).is_err());
+    }
+
+    #[test]
+    fn non_finite_numbers_round_trip() {
+        let value = Value::Array(vec![
+            Value::Number(f64::INFINITY),
+            Value::Number(f64::NEG_INFINITY),
+            Value::Number(0.5),
+        ]);
+        for parsed in &[
+            Value::parse(&value.to_string_pretty()).unwrap(),
+            Value::parse_ron(&value.to_ron_string()).unwrap(),
+        ] {
+            let numbers: Vec<_> = parsed.as_array().unwrap().iter().map(Value::as_f64).collect();
+            assert_eq!(numbers, vec![Some(f64::INFINITY), Some(f64::NEG_INFINITY), Some(0.5)]);
+        }
+        let nan = Value::parse(&Value::Number(f64::NAN).to_string()).unwrap();
+        assert!(nan.as_f64().unwrap().is_nan());
+    }
+}
@@ -0,0 +1,190 @@
+//! Noise graph descriptions that can be loaded from and saved to JSON or RON, and built into
+//! `DynNoise` objects at runtime.
+
+pub mod build;
+pub mod desc;
+pub mod expr;
+pub mod json;
+pub mod tape;
+
+pub use self::desc::{BinaryOp, Interpolation, MapFn, NodeDesc, RotationDesc};
+pub use self::tape::{Tape, Tape1d, Tape2d, Tape3d};
+
+use std::error::Error;
+use std::fmt;
+
+use noise::{DynNoise1d, DynNoise2d, DynNoise3d};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphError {
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    UnknownNode(String),
+    MissingField {
+        node: String,
+        field: String,
+    },
+    InvalidField {
+        node: String,
+        field: String,
+        message: String,
+    },
+    Unsupported {
+        node: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GraphError::Parse {
+                line,
+                column,
+                ref message,
+            } => write!(f, "{} at line {}, column {}", message, line, column),
+            GraphError::UnknownNode(ref node) => write!(f, "Unknown node type '{}'", node),
+            GraphError::MissingField {
+                ref node,
+                ref field,
+            } => write!(f, "Node '{}' is missing field '{}'", node, field),
+            GraphError::InvalidField {
+                ref node,
+                ref field,
+                ref message,
+            } => write!(f, "Invalid field '{}' of node '{}': {}", field, node, message),
+            GraphError::Unsupported {
+                ref node,
+                ref message,
+            } => write!(f, "Unsupported node '{}': {}", node, message),
+        }
+    }
+}
+
+impl Error for GraphError {}
+
+fn invalid(node: &str, field: &str, message: &str) -> GraphError {
+    GraphError::InvalidField {
+        node: node.to_string(),
+        field: field.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn ordered(node: &str, low_field: &str, low: f64, high: f64) -> Result<(), GraphError> {
+    if low < high {
+        Ok(())
+    } else {
+        Err(invalid(node, low_field, "must be less than its upper bound"))
+    }
+}
+
+pub fn load_1d(text: &str) -> Result<DynNoise1d, GraphError> {
+    NodeDesc::from_json_str(text)?.build_1d()
+}
+pub fn load_2d(text: &str) -> Result<DynNoise2d, GraphError> {
+    NodeDesc::from_json_str(text)?.build_2d()
+}
+pub fn load_3d(text: &str) -> Result<DynNoise3d, GraphError> {
+    NodeDesc::from_json_str(text)?.build_3d()
+}
+
+pub fn save(desc: &NodeDesc) -> String {
+    desc.to_json_string()
+}
+
+pub fn load_1d_ron(text: &str) -> Result<DynNoise1d, GraphError> {
+    NodeDesc::from_ron_str(text)?.build_1d()
+}
+pub fn load_2d_ron(text: &str) -> Result<DynNoise2d, GraphError> {
+    NodeDesc::from_ron_str(text)?.build_2d()
+}
+pub fn load_3d_ron(text: &str) -> Result<DynNoise3d, GraphError> {
+    NodeDesc::from_ron_str(text)?.build_3d()
+}
+
+pub fn save_ron(desc: &NodeDesc) -> String {
+    desc.to_ron_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noise::Noise;
+
+    const GRAPH: &str = r#"{
+        "type": "add",
+        "left": {"type": "fbm", "frequency": [0.05], "seed": 7, "octaves": 4},
+        "right": {"type": "scale", "source": {"type": "constant", "value": 1.5}, "amplitude": 2.0}
+    }"#;
+
+    #[test]
+    fn round_trip() {
+        let desc = NodeDesc::from_json_str(GRAPH).unwrap();
+        let saved = save(&desc);
+        assert_eq!(NodeDesc::from_json_str(&saved).unwrap(), desc);
+
+        let a = load_2d(GRAPH).unwrap();
+        let b = load_2d(&saved).unwrap();
+        assert_eq!(a.value_at([10.5, 20.25]), b.value_at([10.5, 20.25]));
+    }
+
+    #[test]
+    fn round_trip_ron_and_non_finite_bounds() {
+        let desc = NodeDesc::from_json_str(GRAPH).unwrap();
+        let saved = save_ron(&desc);
+        assert_eq!(NodeDesc::from_ron_str(&saved).unwrap(), desc);
+        let a = load_2d(GRAPH).unwrap();
+        let b = load_2d_ron(&saved).unwrap();
+        assert_eq!(a.value_at([10.5, 20.25]), b.value_at([10.5, 20.25]));
+
+        let clamp = r#"{"type": "clamp", "source": {"type": "constant", "value": 3}, "low": 0, "high": "inf"}"#;
+        let desc = NodeDesc::from_json_str(clamp).unwrap();
+        assert_eq!(NodeDesc::from_json_str(&save(&desc)).unwrap(), desc);
+        assert_eq!(NodeDesc::from_ron_str(&save_ron(&desc)).unwrap(), desc);
+        assert_eq!(load_1d(clamp).unwrap().value_at(0.0), 3.0);
+
+        //Only the default upper edge of a select may be left out of the saved graph.
+        let constant = |value| Box::new(NodeDesc::Constant(value));
+        for &upper in &[f64::INFINITY, f64::NEG_INFINITY, f64::NAN, 2.0] {
+            let desc = NodeDesc::Select {
+                left: constant(1.0),
+                right: constant(2.0),
+                criteria: constant(0.5),
+                lower: 0.0,
+                upper,
+                falloff: 0.0,
+            };
+            for loaded in &[
+                NodeDesc::from_json_str(&save(&desc)).unwrap(),
+                NodeDesc::from_ron_str(&save_ron(&desc)).unwrap(),
+            ] {
+                match *loaded {
+                    NodeDesc::Select { upper: loaded, .. } => {
+                        assert!(loaded == upper || (loaded.is_nan() && upper.is_nan()))
+                    }
+                    ref other => panic!("unexpected node {:?}", other),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_excessive_octaves() {
+        match load_2d(r#"{"type": "fbm", "octaves": 1e9}"#) {
+            Err(GraphError::InvalidField { ref field, .. }) if field == "octaves" => {}
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn reports_position() {
+        match load_2d("{\n  \"type\": \"perlin\",\n  \"seed\" 3\n}") {
+            Err(GraphError::Parse { line, column, .. }) => assert_eq!((line, column), (3, 10)),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,711 @@
+//! Compiles graph descriptions into a flat instruction tape evaluated a row of points at a time.
+//!
+//! Value-only nodes (arithmetic, scaling, clamping, selection, ...) become instructions on a
+//! register machine where every register holds one value per point. Chains of affine nodes are
+//! fused, constant subexpressions are folded and identical subexpressions are computed once.
+//! Nodes that depend on the position (primitives, input transforms, and the remapping curves)
+//! are built as `DynNoise` sources sampled by a single instruction.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+
+use adapter::combine::select_value;
+use adapter::transform::exponent_curve;
+use graph::desc::{BinaryOp, Interpolation, MapFn, NodeDesc};
+use graph::{invalid, ordered, GraphError};
+use noise::{DynNoise, Noise, Point1, Point2, Point3};
+
+pub type Reg = usize;
+
+pub type Tape1d = Tape<Point1<f64>, f64>;
+pub type Tape2d = Tape<Point2<f64>, (f64, f64)>;
+pub type Tape3d = Tape<Point3<f64>, (f64, f64, f64)>;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum UnaryOp {
+    /// `value * scale + bias`; covers `Scale`, `ScaleBias`, `WithRange`, `InvertRange` and
+    /// `Negate`.
+    Affine { scale: f64, bias: f64 },
+    Clamp { low: f64, high: f64 },
+    Abs,
+    Exponent(f64),
+    Map(MapFn),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Op {
+    Const(f64),
+    /// Evaluates the source with the given index at every point.
+    Sample(usize),
+    Unary(UnaryOp, Reg),
+    Binary(BinaryOp, Reg, Reg),
+    Select {
+        left: Reg,
+        right: Reg,
+        criteria: Reg,
+        lower: f64,
+        upper: f64,
+        falloff: f64,
+    },
+    Blend {
+        left: Reg,
+        right: Reg,
+        criteria: Reg,
+        blend: Interpolation,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Instruction {
+    pub dst: Reg,
+    pub op: Op,
+}
+
+#[derive(Clone, Debug)]
+pub struct Tape<I, D> {
+    instructions: Vec<Instruction>,
+    sources: Vec<DynNoise<I, D>>,
+    registers: usize,
+    output: Reg,
+}
+
+impl UnaryOp {
+    pub fn apply(&self, value: f64) -> f64 {
+        match *self {
+            UnaryOp::Affine { scale, bias } => value * scale + bias,
+            UnaryOp::Clamp { low, high } => value.max(low).min(high),
+            UnaryOp::Abs => value.abs(),
+            UnaryOp::Exponent(exponent) => exponent_curve(value, exponent),
+            UnaryOp::Map(function) => function.apply(value),
+        }
+    }
+
+    fn apply_row(&self, src: &[f64], dst: &mut [f64]) {
+        //The common cases get their own loops so they don't branch per element.
+        match *self {
+            UnaryOp::Affine { scale, bias } => for (d, &s) in dst.iter_mut().zip(src) {
+                *d = s * scale + bias;
+            },
+            UnaryOp::Abs => for (d, &s) in dst.iter_mut().zip(src) {
+                *d = s.abs();
+            },
+            op => for (d, &s) in dst.iter_mut().zip(src) {
+                *d = op.apply(s);
+            },
+        }
+    }
+}
+
+fn binary_row(op: BinaryOp, left: &[f64], right: &[f64], dst: &mut [f64]) {
+    let values = dst.iter_mut().zip(left.iter().zip(right));
+    match op {
+        BinaryOp::Add => for (d, (&l, &r)) in values {
+            *d = l + r;
+        },
+        BinaryOp::Multiply => for (d, (&l, &r)) in values {
+            *d = l * r;
+        },
+        op => for (d, (&l, &r)) in values {
+            *d = op.apply(l, r);
+        },
+    }
+}
+
+fn select(left: f64, right: f64, criteria: f64, lower: f64, upper: f64, falloff: f64) -> f64 {
+    select_value(criteria, (lower, upper), falloff, || left, || right)
+}
+
+impl Op {
+    fn operands(&self) -> Vec<Reg> {
+        match *self {
+            Op::Const(_) | Op::Sample(_) => vec![],
+            Op::Unary(_, src) => vec![src],
+            Op::Binary(_, left, right) => vec![left, right],
+            Op::Select {
+                left,
+                right,
+                criteria,
+                ..
+            }
+            | Op::Blend {
+                left,
+                right,
+                criteria,
+                ..
+            } => vec![left, right, criteria],
+        }
+    }
+
+    /// Identifies the op for common subexpression elimination. Floats are compared by bit pattern,
+    /// so constants such as `-0.0` and `0.0` stay distinct.
+    fn key(&self) -> [u64; 7] {
+        let bits = f64::to_bits;
+        match *self {
+            Op::Const(value) => [0, bits(value), 0, 0, 0, 0, 0],
+            Op::Sample(index) => [1, index as u64, 0, 0, 0, 0, 0],
+            Op::Unary(op, src) => {
+                let (kind, a, b) = match op {
+                    UnaryOp::Affine { scale, bias } => (0, bits(scale), bits(bias)),
+                    UnaryOp::Clamp { low, high } => (1, bits(low), bits(high)),
+                    UnaryOp::Abs => (2, 0, 0),
+                    UnaryOp::Exponent(exponent) => (3, bits(exponent), 0),
+                    UnaryOp::Map(function) => (4, function as u64, 0),
+                };
+                [2, src as u64, kind, a, b, 0, 0]
+            }
+            Op::Binary(op, left, right) => [3, op as u64, left as u64, right as u64, 0, 0, 0],
+            Op::Select {
+                left,
+                right,
+                criteria,
+                lower,
+                upper,
+                falloff,
+            } => [
+                4,
+                left as u64,
+                right as u64,
+                criteria as u64,
+                bits(lower),
+                bits(upper),
+                bits(falloff),
+            ],
+            Op::Blend {
+                left,
+                right,
+                criteria,
+                blend,
+            } => [
+                5,
+                left as u64,
+                right as u64,
+                criteria as u64,
+                blend as u64,
+                0,
+                0,
+            ],
+        }
+    }
+
+    fn map_operands<F: Fn(Reg) -> Reg>(self, f: F) -> Op {
+        match self {
+            Op::Const(_) | Op::Sample(_) => self,
+            Op::Unary(op, src) => Op::Unary(op, f(src)),
+            Op::Binary(op, left, right) => Op::Binary(op, f(left), f(right)),
+            Op::Select {
+                left,
+                right,
+                criteria,
+                lower,
+                upper,
+                falloff,
+            } => Op::Select {
+                left: f(left),
+                right: f(right),
+                criteria: f(criteria),
+                lower,
+                upper,
+                falloff,
+            },
+            Op::Blend {
+                left,
+                right,
+                criteria,
+                blend,
+            } => Op::Blend {
+                left: f(left),
+                right: f(right),
+                criteria: f(criteria),
+                blend,
+            },
+        }
+    }
+}
+
+/// Lowers a description into SSA form, where the index of an op is the value it defines.
+struct Compiler<'a> {
+    ops: Vec<Op>,
+    /// The value defined by each distinct op, keyed by `Op::key`.
+    defined: HashMap<[u64; 7], Reg>,
+    samples: Vec<&'a NodeDesc>,
+}
+
+impl<'a> Compiler<'a> {
+    fn constant(&self, value: Reg) -> Option<f64> {
+        match self.ops[value] {
+            Op::Const(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    fn fold(&self, op: Op) -> Option<f64> {
+        match op {
+            Op::Unary(unary, src) => Some(unary.apply(self.constant(src)?)),
+            Op::Binary(binary, left, right) => {
+                Some(binary.apply(self.constant(left)?, self.constant(right)?))
+            }
+            Op::Select {
+                left,
+                right,
+                criteria,
+                lower,
+                upper,
+                falloff,
+            } => Some(select(
+                self.constant(left)?,
+                self.constant(right)?,
+                self.constant(criteria)?,
+                lower,
+                upper,
+                falloff,
+            )),
+            Op::Blend {
+                left,
+                right,
+                criteria,
+                blend,
+            } => Some(blend.blend_fn()(
+                self.constant(left)?,
+                self.constant(right)?,
+                self.constant(criteria)?,
+            )),
+            Op::Const(_) | Op::Sample(_) => None,
+        }
+    }
+
+    fn emit(&mut self, op: Op) -> Reg {
+        let op = match op {
+            Op::Unary(UnaryOp::Affine { scale, bias }, src) => match self.ops[src] {
+                Op::Unary(UnaryOp::Affine { scale: s, bias: b }, inner) => Op::Unary(
+                    UnaryOp::Affine {
+                        scale: s * scale,
+                        bias: b * scale + bias,
+                    },
+                    inner,
+                ),
+                _ => op,
+            },
+            _ => op,
+        };
+        if let Op::Unary(UnaryOp::Affine { scale, bias }, src) = op {
+            if scale == 1.0 && bias == 0.0 {
+                return src;
+            }
+        }
+        let op = self.fold(op).map_or(op, Op::Const);
+
+        let ops = &mut self.ops;
+        *self.defined.entry(op.key()).or_insert_with(|| {
+            ops.push(op);
+            ops.len() - 1
+        })
+    }
+
+    fn unary(&mut self, op: UnaryOp, source: &'a NodeDesc) -> Result<Reg, GraphError> {
+        let src = self.lower(source)?;
+        Ok(self.emit(Op::Unary(op, src)))
+    }
+
+    fn fold_sources(
+        &mut self,
+        op: BinaryOp,
+        identity: f64,
+        sources: &'a [NodeDesc],
+    ) -> Result<Reg, GraphError> {
+        let mut acc = match sources.first() {
+            Some(first) => self.lower(first)?,
+            None => return Ok(self.emit(Op::Const(identity))),
+        };
+        for source in &sources[1..] {
+            let value = self.lower(source)?;
+            acc = self.emit(Op::Binary(op, acc, value));
+        }
+        Ok(acc)
+    }
+
+    fn sample(&mut self, node: &'a NodeDesc) -> Reg {
+        let index = match self.samples.iter().position(|&n| n == node) {
+            Some(index) => index,
+            None => {
+                self.samples.push(node);
+                self.samples.len() - 1
+            }
+        };
+        self.emit(Op::Sample(index))
+    }
+
+    fn lower(&mut self, node: &'a NodeDesc) -> Result<Reg, GraphError> {
+        let value = match *node {
+            NodeDesc::Constant(value) => self.emit(Op::Const(value)),
+            NodeDesc::Scale {
+                ref source,
+                amplitude,
+            } => self.unary(
+                UnaryOp::Affine {
+                    scale: amplitude,
+                    bias: 0.0,
+                },
+                source,
+            )?,
+            NodeDesc::ScaleBias {
+                ref source,
+                scale,
+                bias,
+            } => self.unary(UnaryOp::Affine { scale, bias }, source)?,
+            NodeDesc::WithRange {
+                ref source,
+                min,
+                max,
+            } => {
+                ordered("with_range", "min", min, max)?;
+                let half_range = 0.5 * (max - min);
+                self.unary(
+                    UnaryOp::Affine {
+                        scale: half_range,
+                        bias: min + half_range,
+                    },
+                    source,
+                )?
+            }
+            NodeDesc::InvertRange {
+                ref source,
+                min,
+                max,
+            } => {
+                ordered("invert_range", "min", min, max)?;
+                self.unary(
+                    UnaryOp::Affine {
+                        scale: -1.0,
+                        bias: min + max,
+                    },
+                    source,
+                )?
+            }
+            NodeDesc::Negate(ref source) => self.unary(
+                UnaryOp::Affine {
+                    scale: -1.0,
+                    bias: 0.0,
+                },
+                source,
+            )?,
+            NodeDesc::Clamp {
+                ref source,
+                low,
+                high,
+            } => {
+                ordered("clamp", "low", low, high)?;
+                self.unary(UnaryOp::Clamp { low, high }, source)?
+            }
+            NodeDesc::Abs(ref source) => self.unary(UnaryOp::Abs, source)?,
+            NodeDesc::Exponent {
+                ref source,
+                exponent,
+            } => self.unary(UnaryOp::Exponent(exponent), source)?,
+            NodeDesc::Map {
+                ref source,
+                function,
+            } => self.unary(UnaryOp::Map(function), source)?,
+            NodeDesc::Binary {
+                op,
+                ref left,
+                ref right,
+            } => {
+                let (left, right) = (self.lower(left)?, self.lower(right)?);
+                self.emit(Op::Binary(op, left, right))
+            }
+            NodeDesc::Sum(ref sources) => self.fold_sources(BinaryOp::Add, 0.0, sources)?,
+            NodeDesc::Product(ref sources) => self.fold_sources(BinaryOp::Multiply, 1.0, sources)?,
+            NodeDesc::Select {
+                ref left,
+                ref right,
+                ref criteria,
+                lower,
+                upper,
+                falloff,
+            } => {
+                ordered("select", "lower", lower, upper)?;
+                if falloff.is_nan() || falloff < 0.0 {
+                    return Err(invalid("select", "falloff", "must not be negative"));
+                }
+                let op = Op::Select {
+                    left: self.lower(left)?,
+                    right: self.lower(right)?,
+                    criteria: self.lower(criteria)?,
+                    lower,
+                    upper,
+                    falloff,
+                };
+                self.emit(op)
+            }
+            NodeDesc::Blend {
+                ref left,
+                ref right,
+                ref criteria,
+                blend,
+            } => {
+                let op = Op::Blend {
+                    left: self.lower(left)?,
+                    right: self.lower(right)?,
+                    criteria: self.lower(criteria)?,
+                    blend,
+                };
+                self.emit(op)
+            }
+            _ => self.sample(node),
+        };
+        Ok(value)
+    }
+}
+
+impl<I, D> Tape<I, D>
+where
+    I: Clone,
+{
+    /// Compiles `desc`, using `build` for the subgraphs that are sampled as a whole.
+    pub fn compile<B>(desc: &NodeDesc, build: B) -> Result<Tape<I, D>, GraphError>
+    where
+        B: Fn(&NodeDesc) -> Result<DynNoise<I, D>, GraphError>,
+    {
+        let mut compiler = Compiler {
+            ops: Vec::new(),
+            defined: HashMap::new(),
+            samples: Vec::new(),
+        };
+        let output = compiler.lower(desc)?;
+        let ops = compiler.ops;
+
+        //Dead code elimination; operands always precede their users.
+        let mut live = vec![false; ops.len()];
+        live[output] = true;
+        for i in (0..ops.len()).rev() {
+            if live[i] {
+                for operand in ops[i].operands() {
+                    live[operand] = true;
+                }
+            }
+        }
+        let mut last_use = vec![0; ops.len()];
+        for (i, op) in ops.iter().enumerate().filter(|&(i, _)| live[i]) {
+            for operand in op.operands() {
+                last_use[operand] = i;
+            }
+        }
+        last_use[output] = usize::MAX;
+
+        //Linear scan register allocation. A register is released after the destination of
+        //the instruction that last reads it is chosen, so `dst` never aliases an operand.
+        let mut assigned = vec![0; ops.len()];
+        let mut free: Vec<Reg> = Vec::new();
+        let mut registers = 0;
+        let mut instructions = Vec::new();
+        let mut sources = Vec::new();
+        for (i, &op) in ops.iter().enumerate().filter(|&(i, _)| live[i]) {
+            let dst = free.pop().unwrap_or_else(|| {
+                registers += 1;
+                registers - 1
+            });
+            assigned[i] = dst;
+
+            let op = match op {
+                Op::Sample(index) => {
+                    sources.push(build(compiler.samples[index])?);
+                    Op::Sample(sources.len() - 1)
+                }
+                op => op.map_operands(|r| assigned[r]),
+            };
+            instructions.push(Instruction { dst, op });
+
+            let mut operands = ops[i].operands();
+            operands.sort();
+            operands.dedup();
+            for operand in operands {
+                if last_use[operand] == i {
+                    free.push(assigned[operand]);
+                }
+            }
+        }
+
+        Ok(Tape {
+            instructions,
+            sources,
+            registers,
+            output: assigned[output],
+        })
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+    pub fn sources(&self) -> &[DynNoise<I, D>] {
+        &self.sources
+    }
+    pub fn num_registers(&self) -> usize {
+        self.registers
+    }
+    pub fn output(&self) -> Reg {
+        self.output
+    }
+
+    /// Evaluates the tape at every point, writing one value per point into `out`.
+    pub fn evaluate(&self, points: &[I], out: &mut [f64]) {
+        self.evaluate_lod(points, 0.0, out)
+    }
+
+    /// Like `evaluate`, but the sampled sources leave out detail finer than `footprint`.
+    pub fn evaluate_lod(&self, points: &[I], footprint: f64, out: &mut [f64]) {
+        assert_eq!(points.len(), out.len(), "Expected one output value per point");
+        let mut registers = vec![vec![0.0; points.len()]; self.registers];
+
+        for instruction in &self.instructions {
+            let mut dst = mem::take(&mut registers[instruction.dst]);
+            match instruction.op {
+                Op::Const(value) => for d in dst.iter_mut() {
+                    *d = value;
+                },
+                Op::Sample(index) => {
+                    let source = &self.sources[index];
+                    for (d, p) in dst.iter_mut().zip(points) {
+                        *d = source.value_at_lod(p.clone(), footprint);
+                    }
+                }
+                Op::Unary(op, src) => op.apply_row(&registers[src], &mut dst),
+                Op::Binary(op, left, right) => {
+                    binary_row(op, &registers[left], &registers[right], &mut dst)
+                }
+                Op::Select {
+                    left,
+                    right,
+                    criteria,
+                    lower,
+                    upper,
+                    falloff,
+                } => for (j, d) in dst.iter_mut().enumerate() {
+                    *d = select(
+                        registers[left][j],
+                        registers[right][j],
+                        registers[criteria][j],
+                        lower,
+                        upper,
+                        falloff,
+                    );
+                },
+                Op::Blend {
+                    left,
+                    right,
+                    criteria,
+                    blend,
+                } => {
+                    let blend = blend.blend_fn();
+                    for (j, d) in dst.iter_mut().enumerate() {
+                        *d = blend(registers[left][j], registers[right][j], registers[criteria][j]);
+                    }
+                }
+            }
+            registers[instruction.dst] = dst;
+        }
+
+        out.copy_from_slice(&registers[self.output]);
+    }
+}
+
+impl Tape<Point1<f64>, f64> {
+    pub fn compile_1d(desc: &NodeDesc) -> Result<Tape1d, GraphError> {
+        Tape::compile(desc, NodeDesc::build_1d)
+    }
+}
+impl Tape<Point2<f64>, (f64, f64)> {
+    pub fn compile_2d(desc: &NodeDesc) -> Result<Tape2d, GraphError> {
+        Tape::compile(desc, NodeDesc::build_2d)
+    }
+}
+impl Tape<Point3<f64>, (f64, f64, f64)> {
+    pub fn compile_3d(desc: &NodeDesc) -> Result<Tape3d, GraphError> {
+        Tape::compile(desc, NodeDesc::build_3d)
+    }
+}
+
+/// Disassembles the tape, one instruction per line.
+impl<I, D> fmt::Display for Tape<I, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for instruction in &self.instructions {
+            write!(f, "r{} = ", instruction.dst)?;
+            match instruction.op {
+                Op::Const(value) => write!(f, "const {:?}", value)?,
+                Op::Sample(index) => write!(f, "sample #{}", index)?,
+                Op::Unary(op, src) => write!(f, "{:?} r{}", op, src)?,
+                Op::Binary(op, left, right) => write!(f, "{} r{}, r{}", op.name(), left, right)?,
+                Op::Select {
+                    left,
+                    right,
+                    criteria,
+                    lower,
+                    upper,
+                    falloff,
+                } => write!(
+                    f,
+                    "select r{}, r{}, r{} in ({:?}, {:?}] falloff {:?}",
+                    left, right, criteria, lower, upper, falloff
+                )?,
+                Op::Blend {
+                    left,
+                    right,
+                    criteria,
+                    blend,
+                } => write!(
+                    f,
+                    "blend {} r{}, r{}, r{}",
+                    blend.name(),
+                    left,
+                    right,
+                    criteria
+                )?,
+            }
+            writeln!(f)?;
+        }
+        write!(f, "return r{}", self.output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graph::expr;
+
+    #[test]
+    fn matches_built_graph() {
+        let text = "select(fbm(freq=0.05, seed=3) * 0.5 + 0.25, abs(perlin(freq=0.1)), \
+                    fbm(freq=0.05, seed=3), -0.2, 0.4, falloff=0.1) - clamp(perlin(freq=0.2), -0.5, 0.5)";
+        let desc = expr::parse(text, 2).unwrap();
+        let noise = desc.build_2d().unwrap();
+        let tape = Tape::compile_2d(&desc).unwrap();
+        //The shared fbm is sampled once.
+        assert_eq!(tape.sources().len(), 3);
+
+        let points: Vec<_> = (0..64)
+            .map(|i| [10.0 + i as f64 * 0.71, 20.0 + i as f64 * 0.29])
+            .collect();
+        let mut out = vec![0.0; points.len()];
+        tape.evaluate(&points, &mut out);
+        for (p, v) in points.iter().zip(&out) {
+            assert!((noise.value_at(*p) - v).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn folds_and_fuses() {
+        let desc = expr::parse("(perlin() * 2 + 1) * 0.5 - 0.5 + max(2, 3) * 0", 1).unwrap();
+        let tape = Tape::compile_1d(&desc).unwrap();
+        //The affine chain cancels out to the identity, leaving the sample alone.
+        assert_eq!(tape.instructions().len(), 1);
+        assert_eq!(tape.num_registers(), 1);
+
+        //Negative zero must not be merged with the zero constant before it.
+        let desc = expr::parse("perlin() * 0 + 1 / (0 * -1)", 1).unwrap();
+        let tape = Tape::compile_1d(&desc).unwrap();
+        let mut out = [0.0];
+        tape.evaluate(&[0.3], &mut out);
+        assert_eq!(out[0], desc.build_1d().unwrap().value_at(0.3));
+        assert_eq!(out[0], f64::NEG_INFINITY);
+    }
+}
@@ -4,9 +4,19 @@ extern crate test;
 extern crate cgmath;
 extern crate image;
 extern crate rand;
+extern crate ron;
+extern crate serde;
+extern crate serde_json;
 
+#[macro_use]
 pub mod adapter;
+pub mod analysis;
 pub mod gradient;
+pub mod graph;
 pub mod grid;
 pub mod interpolate;
+pub mod mesh;
 pub mod noise;
+pub mod presets;
+pub mod render;
+pub mod terrain;
@@ -0,0 +1,378 @@
+use grid::Grid2d;
+use mesh::{normalized, Mesh};
+use noise::Noise2d;
+use render::Region;
+
+/// A rectangle of lattice points, from corner `(x0, y0)` to corner `(x1, y1)` inclusive.
+#[derive(Copy, Clone, Debug)]
+struct Node {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+}
+
+impl Node {
+    fn has_center(&self) -> bool {
+        self.x1 - self.x0 >= 2 && self.y1 - self.y0 >= 2
+    }
+
+    /// The lattice points on the boundary, counter clockwise from `(x0, y0)`.
+    fn perimeter(&self) -> Vec<(usize, usize)> {
+        let mut points = Vec::new();
+        points.extend((self.x0..self.x1).map(|x| (x, self.y0)));
+        points.extend((self.y0..self.y1).map(|y| (self.x1, y)));
+        points.extend((self.x0 + 1..=self.x1).rev().map(|x| (x, self.y1)));
+        points.extend((self.y0 + 1..=self.y1).rev().map(|y| (self.x0, y)));
+        points
+    }
+}
+
+/// Turns heightfields into terrain meshes, with the height along the z axis.
+///
+/// With a maximum error the lattice is simplified by a quadtree: regions which bilinear
+/// interpolation of their corners reproduces to within the error become a single fan of
+/// triangles, or a strip where they are one cell wide. Both also take in the vertices of
+/// smaller neighbours along their edges, so the mesh has no cracks.
+#[derive(Clone, Debug)]
+pub struct HeightmapMesher {
+    height_scale: f64,
+    max_error: f64,
+    skirt_depth: Option<f64>,
+}
+
+impl Default for HeightmapMesher {
+    fn default() -> HeightmapMesher {
+        HeightmapMesher::new()
+    }
+}
+
+impl HeightmapMesher {
+    pub fn new() -> HeightmapMesher {
+        HeightmapMesher {
+            height_scale: 1.0,
+            max_error: 0.0,
+            skirt_depth: None,
+        }
+    }
+
+    pub fn with_height_scale(self, height_scale: f64) -> HeightmapMesher {
+        HeightmapMesher {
+            height_scale,
+            ..self
+        }
+    }
+    /// The largest height difference, after scaling, that simplification may introduce.
+    pub fn with_max_error(self, max_error: f64) -> HeightmapMesher {
+        HeightmapMesher { max_error, ..self }
+    }
+    /// Adds walls reaching `depth` below each border vertex, hiding the gaps between tiles
+    /// meshed at different levels of detail.
+    pub fn with_skirts(self, depth: f64) -> HeightmapMesher {
+        HeightmapMesher {
+            skirt_depth: Some(depth),
+            ..self
+        }
+    }
+
+    pub fn height_scale(&self) -> f64 {
+        self.height_scale
+    }
+    pub fn max_error(&self) -> f64 {
+        self.max_error
+    }
+    pub fn skirt_depth(&self) -> Option<f64> {
+        self.skirt_depth
+    }
+
+    /// Samples `noise` on a `width` x `height` lattice spanning `region`, edges included, and
+    /// meshes the result.
+    pub fn mesh_noise<N>(&self, noise: &N, region: Region, width: u32, height: u32) -> Mesh
+    where
+        N: Noise2d,
+    {
+        assert!(width > 1 && height > 1, "A heightmap needs two points per axis");
+        let step = [
+            (region.max[0] - region.min[0]) / f64::from(width - 1),
+            (region.max[1] - region.min[1]) / f64::from(height - 1),
+        ];
+        let mut grid = Grid2d::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let pos = [
+                    region.min[0] + f64::from(x) * step[0],
+                    region.min[1] + f64::from(y) * step[1],
+                ];
+                grid[(x as usize, y as usize)] = noise.value_at(pos);
+            }
+        }
+        self.mesh_grid(&grid, region)
+    }
+
+    /// Meshes heights sampled on a lattice spanning `region`, edges included.
+    pub fn mesh_grid(&self, grid: &Grid2d<f64>, region: Region) -> Mesh {
+        let (width, height) = (grid.width() as usize, grid.height() as usize);
+        assert!(width > 1 && height > 1, "A heightmap needs two points per axis");
+        let whole = Node {
+            x0: 0,
+            y0: 0,
+            x1: width - 1,
+            y1: height - 1,
+        };
+        let mut leaves = Vec::new();
+        self.subdivide(grid, whole, &mut leaves);
+
+        let mut active = vec![false; width * height];
+        for leaf in &leaves {
+            for &x in &[leaf.x0, leaf.x1] {
+                for &y in &[leaf.y0, leaf.y1] {
+                    active[x + y * width] = true;
+                }
+            }
+        }
+
+        let mut builder = Builder {
+            grid,
+            region,
+            height_scale: self.height_scale,
+            vertices: vec![u32::MAX; width * height],
+            mesh: Mesh::new(),
+        };
+        for leaf in &leaves {
+            if leaf.has_center() {
+                let perimeter: Vec<u32> = leaf.perimeter()
+                    .into_iter()
+                    .filter(|&(x, y)| active[x + y * width])
+                    .map(|(x, y)| builder.vertex(x, y))
+                    .collect();
+                let center = builder.vertex((leaf.x0 + leaf.x1) / 2, (leaf.y0 + leaf.y1) / 2);
+                for k in 0..perimeter.len() {
+                    let next = perimeter[(k + 1) % perimeter.len()];
+                    builder.mesh.triangles.push([center, perimeter[k], next]);
+                }
+            } else {
+                builder.strip(leaf, &active);
+            }
+        }
+
+        if let Some(depth) = self.skirt_depth {
+            let border: Vec<(usize, usize)> = whole
+                .perimeter()
+                .into_iter()
+                .filter(|&(x, y)| active[x + y * width])
+                .collect();
+            for k in 0..border.len() {
+                builder.skirt(border[k], border[(k + 1) % border.len()], depth);
+            }
+        }
+        builder.mesh
+    }
+
+    fn subdivide(&self, grid: &Grid2d<f64>, node: Node, leaves: &mut Vec<Node>) {
+        let (w, h) = (node.x1 - node.x0, node.y1 - node.y0);
+        if (w == 1 && h == 1) || self.error(grid, node) <= self.max_error {
+            leaves.push(node);
+            return;
+        }
+
+        let xs = if w > 1 {
+            vec![(node.x0, node.x0 + w / 2), (node.x0 + w / 2, node.x1)]
+        } else {
+            vec![(node.x0, node.x1)]
+        };
+        let ys = if h > 1 {
+            vec![(node.y0, node.y0 + h / 2), (node.y0 + h / 2, node.y1)]
+        } else {
+            vec![(node.y0, node.y1)]
+        };
+        for &(y0, y1) in &ys {
+            for &(x0, x1) in &xs {
+                self.subdivide(grid, Node { x0, y0, x1, y1 }, leaves);
+            }
+        }
+    }
+
+    /// Largest scaled difference between the heights in `node` and the bilinear interpolation
+    /// of its corners.
+    fn error(&self, grid: &Grid2d<f64>, node: Node) -> f64 {
+        let corners = [
+            grid[(node.x0, node.y0)],
+            grid[(node.x1, node.y0)],
+            grid[(node.x0, node.y1)],
+            grid[(node.x1, node.y1)],
+        ];
+        let mut error: f64 = 0.0;
+        for y in node.y0..=node.y1 {
+            let ty = (y - node.y0) as f64 / (node.y1 - node.y0) as f64;
+            for x in node.x0..=node.x1 {
+                let tx = (x - node.x0) as f64 / (node.x1 - node.x0) as f64;
+                let bottom = corners[0] + (corners[1] - corners[0]) * tx;
+                let top = corners[2] + (corners[3] - corners[2]) * tx;
+                let interpolated = bottom + (top - bottom) * ty;
+                error = error.max((grid[(x, y)] - interpolated).abs() * self.height_scale.abs());
+            }
+        }
+        error
+    }
+}
+
+struct Builder<'a> {
+    grid: &'a Grid2d<f64>,
+    region: Region,
+    height_scale: f64,
+    vertices: Vec<u32>,
+    mesh: Mesh,
+}
+
+impl<'a> Builder<'a> {
+    fn position(&self, x: usize, y: usize) -> [f64; 3] {
+        let (width, height) = (self.grid.width() as usize, self.grid.height() as usize);
+        let (u, v) = (x as f64 / (width - 1) as f64, y as f64 / (height - 1) as f64);
+        [
+            self.region.min[0] + (self.region.max[0] - self.region.min[0]) * u,
+            self.region.min[1] + (self.region.max[1] - self.region.min[1]) * v,
+            self.grid[(x, y)] * self.height_scale,
+        ]
+    }
+
+    /// Index of the vertex at lattice point `(x, y)`, created on first use.
+    fn vertex(&mut self, x: usize, y: usize) -> u32 {
+        let (width, height) = (self.grid.width() as usize, self.grid.height() as usize);
+        let slot = x + y * width;
+        if self.vertices[slot] == u32::MAX {
+            //Central differences, one sided at the border.
+            let (xl, xh) = (x.saturating_sub(1), (x + 1).min(width - 1));
+            let (yl, yh) = (y.saturating_sub(1), (y + 1).min(height - 1));
+            let (pxl, pxh) = (self.position(xl, y), self.position(xh, y));
+            let (pyl, pyh) = (self.position(x, yl), self.position(x, yh));
+            let dx = (pxh[2] - pxl[2]) / (pxh[0] - pxl[0]);
+            let dy = (pyh[2] - pyl[2]) / (pyh[1] - pyl[1]);
+
+            self.vertices[slot] = self.mesh.positions.len() as u32;
+            let position = self.position(x, y);
+            self.mesh.positions.push(position);
+            self.mesh.normals.push(normalized([-dx, -dy, 1.0]));
+            self.mesh
+                .uvs
+                .push([x as f64 / (width - 1) as f64, y as f64 / (height - 1) as f64]);
+        }
+        self.vertices[slot]
+    }
+
+    /// Triangulates a leaf one cell wide by zipping together the vertices along its long sides.
+    fn strip(&mut self, leaf: &Node, active: &[bool]) {
+        let width = self.grid.width() as usize;
+        let is_active = |&(x, y): &(usize, usize)| active[x + y * width];
+        //The first side is the left or top one, which keeps the triangles counter clockwise.
+        let vertical = leaf.x1 - leaf.x0 == 1;
+        let (first, second): (Vec<_>, Vec<_>) = if vertical {
+            (
+                (leaf.y0..=leaf.y1).map(|y| (leaf.x0, y)).filter(is_active).collect(),
+                (leaf.y0..=leaf.y1).map(|y| (leaf.x1, y)).filter(is_active).collect(),
+            )
+        } else {
+            (
+                (leaf.x0..=leaf.x1).map(|x| (x, leaf.y1)).filter(is_active).collect(),
+                (leaf.x0..=leaf.x1).map(|x| (x, leaf.y0)).filter(is_active).collect(),
+            )
+        };
+        let along = |p: (usize, usize)| if vertical { p.1 } else { p.0 };
+
+        let (mut i, mut j) = (0, 0);
+        while i + 1 < first.len() || j + 1 < second.len() {
+            let advance_first = j + 1 == second.len()
+                || (i + 1 < first.len() && along(first[i + 1]) < along(second[j + 1]));
+            let triangle = if advance_first {
+                i += 1;
+                [first[i - 1], second[j], first[i]]
+            } else {
+                j += 1;
+                [first[i], second[j - 1], second[j]]
+            };
+            let t = [
+                self.vertex(triangle[0].0, triangle[0].1),
+                self.vertex(triangle[1].0, triangle[1].1),
+                self.vertex(triangle[2].0, triangle[2].1),
+            ];
+            self.mesh.triangles.push(t);
+        }
+    }
+
+    /// A wall below the border from `a` to `b`, which run counter clockwise around the mesh.
+    fn skirt(&mut self, a: (usize, usize), b: (usize, usize), depth: f64) {
+        let (top_a, top_b) = (self.vertex(a.0, a.1), self.vertex(b.0, b.1));
+        let direction = [b.0 as f64 - a.0 as f64, b.1 as f64 - a.1 as f64];
+        let outward = normalized([direction[1], -direction[0], 0.0]);
+        let bottom_a = self.mesh.positions.len() as u32;
+        for &top in &[top_a, top_b] {
+            let mut position = self.mesh.positions[top as usize];
+            position[2] -= depth;
+            let uv = self.mesh.uvs[top as usize];
+            self.mesh.positions.push(position);
+            self.mesh.normals.push(outward);
+            self.mesh.uvs.push(uv);
+        }
+        let bottom_b = bottom_a + 1;
+        self.mesh.triangles.push([top_a, bottom_a, bottom_b]);
+        self.mesh.triangles.push([top_a, bottom_b, top_b]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use noise::fbm::Fbm2d;
+    use noise::seeded_rng;
+
+    #[test]
+    fn simplified_mesh_covers_region_without_cracks() {
+        let fbm = Fbm2d::new(seeded_rng(2))
+            .with_frequency((1.0, 1.0))
+            .with_num_octaves(3);
+        let region = Region::new([0.0, 0.0], [2.0, 1.0]);
+        let full = HeightmapMesher::new().mesh_noise(&fbm, region, 45, 23);
+        assert_eq!(full.num_triangles(), 2 * 44 * 22);
+
+        let simplified = HeightmapMesher::new()
+            .with_max_error(0.05)
+            .mesh_noise(&fbm, region, 45, 23);
+        assert!(simplified.num_triangles() < full.num_triangles() / 2);
+
+        //Counter clockwise triangles seen from above that tile the region exactly, with every
+        //edge away from the border shared by two of them.
+        let mut area = 0.0;
+        let mut edges = HashMap::new();
+        for (i, t) in simplified.triangles.iter().enumerate() {
+            assert!(simplified.face_normal(i)[2] > 0.0);
+            let p: Vec<_> = t.iter().map(|&v| simplified.positions[v as usize]).collect();
+            area += 0.5 * ((p[1][0] - p[0][0]) * (p[2][1] - p[0][1])
+                - (p[2][0] - p[0][0]) * (p[1][1] - p[0][1]));
+            for k in 0..3 {
+                *edges.entry((t[k], t[(k + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+        assert!((area - 2.0).abs() < 1e-9);
+        for &(a, b) in edges.keys() {
+            let on_border = |v: u32| {
+                let p = simplified.positions[v as usize];
+                p[0] == 0.0 || p[0] == 2.0 || p[1] == 0.0 || p[1] == 1.0
+            };
+            assert!(edges.contains_key(&(b, a)) || (on_border(a) && on_border(b)));
+        }
+
+        let skirted = HeightmapMesher::new()
+            .with_max_error(0.05)
+            .with_skirts(0.5)
+            .mesh_noise(&fbm, region, 45, 23);
+        let mut ply = Vec::new();
+        skirted.write_ply_binary(&mut ply).unwrap();
+        let header_end = b"end_header\n";
+        let header = ply.windows(header_end.len()).position(|w| w == header_end).unwrap();
+        assert_eq!(
+            ply.len() - header - header_end.len(),
+            skirted.num_vertices() * 32 + skirted.num_triangles() * 13
+        );
+    }
+}
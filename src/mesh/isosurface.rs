@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use grid::Grid3d;
+use mesh::{normalized, Mesh};
+use noise::{Noise3d, Point3};
+
+/// An axis aligned box of noise space.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Volume {
+    pub min: Point3<f64>,
+    pub max: Point3<f64>,
+}
+
+impl Volume {
+    pub fn new(min: Point3<f64>, max: Point3<f64>) -> Volume {
+        Volume { min, max }
+    }
+
+    /// Spacing between the lattice points of a grid with `resolution` points per axis.
+    fn spacing(&self, resolution: (u32, u32, u32)) -> Point3<f64> {
+        let step =
+            |axis: usize, points: u32| (self.max[axis] - self.min[axis]) / f64::from(points - 1);
+        [step(0, resolution.0), step(1, resolution.1), step(2, resolution.2)]
+    }
+}
+
+/// Samples `noise` on a lattice spanning `volume` with the given number of points per axis,
+/// including the faces of the volume.
+pub fn sample_volume<N>(noise: &N, volume: Volume, resolution: (u32, u32, u32)) -> Grid3d<f64>
+where
+    N: Noise3d,
+{
+    let (width, height, depth) = resolution;
+    assert!(width > 1 && height > 1 && depth > 1, "A volume needs two points per axis");
+    let spacing = volume.spacing(resolution);
+    let mut grid = Grid3d::new(width, height, depth);
+    for z in 0..depth {
+        for y in 0..height {
+            for x in 0..width {
+                let pos = [
+                    volume.min[0] + f64::from(x) * spacing[0],
+                    volume.min[1] + f64::from(y) * spacing[1],
+                    volume.min[2] + f64::from(z) * spacing[2],
+                ];
+                grid[(x as usize, y as usize, z as usize)] = noise.value_at(pos);
+            }
+        }
+    }
+    grid
+}
+
+//Corner `i` of a cell lies at offset `(i & 1, (i >> 1) & 1, (i >> 2) & 1)`.
+const EDGES: [(usize, usize); 12] = [
+    (0, 1), (2, 3), (4, 5), (6, 7),
+    (0, 2), (1, 3), (4, 6), (5, 7),
+    (0, 4), (1, 5), (2, 6), (3, 7),
+];
+
+//The corners of each face of a cell, counter clockwise when seen from outside the cell.
+const FACES: [[usize; 4]; 6] = [
+    [0, 4, 6, 2], [1, 3, 7, 5],
+    [0, 1, 5, 4], [2, 6, 7, 3],
+    [0, 2, 3, 1], [4, 5, 7, 6],
+];
+
+fn edge_between(a: usize, b: usize) -> usize {
+    EDGES
+        .iter()
+        .position(|&(c0, c1)| (c0, c1) == (a, b) || (c0, c1) == (b, a))
+        .expect("Corners don't share an edge")
+}
+
+/// Triangles, as triples of edges, for the cell whose corners inside the surface are the set
+/// bits of `case`, wound counter clockwise when seen from outside the surface.
+///
+/// Rather than tabulating every case, the surface is traced around the faces of the cell: each
+/// face contributes segments between its crossed edges, directed to keep the inside corners on
+/// their right when seen from outside the cell, and the segments close into polygons. A face
+/// with all four edges crossed separates its two inside corners, a rule neighbouring cells
+/// agree on, which keeps the surface watertight.
+fn triangulate_case(case: usize) -> Vec<[usize; 3]> {
+    let inside = |corner: usize| case & (1 << corner) != 0;
+
+    let mut segments = Vec::new();
+    for face in &FACES {
+        let edges: Vec<usize> = (0..4).map(|k| edge_between(face[k], face[(k + 1) % 4])).collect();
+        let crossed: Vec<usize> = (0..4)
+            .filter(|&k| inside(face[k]) != inside(face[(k + 1) % 4]))
+            .collect();
+        match crossed.len() {
+            2 => {
+                let (i, j) = (crossed[0], crossed[1]);
+                if inside(face[(i + 1) % 4]) {
+                    segments.push((edges[i], edges[j]));
+                } else {
+                    segments.push((edges[j], edges[i]));
+                }
+            }
+            4 => for k in (0..4).filter(|&k| inside(face[k])) {
+                segments.push((edges[(k + 3) % 4], edges[k]));
+            },
+            _ => {}
+        }
+    }
+
+    //Every crossed edge borders two faces, which cross it in opposite directions, so it starts
+    //exactly one segment and ends another.
+    let mut triangles = Vec::new();
+    let mut used = vec![false; segments.len()];
+    while let Some(start) = used.iter().position(|&u| !u) {
+        used[start] = true;
+        let (first, mut current) = segments[start];
+        let mut polygon = vec![first];
+        while current != first {
+            polygon.push(current);
+            let next = (0..segments.len())
+                .find(|&s| !used[s] && segments[s].0 == current)
+                .expect("Open contour in marching cubes case");
+            used[next] = true;
+            current = segments[next].1;
+        }
+        for i in 1..polygon.len() - 1 {
+            triangles.push([polygon[0], polygon[i], polygon[i + 1]]);
+        }
+    }
+    triangles
+}
+
+fn march<F>(grid: &Grid3d<f64>, volume: Volume, iso: f64, normal_at: F) -> Mesh
+where
+    F: Fn(Point3<usize>, Point3<usize>, f64, Point3<f64>) -> Point3<f64>,
+{
+    let cases: Vec<Vec<[usize; 3]>> = (0..256).map(triangulate_case).collect();
+    let resolution = (grid.width(), grid.height(), grid.depth());
+    let spacing = volume.spacing(resolution);
+    let corner = |cell: Point3<usize>, i: usize| {
+        [cell[0] + (i & 1), cell[1] + ((i >> 1) & 1), cell[2] + ((i >> 2) & 1)]
+    };
+    let value = |p: Point3<usize>| grid[(p[0], p[1], p[2])];
+
+    let mut mesh = Mesh::new();
+    //Vertices are welded by the lattice edge they lie on, keyed by its lower corner and axis.
+    let mut welded: HashMap<(Point3<usize>, usize), u32> = HashMap::new();
+    let mut cell_vertices = [0u32; 12];
+
+    for z in 0..resolution.2 as usize - 1 {
+        for y in 0..resolution.1 as usize - 1 {
+            for x in 0..resolution.0 as usize - 1 {
+                let cell = [x, y, z];
+                let case = (0..8)
+                    .filter(|&i| value(corner(cell, i)) > iso)
+                    .fold(0, |case, i| case | (1 << i));
+                if cases[case].is_empty() {
+                    continue;
+                }
+
+                for (e, &(c0, c1)) in EDGES.iter().enumerate() {
+                    let (a, b) = (corner(cell, c0), corner(cell, c1));
+                    let (va, vb) = (value(a), value(b));
+                    if (va > iso) == (vb > iso) {
+                        continue;
+                    }
+                    let next_index = mesh.positions.len() as u32;
+                    let index = *welded.entry((a, e / 4)).or_insert(next_index);
+                    if index == next_index {
+                        let t = (iso - va) / (vb - va);
+                        let mut pos = [0.0; 3];
+                        for (axis, p) in pos.iter_mut().enumerate() {
+                            let (pa, pb) = (a[axis] as f64, b[axis] as f64);
+                            *p = volume.min[axis] + (pa + (pb - pa) * t) * spacing[axis];
+                        }
+                        mesh.normals.push(normal_at(a, b, t, pos));
+                        mesh.positions.push(pos);
+                    }
+                    cell_vertices[e] = index;
+                }
+
+                for triangle in &cases[case] {
+                    mesh.triangles.push([
+                        cell_vertices[triangle[0]],
+                        cell_vertices[triangle[1]],
+                        cell_vertices[triangle[2]],
+                    ]);
+                }
+            }
+        }
+    }
+    mesh
+}
+
+/// Extracts the surface where `grid` crosses `iso`, with values above `iso` counted as inside.
+///
+/// `grid` holds samples on a lattice spanning `volume`, as made by `sample_volume`. Normals
+/// point out of the surface and are estimated from central differences of the grid.
+pub fn marching_cubes(grid: &Grid3d<f64>, volume: Volume, iso: f64) -> Mesh {
+    let size = [grid.width() as usize, grid.height() as usize, grid.depth() as usize];
+    let spacing = volume.spacing((grid.width(), grid.height(), grid.depth()));
+    let gradient = |p: Point3<usize>| {
+        let mut g = [0.0; 3];
+        for (axis, g) in g.iter_mut().enumerate() {
+            let (mut lower, mut upper) = (p, p);
+            lower[axis] = p[axis].saturating_sub(1);
+            upper[axis] = (p[axis] + 1).min(size[axis] - 1);
+            let distance = (upper[axis] - lower[axis]) as f64 * spacing[axis];
+            *g = (grid[(upper[0], upper[1], upper[2])] - grid[(lower[0], lower[1], lower[2])])
+                / distance;
+        }
+        g
+    };
+    march(grid, volume, iso, |a, b, t, _| {
+        let (ga, gb) = (gradient(a), gradient(b));
+        normalized([
+            -(ga[0] + (gb[0] - ga[0]) * t),
+            -(ga[1] + (gb[1] - ga[1]) * t),
+            -(ga[2] + (gb[2] - ga[2]) * t),
+        ])
+    })
+}
+
+/// Samples `noise` over `volume` and extracts the surface where it crosses `iso`.
+///
+/// Normals are taken from the gradient of the noise itself, so they stay smooth even at
+/// resolutions too coarse for the grid to capture the gradient.
+pub fn extract_isosurface<N>(
+    noise: &N,
+    volume: Volume,
+    resolution: (u32, u32, u32),
+    iso: f64,
+) -> Mesh
+where
+    N: Noise3d,
+{
+    let grid = sample_volume(noise, volume, resolution);
+    let spacing = volume.spacing(resolution);
+    let h = 1e-3 * spacing.iter().cloned().fold(f64::INFINITY, f64::min);
+    march(&grid, volume, iso, |_, _, _, pos| {
+        let mut g = [0.0; 3];
+        for (axis, g) in g.iter_mut().enumerate() {
+            let (mut lower, mut upper) = (pos, pos);
+            lower[axis] -= h;
+            upper[axis] += h;
+            *g = (noise.value_at(upper) - noise.value_at(lower)) / (2.0 * h);
+        }
+        normalized([-g[0], -g[1], -g[2]])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapter::FunctionValue;
+    use mesh::dot;
+
+    #[test]
+    fn sphere_is_closed_and_faces_outwards() {
+        let sphere: FunctionValue<Point3<f64>, (f64, f64, f64), _> =
+            FunctionValue::new(|p: &Point3<f64>| 0.8 - dot(*p, *p).sqrt());
+        let volume = Volume::new([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0]);
+        let mesh = extract_isosurface(&sphere, volume, (17, 17, 17), 0.0);
+        assert!(mesh.num_triangles() > 100);
+
+        //Every edge of a closed surface is shared by exactly two triangles, once each way.
+        let mut edges = HashMap::new();
+        for t in &mesh.triangles {
+            for k in 0..3 {
+                *edges.entry((t[k], t[(k + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+        for (&(a, b), &count) in &edges {
+            assert_eq!(count, 1);
+            assert_eq!(edges.get(&(b, a)), Some(&1));
+        }
+
+        for (p, n) in mesh.positions.iter().zip(&mesh.normals) {
+            assert!((dot(*p, *p).sqrt() - 0.8).abs() < 0.02);
+            assert!(dot(normalized(*p), *n) > 0.999);
+        }
+        for i in 0..mesh.num_triangles() {
+            let centroid = mesh.positions[mesh.triangles[i][0] as usize];
+            assert!(dot(mesh.face_normal(i), centroid) > 0.0);
+        }
+
+        let grid_mesh = marching_cubes(&sample_volume(&sphere, volume, (17, 17, 17)), volume, 0.0);
+        assert_eq!(grid_mesh.triangles, mesh.triangles);
+
+        let mut stl = Vec::new();
+        mesh.write_stl(&mut stl).unwrap();
+        assert_eq!(stl.len(), 84 + 50 * mesh.num_triangles());
+    }
+}
@@ -0,0 +1,172 @@
+//! Triangle meshes extracted from noise, and their export to common file formats.
+
+pub mod heightmap;
+pub mod isosurface;
+
+pub use self::heightmap::HeightmapMesher;
+pub use self::isosurface::{extract_isosurface, marching_cubes, sample_volume, Volume};
+
+use std::io::{self, Write};
+
+use noise::Point3;
+
+fn sub(a: Point3<f64>, b: Point3<f64>) -> Point3<f64> {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: Point3<f64>, b: Point3<f64>) -> Point3<f64> {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: Point3<f64>, b: Point3<f64>) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// `v` scaled to unit length, or zero if it has none.
+fn normalized(v: Point3<f64>) -> Point3<f64> {
+    let length = dot(v, v).sqrt();
+    if length > 0.0 {
+        [v[0] / length, v[1] / length, v[2] / length]
+    } else {
+        [0.0; 3]
+    }
+}
+
+fn write_f32_vector<W: Write>(w: &mut W, v: Point3<f64>) -> io::Result<()> {
+    for &c in &v {
+        w.write_all(&(c as f32).to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// An indexed triangle mesh with one normal, and optionally one texture coordinate, per vertex.
+///
+/// Triangles wind counter clockwise when seen from the side their normals face.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<Point3<f64>>,
+    pub normals: Vec<Point3<f64>>,
+    /// Either empty or one per vertex.
+    pub uvs: Vec<[f64; 2]>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+impl Mesh {
+    pub fn new() -> Mesh {
+        Mesh::default()
+    }
+
+    pub fn num_vertices(&self) -> usize {
+        self.positions.len()
+    }
+    pub fn num_triangles(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Unit normal of a triangle from its winding, zero for degenerate triangles.
+    pub fn face_normal(&self, triangle: usize) -> Point3<f64> {
+        let [a, b, c] = self.triangles[triangle];
+        let (a, b, c) = (
+            self.positions[a as usize],
+            self.positions[b as usize],
+            self.positions[c as usize],
+        );
+        normalized(cross(sub(b, a), sub(c, a)))
+    }
+
+    /// Writes the mesh as Wavefront OBJ with positions, normals and texture coordinates.
+    pub fn write_obj<W: Write>(&self, mut w: W) -> io::Result<()> {
+        for p in &self.positions {
+            writeln!(w, "v {} {} {}", p[0], p[1], p[2])?;
+        }
+        for uv in &self.uvs {
+            writeln!(w, "vt {} {}", uv[0], uv[1])?;
+        }
+        for n in &self.normals {
+            writeln!(w, "vn {} {} {}", n[0], n[1], n[2])?;
+        }
+        for t in &self.triangles {
+            let (a, b, c) = (t[0] + 1, t[1] + 1, t[2] + 1);
+            if self.uvs.is_empty() {
+                writeln!(w, "f {}//{} {}//{} {}//{}", a, a, b, b, c, c)?;
+            } else {
+                writeln!(w, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", a, b, c)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_ply_header<W: Write>(&self, w: &mut W, format: &str) -> io::Result<()> {
+        writeln!(w, "ply")?;
+        writeln!(w, "format {} 1.0", format)?;
+        writeln!(w, "element vertex {}", self.positions.len())?;
+        for property in &["x", "y", "z", "nx", "ny", "nz"] {
+            writeln!(w, "property float {}", property)?;
+        }
+        if !self.uvs.is_empty() {
+            writeln!(w, "property float s")?;
+            writeln!(w, "property float t")?;
+        }
+        writeln!(w, "element face {}", self.triangles.len())?;
+        writeln!(w, "property list uchar int vertex_indices")?;
+        writeln!(w, "end_header")
+    }
+
+    /// Writes the mesh as ASCII PLY with positions, normals and texture coordinates.
+    pub fn write_ply_ascii<W: Write>(&self, mut w: W) -> io::Result<()> {
+        self.write_ply_header(&mut w, "ascii")?;
+        for (i, (p, n)) in self.positions.iter().zip(&self.normals).enumerate() {
+            write!(w, "{} {} {} {} {} {}", p[0], p[1], p[2], n[0], n[1], n[2])?;
+            match self.uvs.get(i) {
+                Some(uv) => writeln!(w, " {} {}", uv[0], uv[1])?,
+                None => writeln!(w)?,
+            }
+        }
+        for t in &self.triangles {
+            writeln!(w, "3 {} {} {}", t[0], t[1], t[2])?;
+        }
+        Ok(())
+    }
+
+    /// Writes the mesh as little endian binary PLY.
+    pub fn write_ply_binary<W: Write>(&self, mut w: W) -> io::Result<()> {
+        self.write_ply_header(&mut w, "binary_little_endian")?;
+        for (i, (&p, &n)) in self.positions.iter().zip(&self.normals).enumerate() {
+            write_f32_vector(&mut w, p)?;
+            write_f32_vector(&mut w, n)?;
+            if let Some(uv) = self.uvs.get(i) {
+                w.write_all(&(uv[0] as f32).to_le_bytes())?;
+                w.write_all(&(uv[1] as f32).to_le_bytes())?;
+            }
+        }
+        for t in &self.triangles {
+            w.write_all(&[3])?;
+            for &v in t {
+                w.write_all(&(v as i32).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the mesh as binary STL, which stores face normals and unshared vertices.
+    pub fn write_stl<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let mut header = [0u8; 80];
+        let title = b"noise mesh";
+        header[..title.len()].copy_from_slice(title);
+        w.write_all(&header)?;
+        w.write_all(&(self.triangles.len() as u32).to_le_bytes())?;
+
+        for (i, t) in self.triangles.iter().enumerate() {
+            write_f32_vector(&mut w, self.face_normal(i))?;
+            for &v in t {
+                write_f32_vector(&mut w, self.positions[v as usize])?;
+            }
+            w.write_all(&[0, 0])?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+//! Interval arithmetic on the `(low, high)` output bounds of noise functions.
+//!
+//! Bounds are conservative: the true output always lies within them, but may not reach them.
+//! Unknown bounds are represented by `UNBOUNDED`.
+
+use std::f64;
+
+pub const UNBOUNDED: (f64, f64) = (f64::NEG_INFINITY, f64::INFINITY);
+
+pub fn is_bounded(bounds: (f64, f64)) -> bool {
+    bounds.0.is_finite() && bounds.1.is_finite()
+}
+
+/// Product of two endpoints, where zero times infinity is zero rather than NaN.
+fn mul_endpoints(a: f64, b: f64) -> f64 {
+    if a == 0.0 || b == 0.0 {
+        0.0
+    } else {
+        a * b
+    }
+}
+
+pub fn add(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 + b.0, a.1 + b.1)
+}
+
+pub fn sub(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 - b.1, a.1 - b.0)
+}
+
+pub fn mul(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    let products = [
+        mul_endpoints(a.0, b.0),
+        mul_endpoints(a.0, b.1),
+        mul_endpoints(a.1, b.0),
+        mul_endpoints(a.1, b.1),
+    ];
+    (
+        products.iter().cloned().fold(f64::INFINITY, f64::min),
+        products.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+    )
+}
+
+/// Unbounded whenever the divisor may be zero.
+pub fn div(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    if b.0 <= 0.0 && b.1 >= 0.0 {
+        UNBOUNDED
+    } else {
+        mul(a, (1.0 / b.1, 1.0 / b.0))
+    }
+}
+
+pub fn min(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0.min(b.0), a.1.min(b.1))
+}
+
+pub fn max(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0.max(b.0), a.1.max(b.1))
+}
+
+/// The smallest interval containing both.
+pub fn hull(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0.min(b.0), a.1.max(b.1))
+}
+
+/// Bounds of `value * scale + bias`.
+pub fn affine(a: (f64, f64), scale: f64, bias: f64) -> (f64, f64) {
+    let (low, high) = (mul_endpoints(a.0, scale) + bias, mul_endpoints(a.1, scale) + bias);
+    if scale < 0.0 {
+        (high, low)
+    } else {
+        (low, high)
+    }
+}
+
+pub fn abs(a: (f64, f64)) -> (f64, f64) {
+    if a.0 >= 0.0 {
+        a
+    } else if a.1 <= 0.0 {
+        (-a.1, -a.0)
+    } else {
+        (0.0, a.1.max(-a.0))
+    }
+}
+
+pub fn clamp(a: (f64, f64), low: f64, high: f64) -> (f64, f64) {
+    (a.0.max(low).min(high), a.1.max(low).min(high))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapter::NoiseExt;
+    use noise::fbm::{Fbm1d, Fbm2d, Fbm3d};
+    use noise::{seeded_rng, Noise};
+
+    #[test]
+    fn interval_arithmetic() {
+        assert_eq!(mul((-1.0, 2.0), (-3.0, 0.5)), (-6.0, 3.0));
+        assert_eq!(mul((0.0, 1.0), UNBOUNDED), UNBOUNDED);
+        assert_eq!(mul((0.0, 0.0), UNBOUNDED), (0.0, 0.0));
+        assert_eq!(div((1.0, 2.0), (-1.0, 1.0)), UNBOUNDED);
+        assert_eq!(div((1.0, 2.0), (2.0, 4.0)), (0.25, 1.0));
+        assert_eq!(sub((0.0, 1.0), (0.0, 1.0)), (-1.0, 1.0));
+        assert_eq!(affine((-1.0, 1.0), -2.0, 1.0), (-1.0, 3.0));
+        assert_eq!(abs((-3.0, 2.0)), (0.0, 3.0));
+    }
+
+    #[test]
+    fn fbm_bounds_contain_samples() {
+        let fbm = Fbm2d::new(seeded_rng(7)).with_num_octaves(4);
+        let (low, high) = fbm.output_bounds();
+        assert!(is_bounded((low, high)));
+        for y in 0..64 {
+            for x in 0..64 {
+                let v = fbm.value_at([f64::from(x) * 0.37, f64::from(y) * 0.41]);
+                assert!(low <= v && v <= high, "{} outside of ({}, {})", v, low, high);
+            }
+        }
+
+        let clamped = fbm.abs().clamp(0.25, 10.0);
+        assert_eq!(clamped.output_bounds(), (0.25, high.max(-low)));
+
+        let fbm = Fbm1d::new(seeded_rng(7)).with_num_octaves(4);
+        let (low, high) = fbm.output_bounds();
+        for x in 0..4096 {
+            let v = fbm.value_at(f64::from(x) * 0.013);
+            assert!(low <= v && v <= high, "{} outside of ({}, {})", v, low, high);
+        }
+        //A single octave gets close to the 1d bound of 1, well past half of it.
+        let single = Fbm1d::new(seeded_rng(7)).with_num_octaves(1);
+        let peak = (0..4096)
+            .map(|x| single.value_at(f64::from(x) * 0.013).abs())
+            .fold(0.0, f64::max);
+        assert!(peak > 0.6 && peak <= single.output_bounds().1, "{}", peak);
+
+        let fbm = Fbm3d::new(seeded_rng(7)).with_num_octaves(4);
+        let (low, high) = fbm.output_bounds();
+        for z in 0..16 {
+            for y in 0..16 {
+                for x in 0..16 {
+                    let pos = [f64::from(x) * 0.37, f64::from(y) * 0.41, f64::from(z) * 0.29];
+                    let v = fbm.value_at(pos);
+                    assert!(low <= v && v <= high, "{} outside of ({}, {})", v, low, high);
+                }
+            }
+        }
+    }
+}
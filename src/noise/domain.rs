@@ -0,0 +1,307 @@
+use std::f64;
+use std::fmt;
+
+use cgmath::{Matrix2, Matrix3, Quaternion, Rad, Vector2, Vector3};
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use noise::{Point1, Point2, Point3, PointUtil, SplitPoint};
+
+/// Points that can be rotated and offset to decorrelate the lattices of stacked octaves.
+pub trait DomainPoint: PointUtil<f64> + Copy {
+    type Rotation: Clone + fmt::Debug;
+
+    fn identity_rotation() -> Self::Rotation;
+    fn random_rotation<R: Rng>(rng: &mut R) -> Self::Rotation;
+    /// Returns the rotation equivalent to applying `first` and then `second`.
+    fn compose_rotations(first: &Self::Rotation, second: &Self::Rotation) -> Self::Rotation;
+    fn rotate(self, rotation: &Self::Rotation) -> Self;
+
+    fn random_offset<R: Rng>(rng: &mut R, low: f64, high: f64) -> Self;
+}
+
+/// A rotation about the origin followed by an offset.
+#[derive(Clone, Debug)]
+pub struct DomainTransform<I>
+where
+    I: DomainPoint,
+{
+    rotation: I::Rotation,
+    offset: I,
+}
+
+/// How the input domain of each octave is transformed before sampling.
+///
+/// Rotated octaves sample well outside of the unit domain, so they should be built on an
+/// unbounded gradient provider such as `PermutedGradientTable`.
+#[derive(Clone, Debug)]
+pub enum OctaveRotation<I>
+where
+    I: DomainPoint,
+{
+    /// Every octave samples the untransformed domain.
+    Aligned,
+    /// Octave `n` applies the given transform `n` times, leaving the first octave untouched.
+    Fixed(DomainTransform<I>),
+    /// Each octave gets an independent random rotation and offset derived from the seed.
+    Seeded(u32),
+}
+
+impl<I> DomainTransform<I>
+where
+    I: DomainPoint,
+{
+    pub fn new(rotation: I::Rotation, offset: I) -> DomainTransform<I> {
+        DomainTransform { rotation, offset }
+    }
+
+    pub fn identity() -> DomainTransform<I> {
+        DomainTransform {
+            rotation: I::identity_rotation(),
+            offset: I::saturate(0.0),
+        }
+    }
+
+    pub fn random<R: Rng>(rng: &mut R, offset_low: f64, offset_high: f64) -> DomainTransform<I> {
+        DomainTransform {
+            rotation: I::random_rotation(rng),
+            offset: I::random_offset(rng, offset_low, offset_high),
+        }
+    }
+
+    pub fn rotation(&self) -> &I::Rotation {
+        &self.rotation
+    }
+    pub fn offset(&self) -> I {
+        self.offset
+    }
+
+    #[inline]
+    pub fn apply(&self, pos: I) -> I {
+        pos.rotate(&self.rotation).apply(self.offset, |x, o| x + o)
+    }
+
+    /// Applies the transform to a split point. The rotated cells are computed exactly, so the
+    /// result is as precise far from the origin as near it.
+    pub fn apply_split<S>(&self, pos: S) -> S
+    where
+        S: SplitPoint<Point = I>,
+    {
+        let offsets = self.apply(pos.offsets());
+        pos.map_cells(|cells| cells.rotate(&self.rotation))
+            .shifted(offsets)
+    }
+
+    /// Returns the transform equivalent to applying `self` and then `next`.
+    pub fn then(&self, next: &DomainTransform<I>) -> DomainTransform<I> {
+        DomainTransform {
+            rotation: I::compose_rotations(&self.rotation, &next.rotation),
+            offset: next.apply(self.offset),
+        }
+    }
+}
+
+impl<I> OctaveRotation<I>
+where
+    I: DomainPoint,
+{
+    const SEEDED_OFFSET_RANGE: (f64, f64) = (16.0, 32.0);
+
+    pub fn octave_transform(&self, octave: u32) -> DomainTransform<I> {
+        match *self {
+            OctaveRotation::Aligned => DomainTransform::identity(),
+            OctaveRotation::Fixed(ref step) => {
+                (0..octave).fold(DomainTransform::identity(), |t, _| t.then(step))
+            }
+            OctaveRotation::Seeded(seed) => {
+                let mut rng =
+                    XorShiftRng::from_seed([seed, octave, 0x9E37_79B9, 0x7F4A_7C15]);
+                let (low, high) = Self::SEEDED_OFFSET_RANGE;
+                DomainTransform::random(&mut rng, low, high)
+            }
+        }
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        matches!(*self, OctaveRotation::Aligned)
+    }
+}
+
+impl DomainPoint for Point1<f64> {
+    /// One dimensional domains can only be mirrored.
+    type Rotation = f64;
+
+    fn identity_rotation() -> f64 {
+        1.0
+    }
+    fn random_rotation<R: Rng>(rng: &mut R) -> f64 {
+        if rng.gen() {
+            1.0
+        } else {
+            -1.0
+        }
+    }
+    fn compose_rotations(first: &f64, second: &f64) -> f64 {
+        first * second
+    }
+    #[inline]
+    fn rotate(self, rotation: &f64) -> f64 {
+        self * rotation
+    }
+
+    fn random_offset<R: Rng>(rng: &mut R, low: f64, high: f64) -> f64 {
+        rng.gen_range(low, high)
+    }
+}
+
+impl DomainPoint for Point2<f64> {
+    type Rotation = Matrix2<f64>;
+
+    fn identity_rotation() -> Matrix2<f64> {
+        Matrix2::new(1.0, 0.0, 0.0, 1.0)
+    }
+    fn random_rotation<R: Rng>(rng: &mut R) -> Matrix2<f64> {
+        Matrix2::from_angle(Rad(rng.gen_range(0.0, 2.0 * f64::consts::PI)))
+    }
+    fn compose_rotations(first: &Matrix2<f64>, second: &Matrix2<f64>) -> Matrix2<f64> {
+        second * first
+    }
+    #[inline]
+    fn rotate(self, rotation: &Matrix2<f64>) -> Point2<f64> {
+        (rotation * Vector2::from(self)).into()
+    }
+
+    fn random_offset<R: Rng>(rng: &mut R, low: f64, high: f64) -> Point2<f64> {
+        [rng.gen_range(low, high), rng.gen_range(low, high)]
+    }
+}
+
+impl DomainPoint for Point3<f64> {
+    type Rotation = Matrix3<f64>;
+
+    fn identity_rotation() -> Matrix3<f64> {
+        Matrix3::new(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0)
+    }
+    fn random_rotation<R: Rng>(rng: &mut R) -> Matrix3<f64> {
+        //Uniformly distributed unit quaternion (Shoemake).
+        let u1: f64 = rng.gen();
+        let u2 = rng.gen_range(0.0, 2.0 * f64::consts::PI);
+        let u3 = rng.gen_range(0.0, 2.0 * f64::consts::PI);
+        let (a, b) = ((1.0 - u1).sqrt(), u1.sqrt());
+
+        Matrix3::from(Quaternion::new(
+            b * u3.cos(),
+            a * u2.sin(),
+            a * u2.cos(),
+            b * u3.sin(),
+        ))
+    }
+    fn compose_rotations(first: &Matrix3<f64>, second: &Matrix3<f64>) -> Matrix3<f64> {
+        second * first
+    }
+    #[inline]
+    fn rotate(self, rotation: &Matrix3<f64>) -> Point3<f64> {
+        (rotation * Vector3::from(self)).into()
+    }
+
+    fn random_offset<R: Rng>(rng: &mut R, low: f64, high: f64) -> Point3<f64> {
+        [
+            rng.gen_range(low, high),
+            rng.gen_range(low, high),
+            rng.gen_range(low, high),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapter::RotateInput;
+    use gradient::provider::cube_gradient_table_2d;
+    use gradient::PermutationGradientFactory;
+    use noise::fbm::Fbm2d;
+    use noise::{seeded_rng, Noise, Perlin2d, SplitCoord, SplitNoise};
+
+    fn close(a: Point2<f64>, b: Point2<f64>) -> bool {
+        (a[0] - b[0]).abs() < 1e-12 && (a[1] - b[1]).abs() < 1e-12
+    }
+
+    #[test]
+    fn octave_rotations_and_rotated_octaves() {
+        let pos = [0.3, -1.7];
+        let aligned = OctaveRotation::<Point2<f64>>::Aligned;
+        assert!(aligned.is_aligned());
+        assert_eq!(aligned.octave_transform(3).apply(pos), pos);
+
+        let step = DomainTransform::new(Matrix2::from_angle(Rad(0.5)), [1.0, 2.0]);
+        let fixed = OctaveRotation::Fixed(step.clone());
+        assert_eq!(fixed.octave_transform(0).apply(pos), pos);
+        assert!(close(
+            fixed.octave_transform(2).apply(pos),
+            step.apply(step.apply(pos))
+        ));
+
+        //Seeded transforms are reproducible, differ per octave and preserve distances.
+        let seeded = OctaveRotation::<Point2<f64>>::Seeded(4);
+        let (first, second) = (seeded.octave_transform(1), seeded.octave_transform(2));
+        assert_eq!(first.apply(pos), seeded.octave_transform(1).apply(pos));
+        assert!(!close(first.apply(pos), second.apply(pos)));
+        let origin = first.apply([0.0, 0.0]);
+        let moved = first.apply(pos);
+        let distance = (moved[0] - origin[0]).hypot(moved[1] - origin[1]);
+        assert!((distance - pos[0].hypot(pos[1])).abs() < 1e-12);
+
+        let mut factory =
+            PermutationGradientFactory::new(cube_gradient_table_2d(&mut seeded_rng(2)));
+        let octaves = Perlin2d::build_geometric_octaves_rotated(
+            (2.0, 2.0),
+            3,
+            (2.0, 2.0),
+            2.0,
+            &seeded,
+            &mut factory,
+        );
+        let mut expected = 0.0;
+        for (n, octave) in octaves.octaves().iter().enumerate() {
+            let rotated: &RotateInput<_> = octave.noise();
+            let inner = rotated.inner_noise();
+            let transformed = seeded.octave_transform(n as u32).apply(pos);
+            assert_eq!(rotated.value_at(pos), inner.value_at(transformed));
+            expected += octave.amplitude() * inner.value_at(transformed);
+        }
+        assert!((octaves.value_at(pos) - expected).abs() < 1e-12);
+
+        //The unrotated builder samples the octaves directly, matching aligned rotated octaves.
+        let plain = Perlin2d::build_geometric_octaves((2.0, 2.0), 3, (2.0, 2.0), 2.0, &mut factory);
+        let aligned = Perlin2d::build_geometric_octaves_rotated(
+            (2.0, 2.0),
+            3,
+            (2.0, 2.0),
+            2.0,
+            &aligned,
+            &mut factory,
+        );
+        assert_eq!(plain.value_at(pos), aligned.value_at(pos));
+    }
+
+    #[test]
+    fn rotated_split_points_stay_precise_far_from_the_origin() {
+        //Two points 1e-9 apart, on either side of a cell boundary a trillion cells out.
+        let cell = 1_000_000_000_000;
+        let before = [SplitCoord::new(cell, 1.0 - 1e-9), SplitCoord::new(-cell, 0.5)];
+        let after = [SplitCoord::new(cell + 1, 0.0), SplitCoord::new(-cell, 0.5)];
+        let rotation = OctaveRotation::<Point2<f64>>::Seeded(5);
+        let transform = rotation.octave_transform(2);
+        let (a, b) = (transform.apply_split(before), transform.apply_split(after));
+        let expected = [1e-9, 0.0].rotate(transform.rotation());
+        for axis in 0..2 {
+            let distance = (b[axis].cell() - a[axis].cell()) as f64 + b[axis].offset()
+                - a[axis].offset();
+            assert!((distance - expected[axis]).abs() < 1e-13);
+        }
+
+        let fbm = Fbm2d::new(seeded_rng(21))
+            .with_frequency((4.0, 4.0))
+            .with_octave_rotation(rotation);
+        assert!((fbm.value_at_split(before) - fbm.value_at_split(after)).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt;
+use std::sync::Arc;
+
+use noise::{Noise, Point1, Point2, Point3};
+
+pub type DynNoise1d = DynNoise<Point1<f64>, f64>;
+pub type DynNoise2d = DynNoise<Point2<f64>, (f64, f64)>;
+pub type DynNoise3d = DynNoise<Point3<f64>, (f64, f64, f64)>;
+
+/// A type-erased, reference counted noise function.
+///
+/// Composing adapters produces deeply nested types that can't be named or chosen at runtime.
+/// Wrapping a graph in a `DynNoise` fixes its type to the index and dimension types alone,
+/// so differently built graphs can be stored side by side. Cloning is cheap and shares the
+/// underlying noise.
+pub struct DynNoise<I, D> {
+    noise: Arc<dyn Noise<IndexType = I, DimType = D> + Send + Sync>,
+}
+
+impl<I, D> DynNoise<I, D>
+where
+    I: Clone,
+{
+    pub fn new<N>(noise: N) -> DynNoise<I, D>
+    where
+        N: Noise<IndexType = I, DimType = D> + Send + Sync + 'static,
+    {
+        DynNoise {
+            noise: Arc::new(noise),
+        }
+    }
+
+    pub fn from_arc(
+        noise: Arc<dyn Noise<IndexType = I, DimType = D> + Send + Sync>,
+    ) -> DynNoise<I, D> {
+        DynNoise { noise }
+    }
+
+    pub fn inner_noise(&self) -> &(dyn Noise<IndexType = I, DimType = D> + Send + Sync) {
+        &*self.noise
+    }
+
+    /// Whether both handles refer to the same underlying noise.
+    pub fn ptr_eq(&self, other: &DynNoise<I, D>) -> bool {
+        Arc::ptr_eq(&self.noise, &other.noise)
+    }
+}
+
+impl<I, D> Clone for DynNoise<I, D> {
+    fn clone(&self) -> DynNoise<I, D> {
+        DynNoise {
+            noise: Arc::clone(&self.noise),
+        }
+    }
+}
+
+impl<I, D> fmt::Debug for DynNoise<I, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DynNoise({:p})", Arc::as_ptr(&self.noise) as *const u8)
+    }
+}
+
+impl<I, D> Noise for DynNoise<I, D>
+where
+    I: Clone,
+{
+    type IndexType = I;
+    type DimType = D;
+
+    #[inline]
+    fn value_at(&self, pos: I) -> f64 {
+        self.noise.value_at(pos)
+    }
+    fn value_at_lod(&self, pos: I, footprint: f64) -> f64 {
+        self.noise.value_at_lod(pos, footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        self.noise.output_bounds()
+    }
+    fn frequency(&self) -> D {
+        self.noise.frequency()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapter::{NoiseExt, Sum};
+    use noise::fbm::Fbm2d;
+    use noise::{seeded_rng, Worley2d};
+
+    #[test]
+    fn dyn_noise_matches_the_wrapped_noise() {
+        let fbm = Fbm2d::new(seeded_rng(6)).with_frequency((3.0, 3.0));
+        let erased: DynNoise2d = fbm.clone().into_dyn();
+        for i in 0..50 {
+            let pos = [f64::from(i) * 0.037, f64::from(i % 7) * 0.11];
+            assert_eq!(erased.value_at(pos), fbm.value_at(pos));
+            assert_eq!(erased.value_at_lod(pos, 0.05), fbm.value_at_lod(pos, 0.05));
+        }
+        assert_eq!(erased.output_bounds(), fbm.output_bounds());
+        assert_eq!(erased.frequency(), fbm.frequency());
+
+        let shared = erased.clone();
+        assert!(shared.ptr_eq(&erased));
+        assert!(!shared.ptr_eq(&fbm.clone().into_dyn()));
+
+        //Differently typed graphs can be stored and combined side by side.
+        let worley = Worley2d::new(2);
+        let sum = Sum::new(vec![erased, worley.clone().scale(0.5).into_dyn()]);
+        let pos = [0.4, 0.9];
+        assert_eq!(sum.value_at(pos), fbm.value_at(pos) + 0.5 * worley.value_at(pos));
+    }
+}
@@ -1,6 +1,8 @@
 use std::mem;
 
-use noise::{Noise, Perlin1d, Perlin2d, Perlin3d, Point1, Point2, Point3, TupleUtil, WithFrequency};
+use noise::octave::lod_weight;
+use noise::{bounds, DomainTransform, Noise, OctaveRotation, Perlin1d, Perlin2d, Perlin3d, Point1,
+            Point2, Point3, SplitCoord, SplitNoise, TupleUtil, WithFrequency};
 use interpolate::{self, InterpolationFunction};
 use gradient::{PermutedGradientTable, RandomGradientBuilder1d, RandomGradientBuilder2d,
                RandomGradientBuilder3d};
@@ -12,7 +14,7 @@ pub type DefaultInterpolator = interpolate::Hermite5thOrderInterpolator;
 
 macro_rules! impl_fbm {
     ($name:ident, $freq:ty, $dim:ty, $builder:ident,
-     $noise:ident, $vector:ty, $point:ty, $default_freq:expr, $default_scale:expr) => (
+     $noise:ident, $vector:ty, $point:ty, $split:ty, $default_freq:expr, $default_scale:expr) => (
 
         #[derive(Clone, Debug)]
         pub struct $name<P, R>
@@ -25,6 +27,8 @@ macro_rules! impl_fbm {
             persistance: f64,
             interp: P,
             octaves: Vec<$noise<PermutedGradientTable<$vector>, P>>,
+            rotation: OctaveRotation<$point>,
+            domain_transforms: Vec<DomainTransform<$point>>,
             rng: R,
         }
 
@@ -36,7 +40,9 @@ macro_rules! impl_fbm {
                     persistance: Self::DEFAULT_PERSISTANCE,
                     interp: DefaultInterpolator::default(),
                     octaves: Vec::new(),
-                    rng: rng,
+                    rotation: OctaveRotation::Aligned,
+                    domain_transforms: Vec::new(),
+                    rng,
                 };
                 fbm.build_noise(Self::DEFAULT_NUM_OCTAVES);
                 fbm
@@ -62,13 +68,15 @@ macro_rules! impl_fbm {
                     interp: interp.clone(),
                     octaves: self.octaves.into_iter()
                         .map(|x| x.with_interpolator(interp.clone())).collect(),
+                    rotation: self.rotation,
+                    domain_transforms: self.domain_transforms,
                     rng: self.rng,
                 }
             }
 
             pub fn with_frequency(self, frequency: $dim) -> Self {
                 let mut new = $name {
-                    frequency: frequency,
+                    frequency,
                     ..self
                 };
                 new.set_new_noise_frequencies();
@@ -76,7 +84,7 @@ macro_rules! impl_fbm {
             }
             pub fn with_frequency_scaling(self, frequency_scaling: $dim) -> Self {
                 let mut new = $name {
-                    frequency_scaling: frequency_scaling,
+                    frequency_scaling,
                     ..self
                 };
                 new.set_new_noise_frequencies();
@@ -91,6 +99,16 @@ macro_rules! impl_fbm {
                 new
             }
 
+            /// Transforms the input domain of each octave to hide lattice alignment.
+            pub fn with_octave_rotation(self, rotation: OctaveRotation<$point>) -> Self {
+                let mut new = $name {
+                    rotation,
+                    ..self
+                };
+                new.build_domain_transforms();
+                new
+            }
+
             pub fn with_persistance(self, persistance: f64) -> Self {
                 $name {
                     persistance,
@@ -109,6 +127,9 @@ macro_rules! impl_fbm {
             pub fn octaves(&self) -> &Vec<$noise<PermutedGradientTable<$vector>, P>> {
                 &self.octaves
             }
+            pub fn octave_rotation(&self) -> &OctaveRotation<$point> {
+                &self.rotation
+            }
 
             fn make_default_gradient_provider(&mut self, size: u32)
                 -> PermutedGradientTable<$vector>
@@ -130,6 +151,17 @@ macro_rules! impl_fbm {
                 }
 
                 self.octaves = octaves;
+                self.build_domain_transforms();
+            }
+
+            fn build_domain_transforms(&mut self) {
+                self.domain_transforms = if self.rotation.is_aligned() {
+                    Vec::new()
+                } else {
+                    (0..self.num_octaves() as u32)
+                        .map(|i| self.rotation.octave_transform(i))
+                        .collect()
+                };
             }
 
             fn set_new_noise_frequencies(&mut self) {
@@ -152,6 +184,9 @@ macro_rules! impl_fbm {
             type DimType = $dim;
 
             fn value_at(&self, pos: Self::IndexType) -> f64 {
+                self.value_at_lod(pos, 0.0)
+            }
+            fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
                 let amplitude_multiplier: f64 = 1.0
                     / (0..self.num_octaves())
                         .map(|x| 1.0 / (self.persistance.powi(x as i32)))
@@ -159,21 +194,66 @@ macro_rules! impl_fbm {
 
                 let mut amplitude = amplitude_multiplier;
                 let mut val = 0.0;
-                for o in &self.octaves {
-                    let octave_val = o.value_at(pos);
-                    let scaled_val = octave_val * amplitude;
-
-                    val += scaled_val;
-
+                for (i, o) in self.octaves.iter().enumerate() {
+                    let weight = lod_weight(o.frequency().max_component(), footprint);
+                    if weight > 0.0 {
+                        let octave_pos = match self.domain_transforms.get(i) {
+                            Some(transform) => transform.apply(pos),
+                            None => pos,
+                        };
+                        val += o.value_at(octave_pos) * amplitude * weight;
+                    }
                     amplitude /= self.persistance;
                 }
-
                 val
             }
+            fn output_bounds(&self) -> (f64, f64) {
+                let mut amplitude = 1.0
+                    / (0..self.num_octaves())
+                        .map(|x| 1.0 / (self.persistance.powi(x as i32)))
+                        .sum::<f64>();
+                let mut total = (0.0, 0.0);
+                for o in &self.octaves {
+                    total = bounds::add(total, bounds::affine(o.output_bounds(), amplitude, 0.0));
+                    amplitude /= self.persistance;
+                }
+                total
+            }
             fn frequency(&self) -> Self::DimType {
                 self.frequency
             }
         }
+        impl<P, R> SplitNoise for $name<P, R>
+        where P: InterpolationFunction + Clone,
+              R: Rng + Clone,
+        {
+            type SplitIndex = $split;
+
+            fn value_at_split(&self, pos: Self::SplitIndex) -> f64 {
+                self.value_at_split_lod(pos, 0.0)
+            }
+            fn value_at_split_lod(&self, pos: Self::SplitIndex, footprint: f64) -> f64 {
+                let amplitude_multiplier: f64 = 1.0
+                    / (0..self.num_octaves())
+                        .map(|x| 1.0 / (self.persistance.powi(x as i32)))
+                        .sum::<f64>();
+
+                let mut amplitude = amplitude_multiplier;
+                let mut val = 0.0;
+                for (i, o) in self.octaves.iter().enumerate() {
+                    let weight = lod_weight(o.frequency().max_component(), footprint);
+                    if weight > 0.0 {
+                        let octave_pos = match self.domain_transforms.get(i) {
+                            Some(transform) => transform.apply_split(pos),
+                            None => pos,
+                        };
+                        val += o.value_at_split(octave_pos) * amplitude * weight;
+                    }
+                    amplitude /= self.persistance;
+                }
+                val
+            }
+        }
     );
 }
 
@@ -185,6 +265,7 @@ impl_fbm!(
     Perlin1d,
     f64,
     Point1<f64>,
+    SplitCoord,
     1.0,
     2.0
 );
@@ -196,6 +277,7 @@ impl_fbm!(
     Perlin2d,
     Vector2<f64>,
     Point2<f64>,
+    Point2<SplitCoord>,
     (1.0, 1.0),
     (2.0, 2.0)
 );
@@ -207,6 +289,7 @@ impl_fbm!(
     Perlin3d,
     Vector3<f64>,
     Point3<f64>,
+    Point3<SplitCoord>,
     (1.0, 1.0, 1.0),
     (2.0, 2.0, 2.0)
 );
@@ -1,14 +1,30 @@
+pub mod bounds;
+pub mod domain;
+pub mod dynamic;
 pub mod fbm;
 pub mod perlin;
 pub mod point;
 pub mod octave;
+pub mod split;
+pub mod worley;
 
 pub use noise::perlin::{Perlin1d, Perlin2d, Perlin3d};
+pub use noise::domain::{DomainPoint, DomainTransform, OctaveRotation};
+pub use noise::dynamic::{DynNoise, DynNoise1d, DynNoise2d, DynNoise3d};
 pub use noise::octave::{Octave, OctaveNoise};
 pub use noise::point::{Point1, Point2, Point3, Point4, PointUtil};
+pub use noise::split::{SplitCoord, SplitNoise, SplitPoint};
+pub use noise::worley::{Worley2d, WorleyCell, WorleyOutput};
+
+use rand::{SeedableRng, XorShiftRng};
 
 use adapter::{Extension2d, Extension3d, Slice1d, Slice2d};
 
+/// A deterministic random number generator for building reproducible noise from a seed.
+pub fn seeded_rng(seed: u32) -> XorShiftRng {
+    XorShiftRng::from_seed([seed, 0x9E37_79B9, 0x85EB_CA6B, 0xC2B2_AE35])
+}
+
 pub trait WithFrequency: Noise {
     fn with_frequency(self, frequency: Self::DimType) -> Self;
 }
@@ -19,6 +35,19 @@ pub trait Noise {
 
     fn value_at(&self, pos: Self::IndexType) -> f64;
 
+    /// The value at `pos` for a sample covering `footprint` units of noise space, leaving out
+    /// detail too fine for the footprint to resolve. Noise without separable detail returns
+    /// `value_at(pos)`.
+    fn value_at_lod(&self, pos: Self::IndexType, _footprint: f64) -> f64 {
+        self.value_at(pos)
+    }
+
+    /// Conservative `(low, high)` bounds of the values this noise can produce, derived from
+    /// its structure rather than by sampling. Infinite when unknown, as for arbitrary closures.
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::UNBOUNDED
+    }
+
     fn frequency(&self) -> Self::DimType;
 }
 
@@ -73,6 +102,12 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         (*self).value_at(pos)
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        (*self).value_at_lod(pos, footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        (*self).output_bounds()
+    }
     fn frequency(&self) -> Self::DimType {
         (*self).frequency()
     }
@@ -88,6 +123,12 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         (**self).value_at(pos)
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        (**self).value_at_lod(pos, footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        (**self).output_bounds()
+    }
     fn frequency(&self) -> Self::DimType {
         (**self).frequency()
     }
@@ -103,6 +144,12 @@ where
     fn value_at(&self, pos: Self::IndexType) -> f64 {
         (**self).value_at(pos)
     }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        (**self).value_at_lod(pos, footprint)
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        (**self).output_bounds()
+    }
     fn frequency(&self) -> Self::DimType {
         (**self).frequency()
     }
@@ -126,6 +173,7 @@ where
 
 pub trait TupleUtil<T> {
     fn max(&self, other: &Self) -> Self;
+    fn max_component(&self) -> T;
     fn saturate(val: f64) -> Self;
     fn apply<F>(self, rhs: Self, f: F) -> Self
     where
@@ -144,6 +192,9 @@ impl TupleUtil<f64> for f64 {
     fn max(&self, rhs: &f64) -> f64 {
         f64::max(*self, *rhs)
     }
+    fn max_component(&self) -> f64 {
+        *self
+    }
     fn saturate(val: f64) -> f64 {
         val
     }
@@ -159,6 +210,9 @@ impl TupleUtil<f64> for (f64,) {
     fn max(&self, rhs: &(f64,)) -> (f64,) {
         (self.0.max(rhs.0),)
     }
+    fn max_component(&self) -> f64 {
+        self.0
+    }
     fn saturate(val: f64) -> (f64,) {
         (val,)
     }
@@ -174,6 +228,9 @@ impl TupleUtil<f64> for (f64, f64) {
     fn max(&self, rhs: &(f64, f64)) -> (f64, f64) {
         (self.0.max(rhs.0), self.1.max(rhs.1))
     }
+    fn max_component(&self) -> f64 {
+        self.0.max(self.1)
+    }
     fn saturate(val: f64) -> (f64, f64) {
         (val, val)
     }
@@ -188,6 +245,9 @@ impl TupleUtil<f64> for (f64, f64, f64) {
     fn max(&self, rhs: &(f64, f64, f64)) -> (f64, f64, f64) {
         (self.0.max(rhs.0), self.1.max(rhs.1), self.2.max(rhs.2))
     }
+    fn max_component(&self) -> f64 {
+        self.0.max(self.1).max(self.2)
+    }
     fn saturate(val: f64) -> (f64, f64, f64) {
         (val, val, val)
     }
@@ -207,6 +267,9 @@ impl TupleUtil<f64> for (f64, f64, f64, f64) {
             self.3.max(rhs.3),
         )
     }
+    fn max_component(&self) -> f64 {
+        self.0.max(self.1).max(self.2).max(self.3)
+    }
     fn saturate(val: f64) -> (f64, f64, f64, f64) {
         (val, val, val, val)
     }
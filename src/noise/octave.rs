@@ -1,6 +1,15 @@
 use std::fmt;
 
-use noise::{Noise, TupleUtil, WithFrequency};
+use adapter::RotateInput;
+use noise::{bounds, DomainPoint, Noise, OctaveRotation, SplitNoise, TupleUtil, WithFrequency};
+
+/// How much of an octave with `frequency` to keep in a sample covering `footprint` units.
+///
+/// Octaves with at least four samples per cycle are kept whole, and fade out linearly until
+/// the Nyquist limit of two samples per cycle, past which they would only add aliasing.
+pub fn lod_weight(frequency: f64, footprint: f64) -> f64 {
+    (2.0 - 4.0 * frequency * footprint).clamp(0.0, 1.0)
+}
 
 #[derive(Clone, Debug)]
 pub struct Octave<T: Noise> {
@@ -87,7 +96,7 @@ where
             .map(|(x, f)| x.with_frequency((*f).clone()))
             .collect();
 
-        OctaveNoise { octaves: octaves }
+        OctaveNoise { octaves }
     }
 
     pub fn with_geometric_frequencies(
@@ -120,7 +129,13 @@ where
     fn value_at(&self, pos: T::IndexType) -> f64 {
         self.noise.value_at(pos) * self.amplitude
     }
+    fn value_at_lod(&self, pos: T::IndexType, footprint: f64) -> f64 {
+        self.noise.value_at_lod(pos, footprint) * self.amplitude
+    }
 
+    fn output_bounds(&self) -> (f64, f64) {
+        bounds::affine(self.noise.output_bounds(), self.amplitude, 0.0)
+    }
     fn frequency(&self) -> T::DimType {
         self.noise.frequency()
     }
@@ -129,7 +144,7 @@ where
 impl<T> Noise for OctaveNoise<T>
 where
     T: Noise,
-    T::DimType: Default,
+    T::DimType: Default + TupleUtil<f64>,
 {
     type IndexType = T::IndexType;
     type DimType = T::DimType;
@@ -139,7 +154,22 @@ where
             .iter()
             .fold(0.0, |l, o| l + o.value_at(pos.clone()))
     }
+    fn value_at_lod(&self, pos: T::IndexType, footprint: f64) -> f64 {
+        let mut val = 0.0;
+        for o in &self.octaves {
+            let weight = lod_weight(o.frequency().max_component(), footprint);
+            if weight > 0.0 {
+                val += o.value_at_lod(pos.clone(), footprint) * weight;
+            }
+        }
+        val
+    }
 
+    fn output_bounds(&self) -> (f64, f64) {
+        self.octaves
+            .iter()
+            .fold((0.0, 0.0), |b, o| bounds::add(b, o.output_bounds()))
+    }
     fn frequency(&self) -> T::DimType {
         if self.octaves.is_empty() {
             self.octaves[0].frequency()
@@ -149,6 +179,44 @@ where
     }
 }
 
+impl<T> SplitNoise for Octave<T>
+where
+    T: SplitNoise,
+{
+    type SplitIndex = T::SplitIndex;
+
+    fn value_at_split(&self, pos: T::SplitIndex) -> f64 {
+        self.noise.value_at_split(pos) * self.amplitude
+    }
+    fn value_at_split_lod(&self, pos: T::SplitIndex, footprint: f64) -> f64 {
+        self.noise.value_at_split_lod(pos, footprint) * self.amplitude
+    }
+}
+
+impl<T> SplitNoise for OctaveNoise<T>
+where
+    T: SplitNoise,
+    T::DimType: Default + TupleUtil<f64>,
+{
+    type SplitIndex = T::SplitIndex;
+
+    fn value_at_split(&self, pos: T::SplitIndex) -> f64 {
+        self.octaves
+            .iter()
+            .fold(0.0, |l, o| l + o.value_at_split(pos))
+    }
+    fn value_at_split_lod(&self, pos: T::SplitIndex, footprint: f64) -> f64 {
+        let mut val = 0.0;
+        for o in &self.octaves {
+            let weight = lod_weight(o.frequency().max_component(), footprint);
+            if weight > 0.0 {
+                val += o.value_at_split_lod(pos, footprint) * weight;
+            }
+        }
+        val
+    }
+}
+
 impl<T> fmt::Display for Octave<T>
 where
     T: Noise,
@@ -173,6 +241,7 @@ where
     }
 }
 
+/// Builds octaves with geometrically growing frequencies and shrinking amplitudes.
 pub fn build_geometric_fractal_noise<N, F>(
     initial_frequency: N::DimType,
     num_octaves: u32,
@@ -195,13 +264,63 @@ where
     let mut frequency = initial_frequency;
     for i in 0..num_octaves {
         let amplitude = (1.0 / persistance.powi(i as i32 + 1)) * amplitude_multiplier;
-        let octave = Octave::new(
-            noise_builder(i as u32, frequency.clone(), amplitude),
-            amplitude,
-        );
+        let octave = Octave::new(noise_builder(i, frequency.clone(), amplitude), amplitude);
         frequency = frequency.apply(scaling.clone(), |f, s| f * s);
         octaves.push(octave);
     }
 
     OctaveNoise::from_octaves(octaves)
 }
+
+/// Like `build_geometric_fractal_noise`, but each octave samples a domain transformed by
+/// `rotation`, so that the lattices of the octaves need not line up.
+pub fn build_geometric_fractal_noise_rotated<N, F>(
+    initial_frequency: N::DimType,
+    num_octaves: u32,
+    frequency_scaling: N::DimType,
+    persistance: f64,
+    rotation: &OctaveRotation<N::IndexType>,
+    noise_builder: &mut F,
+) -> OctaveNoise<RotateInput<N>>
+where
+    N: Noise,
+    N::IndexType: DomainPoint,
+    N::DimType: TupleUtil<f64> + Clone + fmt::Debug,
+    F: FnMut(u32, N::DimType, f64) -> N,
+{
+    build_geometric_fractal_noise(
+        initial_frequency,
+        num_octaves,
+        frequency_scaling,
+        persistance,
+        &mut |i, frequency, amplitude| {
+            RotateInput::new(
+                noise_builder(i, frequency, amplitude),
+                rotation.octave_transform(i),
+            )
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lod_weight;
+    use noise::fbm::Fbm2d;
+    use noise::{seeded_rng, Noise};
+
+    #[test]
+    fn lod_drops_unresolvable_octaves() {
+        assert_eq!(lod_weight(4.0, 1.0 / 16.0), 1.0);
+        assert_eq!(lod_weight(6.0, 1.0 / 16.0), 0.5);
+        assert_eq!(lod_weight(8.0, 1.0 / 16.0), 0.0);
+
+        let fbm = Fbm2d::new(seeded_rng(11))
+            .with_frequency((2.0, 2.0))
+            .with_num_octaves(6);
+        let pos = [0.3, 0.7];
+        assert_eq!(fbm.value_at_lod(pos, 1e-6), fbm.value_at(pos));
+        assert!(fbm.value_at_lod(pos, 1.0 / 16.0) != fbm.value_at(pos));
+        //Even the lowest octave is above the Nyquist limit of a whole unit footprint.
+        assert_eq!(fbm.value_at_lod(pos, 1.0), 0.0);
+    }
+}
@@ -5,12 +5,24 @@ use cgmath::{InnerSpace, Vector3};
 
 use grid::{Grid1d, Grid2d, Grid3d};
 use interpolate::{self, InterpolationFunction, Lerp};
-use noise::{Noise, Noise1d, Noise2d, Noise3d, Point1, Point2, Point3, TupleUtil, WithFrequency};
+use noise::{Noise, Noise1d, Noise2d, Noise3d, Point1, Point2, Point3, SplitCoord, SplitNoise,
+            SplitPoint, TupleUtil, WithFrequency};
 use gradient::{GradientFactory, GradientProvider};
-use noise::octave::{build_geometric_fractal_noise, OctaveNoise};
+use noise::octave::{build_geometric_fractal_noise, build_geometric_fractal_noise_rotated,
+                    OctaveNoise};
+use noise::OctaveRotation;
+use adapter::RotateInput;
 
 pub type DefaultInterpolator = interpolate::Hermite5thOrderInterpolator;
 
+//Bounds on the output of each Perlin noise, assuming gradients of at most unit length and an
+//interpolation function with s(x) <= x on [0, 1/2]. The extreme is reached at the center of a
+//cell, half the cell diagonal away from every corner; 1d values are then doubled and 2d and 3d
+//values scaled by sqrt(2). `from_grid` doesn't check its gradients, so longer ones exceed these.
+const PERLIN_1D_BOUND: f64 = 1.0;
+const PERLIN_2D_BOUND: f64 = 1.0;
+const PERLIN_3D_BOUND: f64 = 1.224_744_871_391_589;
+
 macro_rules! size_tuple_to_frequency {
     ($name:ident, ($($idx:tt),+)) => (
         (
@@ -24,6 +36,19 @@ macro_rules! size_tuple_to_frequency {
     );
 }
 
+//The indices of a lattice cell's lower and upper gradients, wrapped as documented on `new`, or
+//around the `u32` range for unbounded providers.
+fn wrap_cell(cell: i64, max: Option<u32>) -> (u32, u32) {
+    match max {
+        Some(0) => (0, 0),
+        Some(max) => {
+            let cell = cell.rem_euclid(i64::from(max)) as u32;
+            (cell, cell + 1)
+        }
+        None => (cell as u32, (cell as u32).wrapping_add(1)),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Perlin1d<G, P>
 where
@@ -59,6 +84,9 @@ impl<G> Perlin1d<G, DefaultInterpolator>
 where
     G: GradientProvider<Point1<u32>, DimType = u32, Output = f64>,
 {
+    /// A bounded provider, such as a grid, holds gradients for lattice points `0` through its
+    /// `max_dimensions`. Outside of that range the lattice wraps around every `max` cells, which
+    /// keeps the noise continuous but only tiles smoothly if the last gradients repeat the first.
     pub fn new(frequency: f64, gradients: G) -> Perlin1d<G, DefaultInterpolator> {
         if let Some(dim) = gradients.max_dimensions() {
             assert!(frequency <= size_tuple_to_frequency!(dim),
@@ -93,9 +121,38 @@ where
             },
         )
     }
+
+    /// Like `build_geometric_octaves`, with each octave transformed by `rotation`.
+    pub fn build_geometric_octaves_rotated<F>(
+        initial_frequency: <Self as Noise>::DimType,
+        num_octaves: u32,
+        frequency_scaling: <Self as Noise>::DimType,
+        persistance: f64,
+        rotation: &OctaveRotation<Point1<f64>>,
+        gradient_factory: &mut F,
+    ) -> OctaveNoise<RotateInput<Perlin1d<G, DefaultInterpolator>>>
+    where
+        <Self as Noise>::DimType: TupleUtil<f64>,
+        F: GradientFactory<f64, f64, Index = Point1<u32>, Output = G>,
+    {
+        build_geometric_fractal_noise_rotated(
+            initial_frequency,
+            num_octaves,
+            frequency_scaling,
+            persistance,
+            rotation,
+            &mut move |n, frequency, _| {
+                let g = gradient_factory.build(n, frequency);
+                Perlin1d::new(frequency, g)
+            },
+        )
+    }
 }
 
 impl Perlin1d<Grid1d<f64>, DefaultInterpolator> {
+    /// One lattice cell per gradient over the unit interval. Cells wrap like `new`, so the last
+    /// cell before 1 already blends back into the first gradient. `output_bounds` assumes the
+    /// gradients lie within [-1, 1].
     pub fn from_grid(grid: Grid1d<f64>) -> Perlin1d<Grid1d<f64>, DefaultInterpolator> {
         Perlin1d {
             frequency: grid.len() as f64,
@@ -116,7 +173,7 @@ where
                 "The gradient provider has a smaller maximum size than the requested noise frequency.");
         }
         Self {
-            frequency: frequency,
+            frequency,
             ..self
         }
     }
@@ -141,7 +198,7 @@ where
 
 impl<G, P> Noise for Perlin1d<G, P>
 where
-    G: GradientProvider<Point1<u32>, Output = f64>,
+    G: GradientProvider<Point1<u32>, DimType = u32, Output = f64>,
     P: InterpolationFunction,
 {
     type IndexType = Point1<f64>;
@@ -149,10 +206,38 @@ where
 
     fn value_at(&self, pos: f64) -> f64 {
         let cell_pos = pos * self.width();
-        let rel_pos = cell_pos - cell_pos.floor();
+        let cell = cell_pos.floor();
+        self.lattice_value(cell as i64, cell_pos - cell)
+    }
+
+    fn output_bounds(&self) -> (f64, f64) {
+        (-PERLIN_1D_BOUND, PERLIN_1D_BOUND)
+    }
+    fn frequency(&self) -> f64 {
+        self.frequency
+    }
+}
 
-        let x_0 = cell_pos as u32;
-        let x_1 = x_0 + 1;
+impl<G, P> SplitNoise for Perlin1d<G, P>
+where
+    G: GradientProvider<Point1<u32>, DimType = u32, Output = f64>,
+    P: InterpolationFunction,
+{
+    type SplitIndex = SplitCoord;
+
+    fn value_at_split(&self, pos: SplitCoord) -> f64 {
+        let cell_pos = pos.scaled(self.width());
+        self.lattice_value(cell_pos.cell(), cell_pos.offset())
+    }
+}
+
+impl<G, P> Perlin1d<G, P>
+where
+    G: GradientProvider<Point1<u32>, DimType = u32, Output = f64>,
+    P: InterpolationFunction,
+{
+    fn lattice_value(&self, cell: i64, rel_pos: f64) -> f64 {
+        let (x_0, x_1) = wrap_cell(cell, self.gradients.max_dimensions());
 
         let gradients = [
             *self.gradients.get_gradient(x_0),
@@ -175,16 +260,15 @@ where
 
         Lerp::lerp(values[0], values[1], interp_coeff) * 2.0
     }
-
-    fn frequency(&self) -> f64 {
-        self.frequency
-    }
 }
 
 impl<G> Perlin2d<G, DefaultInterpolator>
 where
     G: GradientProvider<Point2<u32>, DimType = (u32, u32), Output = Vector2<f64>>,
 {
+    /// A bounded provider, such as a grid, holds gradients for lattice points `0` through its
+    /// `max_dimensions`. Outside of that range the lattice wraps around every `max` cells, which
+    /// keeps the noise continuous but only tiles smoothly if the last gradients repeat the first.
     pub fn new(frequency: (f64, f64), gradients: G) -> Perlin2d<G, DefaultInterpolator> {
         if let Some(dim) = gradients.max_dimensions() {
             assert!(frequency <= size_tuple_to_frequency!(dim, (0, 1)),
@@ -219,9 +303,38 @@ where
             },
         )
     }
+
+    /// Like `build_geometric_octaves`, with each octave transformed by `rotation`.
+    pub fn build_geometric_octaves_rotated<F>(
+        initial_frequency: <Self as Noise>::DimType,
+        num_octaves: u32,
+        frequency_scaling: <Self as Noise>::DimType,
+        persistance: f64,
+        rotation: &OctaveRotation<Point2<f64>>,
+        gradient_factory: &mut F,
+    ) -> OctaveNoise<RotateInput<Perlin2d<G, DefaultInterpolator>>>
+    where
+        <Self as Noise>::DimType: TupleUtil<f64>,
+        F: GradientFactory<Vector2<f64>, (f64, f64), Index = Point2<u32>, Output = G>,
+    {
+        build_geometric_fractal_noise_rotated(
+            initial_frequency,
+            num_octaves,
+            frequency_scaling,
+            persistance,
+            rotation,
+            &mut move |n, frequency, _| {
+                let g = gradient_factory.build(n, frequency);
+                Perlin2d::new(frequency, g)
+            },
+        )
+    }
 }
 
 impl Perlin2d<Grid2d<Vector2<f64>>, DefaultInterpolator> {
+    /// One lattice cell per gradient along each axis of the unit domain. Cells wrap like `new`,
+    /// so the last cells before 1 already blend back into the first gradients. `output_bounds`
+    /// assumes the gradients are at most unit length.
     pub fn from_grid(
         grid: Grid2d<Vector2<f64>>,
     ) -> Perlin2d<Grid2d<Vector2<f64>>, DefaultInterpolator> {
@@ -261,7 +374,7 @@ where
                 "The gradient provider has a smaller maximum size than the requested noise frequency.");
         }
         Self {
-            frequency: frequency,
+            frequency,
             ..self
         }
     }
@@ -269,22 +382,57 @@ where
 
 impl<G, P> Noise for Perlin2d<G, P>
 where
-    G: GradientProvider<Point2<u32>, Output = Vector2<f64>>,
+    G: GradientProvider<Point2<u32>, DimType = (u32, u32), Output = Vector2<f64>>,
     P: InterpolationFunction,
 {
     type IndexType = Point2<f64>;
     type DimType = (f64, f64);
 
     fn value_at(&self, pos: Point2<f64>) -> f64 {
-        let cell_pos = Vector2::new(pos[0] * self.width(), pos[1] * self.height());
-        let rel_x = cell_pos.x - f64::floor(cell_pos.x);
-        let rel_y = cell_pos.y - f64::floor(cell_pos.y);
+        let cell_pos = [pos[0] * self.width(), pos[1] * self.height()];
+        let cell = [cell_pos[0].floor(), cell_pos[1].floor()];
+        self.lattice_value(
+            [cell[0] as i64, cell[1] as i64],
+            [cell_pos[0] - cell[0], cell_pos[1] - cell[1]],
+        )
+    }
+
+    fn output_bounds(&self) -> (f64, f64) {
+        (-PERLIN_2D_BOUND, PERLIN_2D_BOUND)
+    }
+    fn frequency(&self) -> (f64, f64) {
+        self.frequency
+    }
+}
+
+impl<G, P> SplitNoise for Perlin2d<G, P>
+where
+    G: GradientProvider<Point2<u32>, DimType = (u32, u32), Output = Vector2<f64>>,
+    P: InterpolationFunction,
+{
+    type SplitIndex = Point2<SplitCoord>;
+
+    fn value_at_split(&self, pos: Point2<SplitCoord>) -> f64 {
+        let cell_pos = pos.scaled([self.width(), self.height()]);
+        self.lattice_value(
+            [cell_pos[0].cell(), cell_pos[1].cell()],
+            cell_pos.offsets(),
+        )
+    }
+}
+
+impl<G, P> Perlin2d<G, P>
+where
+    G: GradientProvider<Point2<u32>, DimType = (u32, u32), Output = Vector2<f64>>,
+    P: InterpolationFunction,
+{
+    fn lattice_value(&self, cell: Point2<i64>, rel_pos: Point2<f64>) -> f64 {
+        let (rel_x, rel_y) = (rel_pos[0], rel_pos[1]);
         let rel_pos = Vector2::new(rel_x, rel_y);
 
-        let x_0 = cell_pos.x as u32;
-        let x_1 = x_0 + 1;
-        let y_0 = cell_pos.y as u32;
-        let y_1 = y_0 + 1;
+        let dim = self.gradients.max_dimensions();
+        let (x_0, x_1) = wrap_cell(cell[0], dim.map(|d| d.0));
+        let (y_0, y_1) = wrap_cell(cell[1], dim.map(|d| d.1));
 
         let gradients = [
             *self.gradients.get_gradient([x_0, y_0]),
@@ -318,16 +466,15 @@ where
 
         Lerp::lerp(p1, p2, interp_y) * f64::consts::SQRT_2
     }
-
-    fn frequency(&self) -> (f64, f64) {
-        self.frequency
-    }
 }
 
 impl<G> Perlin3d<G, DefaultInterpolator>
 where
     G: GradientProvider<Point3<u32>, DimType = (u32, u32, u32), Output = Vector3<f64>>,
 {
+    /// A bounded provider, such as a grid, holds gradients for lattice points `0` through its
+    /// `max_dimensions`. Outside of that range the lattice wraps around every `max` cells, which
+    /// keeps the noise continuous but only tiles smoothly if the last gradients repeat the first.
     pub fn new(frequency: (f64, f64, f64), gradients: G) -> Perlin3d<G, DefaultInterpolator> {
         if let Some(dim) = gradients.max_dimensions() {
             assert!(frequency <= size_tuple_to_frequency!(dim, (0, 1, 2)),
@@ -362,9 +509,38 @@ where
             },
         )
     }
+
+    /// Like `build_geometric_octaves`, with each octave transformed by `rotation`.
+    pub fn build_geometric_octaves_rotated<F>(
+        initial_frequency: <Self as Noise>::DimType,
+        num_octaves: u32,
+        frequency_scaling: <Self as Noise>::DimType,
+        persistance: f64,
+        rotation: &OctaveRotation<Point3<f64>>,
+        gradient_factory: &mut F,
+    ) -> OctaveNoise<RotateInput<Perlin3d<G, DefaultInterpolator>>>
+    where
+        <Self as Noise>::DimType: TupleUtil<f64>,
+        F: GradientFactory<Vector3<f64>, (f64, f64, f64), Index = Point3<u32>, Output = G>,
+    {
+        build_geometric_fractal_noise_rotated(
+            initial_frequency,
+            num_octaves,
+            frequency_scaling,
+            persistance,
+            rotation,
+            &mut move |n, frequency, _| {
+                let g = gradient_factory.build(n, frequency);
+                Perlin3d::new(frequency, g)
+            },
+        )
+    }
 }
 
 impl Perlin3d<Grid3d<Vector3<f64>>, DefaultInterpolator> {
+    /// One lattice cell per gradient along each axis of the unit domain. Cells wrap like `new`,
+    /// so the last cells before 1 already blend back into the first gradients. `output_bounds`
+    /// assumes the gradients are at most unit length.
     pub fn from_grid(
         grid: Grid3d<Vector3<f64>>,
     ) -> Perlin3d<Grid3d<Vector3<f64>>, DefaultInterpolator> {
@@ -408,7 +584,7 @@ where
                 "The gradient provider has a smaller maximum size than the requested noise frequency.");
         }
         Self {
-            frequency: frequency,
+            frequency,
             ..self
         }
     }
@@ -416,30 +592,70 @@ where
 
 impl<G, P> Noise for Perlin3d<G, P>
 where
-    G: GradientProvider<Point3<u32>, Output = Vector3<f64>>,
+    G: GradientProvider<Point3<u32>, DimType = (u32, u32, u32), Output = Vector3<f64>>,
     P: InterpolationFunction,
 {
     type IndexType = Point3<f64>;
     type DimType = (f64, f64, f64);
 
     fn value_at(&self, pos: Point3<f64>) -> f64 {
-        let cell_pos = Vector3::new(
+        let cell_pos = [
             pos[0] * self.width(),
             pos[1] * self.height(),
             pos[2] * self.depth(),
-        );
+        ];
+        let cell = [
+            cell_pos[0].floor(),
+            cell_pos[1].floor(),
+            cell_pos[2].floor(),
+        ];
+        self.lattice_value(
+            [cell[0] as i64, cell[1] as i64, cell[2] as i64],
+            [
+                cell_pos[0] - cell[0],
+                cell_pos[1] - cell[1],
+                cell_pos[2] - cell[2],
+            ],
+        )
+    }
+
+    fn output_bounds(&self) -> (f64, f64) {
+        (-PERLIN_3D_BOUND, PERLIN_3D_BOUND)
+    }
+    fn frequency(&self) -> (f64, f64, f64) {
+        self.frequency
+    }
+}
+
+impl<G, P> SplitNoise for Perlin3d<G, P>
+where
+    G: GradientProvider<Point3<u32>, DimType = (u32, u32, u32), Output = Vector3<f64>>,
+    P: InterpolationFunction,
+{
+    type SplitIndex = Point3<SplitCoord>;
 
-        let rel_x = cell_pos.x - cell_pos.x.floor();
-        let rel_y = cell_pos.y - cell_pos.y.floor();
-        let rel_z = cell_pos.z - cell_pos.z.floor();
+    fn value_at_split(&self, pos: Point3<SplitCoord>) -> f64 {
+        let cell_pos = pos.scaled([self.width(), self.height(), self.depth()]);
+        self.lattice_value(
+            [cell_pos[0].cell(), cell_pos[1].cell(), cell_pos[2].cell()],
+            cell_pos.offsets(),
+        )
+    }
+}
+
+impl<G, P> Perlin3d<G, P>
+where
+    G: GradientProvider<Point3<u32>, DimType = (u32, u32, u32), Output = Vector3<f64>>,
+    P: InterpolationFunction,
+{
+    fn lattice_value(&self, cell: Point3<i64>, rel_pos: Point3<f64>) -> f64 {
+        let (rel_x, rel_y, rel_z) = (rel_pos[0], rel_pos[1], rel_pos[2]);
         let rel_pos = Vector3::new(rel_x, rel_y, rel_z);
 
-        let x_0 = cell_pos.x as u32;
-        let x_1 = x_0 + 1;
-        let y_0 = cell_pos.y as u32;
-        let y_1 = y_0 + 1;
-        let z_0 = cell_pos.z as u32;
-        let z_1 = z_0 + 1;
+        let dim = self.gradients.max_dimensions();
+        let (x_0, x_1) = wrap_cell(cell[0], dim.map(|d| d.0));
+        let (y_0, y_1) = wrap_cell(cell[1], dim.map(|d| d.1));
+        let (z_0, z_1) = wrap_cell(cell[2], dim.map(|d| d.2));
 
         let gradients = [
             *self.gradients.get_gradient([x_0, y_0, z_0]),
@@ -484,31 +700,38 @@ where
 
         Lerp::lerp(front_p, back_p, interp_z) * f64::consts::SQRT_2
     }
-
-    fn frequency(&self) -> (f64, f64, f64) {
-        self.frequency
-    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::f64;
-
     use rand;
     use test;
-    use super::Perlin2d;
+    use super::{Perlin1d, Perlin2d};
+    use gradient;
     use grid::{self, GradientGrid};
-    use interpolate;
-    use noise::{gradient, Noise};
+    use noise::{seeded_rng, Noise};
+
+    #[test]
+    fn grids_wrap_below_the_origin() {
+        let grid = grid::Grid2d::build_grid(
+            (4, 4),
+            &mut gradient::RandomGradientBuilder2d::new(seeded_rng(6)),
+        );
+        let perlin = Perlin2d::from_grid(grid);
+        //Bounded providers repeat every `max_dimensions` cells instead of indexing past their end.
+        for &pos in &[[-0.01, 0.3], [0.3, -0.01], [-5.3, -2.9], [0.99, 0.99]] {
+            let value = perlin.value_at(pos);
+            assert!(value.is_finite());
+            assert!((value - perlin.value_at([pos[0] + 0.75, pos[1] - 0.75])).abs() < 1e-9);
+        }
+        let perlin = Perlin1d::from_grid(vec![1.0, -1.0, 0.5, -0.5]);
+        assert!((perlin.value_at(-0.1) - perlin.value_at(0.65)).abs() < 1e-9);
+    }
 
     #[bench]
     fn bench_perlin_2d_grid(b: &mut test::Bencher) {
-        let (width, height) = (1000u32, 1000);
-        let dx = 1.0 / f64::from(width);
-        let dy = 1.0 / f64::from(height);
-
         let perlin = Perlin2d::new(
-            (10, 10),
+            (10.0, 10.0),
             grid::Grid2d::build_grid(
                 (11, 11),
                 &mut gradient::RandomGradientBuilder2d::new(rand::thread_rng()),
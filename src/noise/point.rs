@@ -11,6 +11,10 @@ pub trait PointUtil<T> {
     where
         F: Fn(T, T, T) -> T;
     fn saturate(val: T) -> Self;
+    /// Combines the components, in order, into a single value.
+    fn fold<A, F>(self, init: A, f: F) -> A
+    where
+        F: Fn(A, T) -> A;
 }
 
 impl<T: Copy> PointUtil<T> for Point1<T> {
@@ -29,6 +33,12 @@ impl<T: Copy> PointUtil<T> for Point1<T> {
     fn saturate(val: T) -> Self {
         val
     }
+    fn fold<A, F>(self, init: A, f: F) -> A
+    where
+        F: Fn(A, T) -> A,
+    {
+        f(init, self)
+    }
 }
 impl<T: Copy> PointUtil<T> for Point2<T> {
     fn apply<F>(self, rhs: Self, f: F) -> Self
@@ -49,6 +59,12 @@ impl<T: Copy> PointUtil<T> for Point2<T> {
     fn saturate(val: T) -> Self {
         [val, val]
     }
+    fn fold<A, F>(self, init: A, f: F) -> A
+    where
+        F: Fn(A, T) -> A,
+    {
+        self.iter().fold(init, |acc, &x| f(acc, x))
+    }
 }
 impl<T: Copy> PointUtil<T> for Point3<T> {
     fn apply<F>(self, rhs: Self, f: F) -> Self
@@ -70,6 +86,12 @@ impl<T: Copy> PointUtil<T> for Point3<T> {
     fn saturate(val: T) -> Self {
         [val, val, val]
     }
+    fn fold<A, F>(self, init: A, f: F) -> A
+    where
+        F: Fn(A, T) -> A,
+    {
+        self.iter().fold(init, |acc, &x| f(acc, x))
+    }
 }
 impl<T: Copy> PointUtil<T> for Point4<T> {
     fn apply<F>(self, rhs: Self, f: F) -> Self
@@ -97,4 +119,10 @@ impl<T: Copy> PointUtil<T> for Point4<T> {
     fn saturate(val: T) -> Self {
         [val, val, val, val]
     }
+    fn fold<A, F>(self, init: A, f: F) -> A
+    where
+        F: Fn(A, T) -> A,
+    {
+        self.iter().fold(init, |acc, &x| f(acc, x))
+    }
 }
@@ -0,0 +1,239 @@
+use noise::{Noise, Point2, Point3};
+
+/// A coordinate split into an integer cell and a fractional offset within it.
+///
+/// Far from the origin an `f64` position has few bits left for its fraction, and scaling it by
+/// a frequency loses the rest. Keeping the integer part separate lets noise compute the lattice
+/// cell and the position inside it exactly, so regions billions of units away are as smooth as
+/// those near the origin.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SplitCoord {
+    cell: i64,
+    offset: f64,
+}
+
+impl SplitCoord {
+    /// The coordinate `cell + offset`, normalized so that the offset lies within [0, 1).
+    pub fn new(cell: i64, offset: f64) -> SplitCoord {
+        let whole = offset.floor();
+        let mut coord = SplitCoord {
+            cell: cell.wrapping_add(whole as i64),
+            offset: offset - whole,
+        };
+        //Tiny negative offsets round up to a whole cell.
+        if coord.offset >= 1.0 {
+            coord.cell = coord.cell.wrapping_add(1);
+            coord.offset = 0.0;
+        }
+        coord
+    }
+
+    pub fn from_f64(value: f64) -> SplitCoord {
+        SplitCoord::new(0, value)
+    }
+
+    pub fn cell(&self) -> i64 {
+        self.cell
+    }
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    /// The nearest `f64` to the coordinate, losing precision far from the origin.
+    pub fn to_f64(&self) -> f64 {
+        self.cell as f64 + self.offset
+    }
+
+    pub fn shifted(self, shift: f64) -> SplitCoord {
+        let shift = SplitCoord::from_f64(shift);
+        SplitCoord::new(
+            self.cell.wrapping_add(shift.cell),
+            self.offset + shift.offset,
+        )
+    }
+
+    pub fn scaled(self, scale: f64) -> SplitCoord {
+        //The rounding error of `cell * scale` is recovered exactly with a fused multiply-add, so
+        //the fraction of the product survives however large the cell is.
+        let cell = self.cell as f64;
+        let product = cell * scale;
+        let error = cell.mul_add(scale, -product);
+        let whole = product.floor();
+        SplitCoord::new(
+            whole as i64,
+            (product - whole) + error + self.offset * scale,
+        )
+    }
+
+    pub fn added(self, other: SplitCoord) -> SplitCoord {
+        SplitCoord::new(
+            self.cell.wrapping_add(other.cell),
+            self.offset + other.offset,
+        )
+    }
+}
+
+/// Points made of `SplitCoord`s, with the operations input adapters apply to them.
+pub trait SplitPoint: Copy {
+    type Point: Copy;
+
+    fn from_point(point: Self::Point) -> Self;
+    fn to_point(&self) -> Self::Point;
+    /// The integer cells, as a point.
+    fn cells(&self) -> Self::Point;
+    /// The offsets within the cells, as a point.
+    fn offsets(&self) -> Self::Point;
+
+    fn shifted(self, shift: Self::Point) -> Self;
+    fn scaled(self, scale: Self::Point) -> Self;
+    /// Applies the linear map `map` to the cells, dropping the offsets. Each product of a cell
+    /// and a matrix entry is split exactly, like `scaled`.
+    fn map_cells<F>(&self, map: F) -> Self
+    where
+        F: Fn(Self::Point) -> Self::Point;
+}
+
+impl SplitPoint for SplitCoord {
+    type Point = f64;
+
+    fn from_point(point: f64) -> SplitCoord {
+        SplitCoord::from_f64(point)
+    }
+    fn to_point(&self) -> f64 {
+        self.to_f64()
+    }
+    fn cells(&self) -> f64 {
+        self.cell as f64
+    }
+    fn offsets(&self) -> f64 {
+        self.offset
+    }
+
+    fn shifted(self, shift: f64) -> SplitCoord {
+        SplitCoord::shifted(self, shift)
+    }
+    fn scaled(self, scale: f64) -> SplitCoord {
+        SplitCoord::scaled(self, scale)
+    }
+    fn map_cells<F>(&self, map: F) -> SplitCoord
+    where
+        F: Fn(f64) -> f64,
+    {
+        SplitCoord::new(self.cell, 0.0).scaled(map(1.0))
+    }
+}
+
+macro_rules! impl_split_point {
+    ($point:ident, $($idx:tt),+) => (
+        impl SplitPoint for $point<SplitCoord> {
+            type Point = $point<f64>;
+
+            fn from_point(point: Self::Point) -> Self {
+                [$(SplitCoord::from_f64(point[$idx])),+]
+            }
+            fn to_point(&self) -> Self::Point {
+                [$(self[$idx].to_f64()),+]
+            }
+            fn cells(&self) -> Self::Point {
+                [$(self[$idx].cell as f64),+]
+            }
+            fn offsets(&self) -> Self::Point {
+                [$(self[$idx].offset),+]
+            }
+
+            fn shifted(self, shift: Self::Point) -> Self {
+                [$(self[$idx].shifted(shift[$idx])),+]
+            }
+            fn scaled(self, scale: Self::Point) -> Self {
+                [$(self[$idx].scaled(scale[$idx])),+]
+            }
+            fn map_cells<F>(&self, map: F) -> Self
+            where
+                F: Fn(Self::Point) -> Self::Point,
+            {
+                //Column `axis` of the map's matrix holds the factors of that axis' cell.
+                let zero: Self::Point = Default::default();
+                let mut mapped = Self::from_point(zero);
+                for (axis, coord) in self.iter().enumerate() {
+                    let mut unit = zero;
+                    unit[axis] = 1.0;
+                    let column = map(unit);
+                    for (sum, &factor) in mapped.iter_mut().zip(column.iter()) {
+                        *sum = sum.added(SplitCoord::new(coord.cell, 0.0).scaled(factor));
+                    }
+                }
+                mapped
+            }
+        }
+    );
+}
+
+impl_split_point!(Point2, 0, 1);
+impl_split_point!(Point3, 0, 1, 2);
+
+/// Noise that can be sampled at split coordinates without losing precision far from the origin.
+pub trait SplitNoise: Noise {
+    type SplitIndex: SplitPoint<Point = Self::IndexType>;
+
+    fn value_at_split(&self, pos: Self::SplitIndex) -> f64;
+
+    /// The split counterpart of `Noise::value_at_lod`.
+    fn value_at_split_lod(&self, pos: Self::SplitIndex, _footprint: f64) -> f64 {
+        self.value_at_split(pos)
+    }
+}
+
+impl<'a, N> SplitNoise for &'a N
+where
+    N: SplitNoise + 'a,
+{
+    type SplitIndex = N::SplitIndex;
+
+    fn value_at_split(&self, pos: Self::SplitIndex) -> f64 {
+        (*self).value_at_split(pos)
+    }
+    fn value_at_split_lod(&self, pos: Self::SplitIndex, footprint: f64) -> f64 {
+        (*self).value_at_split_lod(pos, footprint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapter::{ScaleInput, ShiftInput};
+    use noise::fbm::Fbm2d;
+    use noise::{seeded_rng, OctaveRotation};
+
+    #[test]
+    fn far_regions_match_the_origin() {
+        let coord = SplitCoord::new(10_000_000_000, 0.25).scaled(0.1);
+        assert_eq!(coord.cell(), 1_000_000_000);
+        assert!((coord.offset() - 0.025).abs() < 1e-6);
+        assert_eq!(SplitCoord::new(3, -0.25), SplitCoord::new(2, 0.75));
+
+        //Every octave repeats after 256 lattice cells, so shifting by a multiple of 64 units of
+        //noise space at a base frequency of 4 must leave the value unchanged.
+        let fbm = Fbm2d::new(seeded_rng(21)).with_frequency((4.0, 4.0));
+        let (near, period) = ([0.3, 0.7], 64);
+        let expected = fbm.value_at(near);
+        let far = [
+            SplitCoord::new(period * 100_000_000, near[0]),
+            SplitCoord::new(-period * 50_000_000, near[1]),
+        ];
+        assert!((fbm.value_at_split(far) - expected).abs() < 1e-9);
+        assert!((fbm.value_at_split(SplitPoint::from_point(near)) - expected).abs() < 1e-12);
+        assert!((fbm.value_at([near[0] - 64.0, near[1]]) - expected).abs() < 1e-9);
+
+        let shifted = ShiftInput::new(&fbm, [6.4e9, -3.2e9]);
+        assert!((shifted.value_at_split(SplitPoint::from_point(near)) - expected).abs() < 1e-9);
+        let scaled = ScaleInput::new(&fbm, [1e8, 1.0]);
+        let pos = [SplitCoord::new(64, 0.3e-8), SplitCoord::from_f64(near[1])];
+        assert!((scaled.value_at_split(pos) - expected).abs() < 1e-6);
+
+        let rotated = Fbm2d::new(seeded_rng(21))
+            .with_frequency((4.0, 4.0))
+            .with_octave_rotation(OctaveRotation::Seeded(5));
+        let split = rotated.value_at_split(SplitPoint::from_point(near));
+        assert!((split - rotated.value_at(near)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,226 @@
+use std::f64;
+
+use noise::{Noise, Point2, WithFrequency};
+
+//With at most one feature point per cell the nearest point is never further than the cell
+//diagonal, and the second nearest never further than the far corner of the adjacent cell on the
+//nearer side, sqrt(1.5^2 + 1^2). Both lie within two cells, so a 5x5 block of cells is searched.
+const F1_BOUND: f64 = f64::consts::SQRT_2;
+const F2_BOUND: f64 = 1.802_775_637_731_995;
+const SEARCH_RADIUS: i64 = 2;
+
+/// Which property of the nearest feature points a `Worley2d` returns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WorleyOutput {
+    /// Distance to the nearest feature point.
+    F1,
+    /// Distance to the second nearest feature point.
+    F2,
+    /// Difference between the two, zero on the borders between cells.
+    F2MinusF1,
+    /// A random value in [-1, 1] shared by every position of a cell.
+    CellValue,
+}
+
+/// A feature point of a `Worley2d` and its distance from a position, in cell units.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WorleyCell {
+    pub cell: (i64, i64),
+    pub point: Point2<f64>,
+    pub distance: f64,
+}
+
+/// Cellular noise, from the distances to feature points scattered one per lattice cell.
+///
+/// Distances are measured in cells, so the output doesn't depend on the frequency.
+#[derive(Clone, Debug)]
+pub struct Worley2d {
+    frequency: (f64, f64),
+    seed: u32,
+    jitter: f64,
+    output: WorleyOutput,
+}
+
+fn hash(x: i64, y: i64, seed: u32, salt: u64) -> u64 {
+    let mut h = (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ ((u64::from(seed) << 8) | salt).wrapping_mul(0x1656_67B1_9E37_79F9);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+    h ^ (h >> 33)
+}
+
+/// A uniform value in [0, 1) from the upper bits of a hash.
+fn unit(h: u64) -> f64 {
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl Worley2d {
+    pub fn new(seed: u32) -> Worley2d {
+        Worley2d {
+            frequency: (1.0, 1.0),
+            seed,
+            jitter: 1.0,
+            output: WorleyOutput::F1,
+        }
+    }
+
+    /// How far feature points stray from the centers of their cells, within [0, 1].
+    pub fn with_jitter(self, jitter: f64) -> Worley2d {
+        assert!(
+            (0.0..=1.0).contains(&jitter),
+            "Jitter must be within [0, 1]"
+        );
+        Worley2d { jitter, ..self }
+    }
+    pub fn with_output(self, output: WorleyOutput) -> Worley2d {
+        Worley2d { output, ..self }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+    pub fn jitter(&self) -> f64 {
+        self.jitter
+    }
+    pub fn output(&self) -> WorleyOutput {
+        self.output
+    }
+
+    /// The random value in [-1, 1] of a cell.
+    pub fn cell_value(&self, cell: (i64, i64)) -> f64 {
+        unit(hash(cell.0, cell.1, self.seed, 2)) * 2.0 - 1.0
+    }
+
+    /// The feature point of a cell, in noise space.
+    pub fn feature_point(&self, cell: (i64, i64)) -> Point2<f64> {
+        let offset = |salt| 0.5 + self.jitter * (unit(hash(cell.0, cell.1, self.seed, salt)) - 0.5);
+        [
+            (cell.0 as f64 + offset(0)) / self.frequency.0,
+            (cell.1 as f64 + offset(1)) / self.frequency.1,
+        ]
+    }
+
+    /// Calls `f` with every feature point within `radius` cells of `pos`, in no particular order.
+    pub fn for_each_cell_within<F>(&self, pos: Point2<f64>, radius: f64, mut f: F)
+    where
+        F: FnMut(WorleyCell),
+    {
+        let (x, y) = (pos[0] * self.frequency.0, pos[1] * self.frequency.1);
+        //A feature point lies within its own cell, so only cells overlapping the search square
+        //can hold one within range.
+        for j in (y - radius).floor() as i64..=(y + radius).floor() as i64 {
+            for i in (x - radius).floor() as i64..=(x + radius).floor() as i64 {
+                let point = self.feature_point((i, j));
+                let (dx, dy) = (
+                    point[0] * self.frequency.0 - x,
+                    point[1] * self.frequency.1 - y,
+                );
+                let distance = (dx * dx + dy * dy).sqrt();
+                if distance <= radius {
+                    f(WorleyCell {
+                        cell: (i, j),
+                        point,
+                        distance,
+                    });
+                }
+            }
+        }
+    }
+
+    /// The nearest and second nearest feature points to `pos`.
+    pub fn nearest_cells(&self, pos: Point2<f64>) -> (WorleyCell, WorleyCell) {
+        let (x, y) = (pos[0] * self.frequency.0, pos[1] * self.frequency.1);
+        let (cx, cy) = (x.floor() as i64, y.floor() as i64);
+        let far = WorleyCell {
+            cell: (0, 0),
+            point: pos,
+            distance: f64::INFINITY,
+        };
+        let (mut first, mut second) = (far, far);
+        for j in cy - SEARCH_RADIUS..=cy + SEARCH_RADIUS {
+            for i in cx - SEARCH_RADIUS..=cx + SEARCH_RADIUS {
+                let point = self.feature_point((i, j));
+                let (dx, dy) = (
+                    point[0] * self.frequency.0 - x,
+                    point[1] * self.frequency.1 - y,
+                );
+                let candidate = WorleyCell {
+                    cell: (i, j),
+                    point,
+                    distance: (dx * dx + dy * dy).sqrt(),
+                };
+                if candidate.distance < first.distance {
+                    second = first;
+                    first = candidate;
+                } else if candidate.distance < second.distance {
+                    second = candidate;
+                }
+            }
+        }
+        (first, second)
+    }
+}
+
+impl WithFrequency for Worley2d {
+    fn with_frequency(self, frequency: Self::DimType) -> Self {
+        Worley2d { frequency, ..self }
+    }
+}
+
+impl Noise for Worley2d {
+    type IndexType = Point2<f64>;
+    type DimType = (f64, f64);
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        let (first, second) = self.nearest_cells(pos);
+        match self.output {
+            WorleyOutput::F1 => first.distance,
+            WorleyOutput::F2 => second.distance,
+            WorleyOutput::F2MinusF1 => second.distance - first.distance,
+            WorleyOutput::CellValue => self.cell_value(first.cell),
+        }
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        match self.output {
+            WorleyOutput::F1 => (0.0, F1_BOUND),
+            WorleyOutput::F2 | WorleyOutput::F2MinusF1 => (0.0, F2_BOUND),
+            WorleyOutput::CellValue => (-1.0, 1.0),
+        }
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.frequency
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_match_brute_force_and_bounds() {
+        let worley = Worley2d::new(5).with_frequency((3.0, 5.0));
+        for i in 0..400 {
+            let pos = [(i % 20) as f64 * 0.173 - 1.3, (i / 20) as f64 * 0.091 - 0.7];
+            let (first, second) = worley.nearest_cells(pos);
+
+            let mut distances = Vec::new();
+            for cy in -12..12 {
+                for cx in -12..12 {
+                    let p = worley.feature_point((cx, cy));
+                    let (dx, dy) = ((p[0] - pos[0]) * 3.0, (p[1] - pos[1]) * 5.0);
+                    distances.push((dx * dx + dy * dy).sqrt());
+                }
+            }
+            distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert!((first.distance - distances[0]).abs() < 1e-12);
+            assert!((second.distance - distances[1]).abs() < 1e-12);
+            assert!(second.distance <= F2_BOUND);
+
+            let cells = worley.clone().with_output(WorleyOutput::CellValue);
+            assert_eq!(cells.value_at(pos), cells.cell_value(first.cell));
+        }
+    }
+}
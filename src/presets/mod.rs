@@ -0,0 +1,457 @@
+//! Ready made noise graphs for classic procedural materials, after Ken Perlin's textures.
+//!
+//! Every preset outputs values in [0, 1] and comes with a color gradient to render them with.
+
+use std::f64::consts::PI;
+
+use adapter::{Abs, NoiseExt};
+use noise::fbm::{Fbm2d, Fbm3d};
+use noise::{seeded_rng, DynNoise2d, DynNoise3d, Noise, Octave, OctaveNoise, Point2, Point3};
+use render::ColorGradient;
+
+/// Sums the absolute values of the octaves of an fbm, halving the amplitude of each octave and
+/// scaling the total amplitude to one.
+fn turbulence<N>(octaves: &[N]) -> OctaveNoise<Abs<N>>
+where
+    N: Noise + Clone,
+{
+    let total: f64 = (0..octaves.len()).map(|i| 0.5f64.powi(i as i32)).sum();
+    let octaves = octaves
+        .iter()
+        .enumerate()
+        .map(|(i, o)| Octave::new(Abs::new(o.clone()), 0.5f64.powi(i as i32) / total))
+        .collect();
+    OctaveNoise::from_octaves(octaves)
+}
+
+/// A material that can be built as 2d or 3d noise.
+pub trait Preset {
+    fn build_2d(&self) -> DynNoise2d;
+    /// The material through a volume, for solid texturing.
+    fn build_3d(&self) -> DynNoise3d;
+    fn gradient(&self) -> ColorGradient;
+}
+
+/// Veins running across the x axis, displaced by turbulence: `sin(PI * (veins * x + turbulence))`.
+#[derive(Clone, Debug)]
+pub struct Marble {
+    pub seed: u32,
+    pub frequency: f64,
+    pub octaves: usize,
+    /// Light and dark veins per unit along the x axis, each half a period of the sine.
+    pub veins: f64,
+    /// How far, in veins, the turbulence displaces them.
+    pub turbulence: f64,
+}
+
+impl Marble {
+    pub fn new(seed: u32) -> Marble {
+        Marble {
+            seed,
+            frequency: 2.0,
+            octaves: 6,
+            veins: 2.0,
+            turbulence: 4.0,
+        }
+    }
+
+    pub fn with_frequency(self, frequency: f64) -> Marble {
+        Marble { frequency, ..self }
+    }
+    pub fn with_octaves(self, octaves: usize) -> Marble {
+        Marble { octaves, ..self }
+    }
+    pub fn with_veins(self, veins: f64) -> Marble {
+        Marble { veins, ..self }
+    }
+    pub fn with_turbulence(self, turbulence: f64) -> Marble {
+        Marble { turbulence, ..self }
+    }
+}
+
+fn marble(x: f64, turbulence: f64, veins: f64, strength: f64) -> f64 {
+    0.5 + 0.5 * (PI * (veins * x + strength * turbulence)).sin()
+}
+
+impl Preset for Marble {
+    fn build_2d(&self) -> DynNoise2d {
+        let (veins, strength) = (self.veins, self.turbulence);
+        let fbm = Fbm2d::new(seeded_rng(self.seed))
+            .with_frequency((self.frequency, self.frequency))
+            .with_num_octaves(self.octaves);
+        turbulence(fbm.octaves())
+            .transform(move |p: &Point2<f64>, t| marble(p[0], t, veins, strength))
+            .with_output_bounds((0.0, 1.0))
+            .into_dyn()
+    }
+    fn build_3d(&self) -> DynNoise3d {
+        let (veins, strength) = (self.veins, self.turbulence);
+        let fbm = Fbm3d::new(seeded_rng(self.seed))
+            .with_frequency((self.frequency, self.frequency, self.frequency))
+            .with_num_octaves(self.octaves);
+        turbulence(fbm.octaves())
+            .transform(move |p: &Point3<f64>, t| marble(p[0], t, veins, strength))
+            .with_output_bounds((0.0, 1.0))
+            .into_dyn()
+    }
+    fn gradient(&self) -> ColorGradient {
+        ColorGradient::new(vec![
+            (0.0, [60, 60, 70]),
+            (0.2, [150, 150, 160]),
+            (0.45, [235, 235, 240]),
+            (1.0, [250, 250, 250]),
+        ])
+    }
+}
+
+/// Growth rings around the origin, or around the z axis in 3d, distorted by fbm.
+#[derive(Clone, Debug)]
+pub struct Wood {
+    pub seed: u32,
+    pub frequency: f64,
+    pub octaves: usize,
+    /// Rings per unit of distance from the center.
+    pub rings: f64,
+    /// How far, in rings, the grain noise displaces them.
+    pub grain: f64,
+}
+
+impl Wood {
+    pub fn new(seed: u32) -> Wood {
+        Wood {
+            seed,
+            frequency: 2.0,
+            octaves: 4,
+            rings: 8.0,
+            grain: 1.5,
+        }
+    }
+
+    pub fn with_frequency(self, frequency: f64) -> Wood {
+        Wood { frequency, ..self }
+    }
+    pub fn with_octaves(self, octaves: usize) -> Wood {
+        Wood { octaves, ..self }
+    }
+    pub fn with_rings(self, rings: f64) -> Wood {
+        Wood { rings, ..self }
+    }
+    pub fn with_grain(self, grain: f64) -> Wood {
+        Wood { grain, ..self }
+    }
+}
+
+//The position within a ring, light early wood rising to dark late wood.
+fn wood(x: f64, y: f64, noise: f64, rings: f64, grain: f64) -> f64 {
+    let g = rings * (x * x + y * y).sqrt() + grain * noise;
+    g - g.floor()
+}
+
+impl Preset for Wood {
+    fn build_2d(&self) -> DynNoise2d {
+        let (rings, grain) = (self.rings, self.grain);
+        Fbm2d::new(seeded_rng(self.seed))
+            .with_frequency((self.frequency, self.frequency))
+            .with_num_octaves(self.octaves)
+            .transform(move |p: &Point2<f64>, n| wood(p[0], p[1], n, rings, grain))
+            .with_output_bounds((0.0, 1.0))
+            .into_dyn()
+    }
+    fn build_3d(&self) -> DynNoise3d {
+        let (rings, grain) = (self.rings, self.grain);
+        Fbm3d::new(seeded_rng(self.seed))
+            .with_frequency((self.frequency, self.frequency, self.frequency))
+            .with_num_octaves(self.octaves)
+            .transform(move |p: &Point3<f64>, n| wood(p[0], p[1], n, rings, grain))
+            .with_output_bounds((0.0, 1.0))
+            .into_dyn()
+    }
+    fn gradient(&self) -> ColorGradient {
+        ColorGradient::new(vec![
+            (0.0, [222, 184, 135]),
+            (0.7, [190, 140, 90]),
+            (1.0, [110, 70, 40]),
+        ])
+    }
+}
+
+/// Fbm cut off below a density threshold, leaving clear sky between the clouds.
+#[derive(Clone, Debug)]
+pub struct Clouds {
+    pub seed: u32,
+    pub frequency: f64,
+    pub octaves: usize,
+    /// Roughly the fraction of the sky covered, within [0, 1].
+    pub coverage: f64,
+    /// How quickly the clouds turn opaque past their edges.
+    pub sharpness: f64,
+}
+
+impl Clouds {
+    pub fn new(seed: u32) -> Clouds {
+        Clouds {
+            seed,
+            frequency: 3.0,
+            octaves: 6,
+            coverage: 0.5,
+            sharpness: 3.0,
+        }
+    }
+
+    pub fn with_frequency(self, frequency: f64) -> Clouds {
+        Clouds { frequency, ..self }
+    }
+    pub fn with_octaves(self, octaves: usize) -> Clouds {
+        Clouds { octaves, ..self }
+    }
+    pub fn with_coverage(self, coverage: f64) -> Clouds {
+        Clouds { coverage, ..self }
+    }
+    pub fn with_sharpness(self, sharpness: f64) -> Clouds {
+        Clouds { sharpness, ..self }
+    }
+}
+
+fn clouds(noise: f64, coverage: f64, sharpness: f64) -> f64 {
+    ((0.5 + 0.5 * noise - (1.0 - coverage)) * sharpness).clamp(0.0, 1.0)
+}
+
+impl Preset for Clouds {
+    fn build_2d(&self) -> DynNoise2d {
+        let (coverage, sharpness) = (self.coverage, self.sharpness);
+        Fbm2d::new(seeded_rng(self.seed))
+            .with_frequency((self.frequency, self.frequency))
+            .with_num_octaves(self.octaves)
+            .transform(move |_: &Point2<f64>, n| clouds(n, coverage, sharpness))
+            .with_output_bounds((0.0, 1.0))
+            .into_dyn()
+    }
+    fn build_3d(&self) -> DynNoise3d {
+        let (coverage, sharpness) = (self.coverage, self.sharpness);
+        Fbm3d::new(seeded_rng(self.seed))
+            .with_frequency((self.frequency, self.frequency, self.frequency))
+            .with_num_octaves(self.octaves)
+            .transform(move |_: &Point3<f64>, n| clouds(n, coverage, sharpness))
+            .with_output_bounds((0.0, 1.0))
+            .into_dyn()
+    }
+    fn gradient(&self) -> ColorGradient {
+        ColorGradient::new(vec![
+            (0.0, [70, 130, 200]),
+            (0.6, [210, 225, 240]),
+            (1.0, [255, 255, 255]),
+        ])
+    }
+}
+
+/// Fine, high contrast turbulence, speckled like a polished stone.
+#[derive(Clone, Debug)]
+pub struct Granite {
+    pub seed: u32,
+    pub frequency: f64,
+    pub octaves: usize,
+    /// The turbulence level mapped to the middle of the output.
+    pub threshold: f64,
+    pub contrast: f64,
+}
+
+impl Granite {
+    pub fn new(seed: u32) -> Granite {
+        Granite {
+            seed,
+            frequency: 16.0,
+            octaves: 3,
+            threshold: 0.3,
+            contrast: 4.0,
+        }
+    }
+
+    pub fn with_frequency(self, frequency: f64) -> Granite {
+        Granite { frequency, ..self }
+    }
+    pub fn with_octaves(self, octaves: usize) -> Granite {
+        Granite { octaves, ..self }
+    }
+    pub fn with_threshold(self, threshold: f64) -> Granite {
+        Granite { threshold, ..self }
+    }
+    pub fn with_contrast(self, contrast: f64) -> Granite {
+        Granite { contrast, ..self }
+    }
+}
+
+fn granite(turbulence: f64, threshold: f64, contrast: f64) -> f64 {
+    ((turbulence - threshold) * contrast + 0.5).clamp(0.0, 1.0)
+}
+
+impl Preset for Granite {
+    fn build_2d(&self) -> DynNoise2d {
+        let (threshold, contrast) = (self.threshold, self.contrast);
+        let fbm = Fbm2d::new(seeded_rng(self.seed))
+            .with_frequency((self.frequency, self.frequency))
+            .with_num_octaves(self.octaves);
+        turbulence(fbm.octaves())
+            .transform(move |_: &Point2<f64>, t| granite(t, threshold, contrast))
+            .with_output_bounds((0.0, 1.0))
+            .into_dyn()
+    }
+    fn build_3d(&self) -> DynNoise3d {
+        let (threshold, contrast) = (self.threshold, self.contrast);
+        let fbm = Fbm3d::new(seeded_rng(self.seed))
+            .with_frequency((self.frequency, self.frequency, self.frequency))
+            .with_num_octaves(self.octaves);
+        turbulence(fbm.octaves())
+            .transform(move |_: &Point3<f64>, t| granite(t, threshold, contrast))
+            .with_output_bounds((0.0, 1.0))
+            .into_dyn()
+    }
+    fn gradient(&self) -> ColorGradient {
+        ColorGradient::new(vec![
+            (0.0, [35, 30, 30]),
+            (0.4, [120, 105, 100]),
+            (0.7, [190, 170, 165]),
+            (1.0, [235, 225, 220]),
+        ])
+    }
+}
+
+/// Glowing cracks between plates of cooled crust, along the zero crossings of fbm.
+#[derive(Clone, Debug)]
+pub struct Lava {
+    pub seed: u32,
+    pub frequency: f64,
+    pub octaves: usize,
+    /// How narrow the cracks are.
+    pub sharpness: f64,
+}
+
+impl Lava {
+    pub fn new(seed: u32) -> Lava {
+        Lava {
+            seed,
+            frequency: 3.0,
+            octaves: 5,
+            sharpness: 6.0,
+        }
+    }
+
+    pub fn with_frequency(self, frequency: f64) -> Lava {
+        Lava { frequency, ..self }
+    }
+    pub fn with_octaves(self, octaves: usize) -> Lava {
+        Lava { octaves, ..self }
+    }
+    pub fn with_sharpness(self, sharpness: f64) -> Lava {
+        Lava { sharpness, ..self }
+    }
+}
+
+fn lava(noise: f64, sharpness: f64) -> f64 {
+    (1.0 - noise.min(1.0)).powf(sharpness)
+}
+
+impl Preset for Lava {
+    fn build_2d(&self) -> DynNoise2d {
+        let sharpness = self.sharpness;
+        Fbm2d::new(seeded_rng(self.seed))
+            .with_frequency((self.frequency, self.frequency))
+            .with_num_octaves(self.octaves)
+            .abs()
+            .transform(move |_: &Point2<f64>, n| lava(n, sharpness))
+            .with_output_bounds((0.0, 1.0))
+            .into_dyn()
+    }
+    fn build_3d(&self) -> DynNoise3d {
+        let sharpness = self.sharpness;
+        Fbm3d::new(seeded_rng(self.seed))
+            .with_frequency((self.frequency, self.frequency, self.frequency))
+            .with_num_octaves(self.octaves)
+            .abs()
+            .transform(move |_: &Point3<f64>, n| lava(n, sharpness))
+            .with_output_bounds((0.0, 1.0))
+            .into_dyn()
+    }
+    fn gradient(&self) -> ColorGradient {
+        ColorGradient::new(vec![
+            (0.0, [20, 10, 10]),
+            (0.3, [120, 20, 10]),
+            (0.7, [240, 110, 20]),
+            (1.0, [255, 240, 150]),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::File;
+
+    use image;
+    use image::png::PNGEncoder;
+    use image::ColorType;
+
+    use super::*;
+    use grid::Grid2d;
+    use render::{sample_noise, to_rgb8, Region};
+
+    const SIZE: u32 = 64;
+
+    /// Compares a rendering against `golden/<name>.png`, allowing for rounding differences
+    /// between platforms. Set `UPDATE_GOLDEN` to write the rendering as the new golden image.
+    fn check_golden(name: &str, pixels: &[u8]) {
+        let path = format!(
+            "{}/src/presets/golden/{}.png",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        if env::var_os("UPDATE_GOLDEN").is_some() {
+            PNGEncoder::new(File::create(&path).unwrap())
+                .encode(pixels, SIZE, SIZE, ColorType::RGB(8))
+                .unwrap();
+        }
+        let golden = image::open(&path).unwrap().to_rgb().into_raw();
+        assert_eq!(golden.len(), pixels.len());
+        let mismatches = golden
+            .iter()
+            .zip(pixels)
+            .filter(|&(&a, &b)| (i32::from(a) - i32::from(b)).abs() > 2)
+            .count();
+        assert_eq!(mismatches, 0, "{} differs from its golden image", name);
+    }
+
+    #[test]
+    fn presets_match_golden_images() {
+        let presets: Vec<(&str, Box<dyn Preset>)> = vec![
+            ("marble", Box::new(Marble::new(1))),
+            ("wood", Box::new(Wood::new(2))),
+            ("clouds", Box::new(Clouds::new(3))),
+            ("granite", Box::new(Granite::new(4))),
+            ("lava", Box::new(Lava::new(5))),
+        ];
+        let region = Region::new([0.0, 0.0], [1.0, 1.0]);
+        for (name, preset) in presets {
+            assert_eq!(preset.build_2d().output_bounds(), (0.0, 1.0));
+            assert_eq!(preset.build_3d().output_bounds(), (0.0, 1.0));
+            let flat = sample_noise(&preset.build_2d(), region, SIZE, SIZE);
+            assert!(flat.data().iter().all(|&v| (0.0..=1.0).contains(&v)));
+            check_golden(
+                &format!("{}_2d", name),
+                &to_rgb8(&flat, (0.0, 1.0), &preset.gradient()),
+            );
+
+            let solid = preset.build_3d();
+            let mut slice = Grid2d::new(SIZE, SIZE);
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    let p = region.pixel_center(x, y, SIZE, SIZE);
+                    slice[(x as usize, y as usize)] = solid.value_at([p[0], p[1], 0.37]);
+                }
+            }
+            assert!(slice.data().iter().all(|&v| (0.0..=1.0).contains(&v)));
+            check_golden(
+                &format!("{}_3d", name),
+                &to_rgb8(&slice, (0.0, 1.0), &preset.gradient()),
+            );
+        }
+    }
+}
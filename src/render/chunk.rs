@@ -0,0 +1,304 @@
+use grid::{Grid2d, Grid3d};
+use mesh::Volume;
+use noise::{Noise2d, Noise3d, Point2, Point3, SplitCoord, SplitNoise};
+use render::Region;
+
+/// Splits an unbounded world into square or cubic chunks of samples addressed by integer chunk
+/// coordinates.
+///
+/// Samples lie on a single world lattice with `spacing` noise space units between neighbours,
+/// and chunk `c` starts at lattice index `c * size`. Every sample position is computed from its
+/// lattice index alone, so chunks that overlap, through padding or a shared border, hold
+/// bit-identical values where they do, however far from the origin they are.
+#[derive(Clone, Debug)]
+pub struct ChunkSampler {
+    size: u32,
+    spacing: f64,
+    origin: Point3<f64>,
+    padding: u32,
+    shared_border: bool,
+}
+
+impl ChunkSampler {
+    /// Chunks of `size` samples per axis, `spacing` apart.
+    pub fn new(size: u32, spacing: f64) -> ChunkSampler {
+        assert!(size > 0, "A chunk needs at least one sample per axis");
+        ChunkSampler {
+            size,
+            spacing,
+            origin: [0.0; 3],
+            padding: 0,
+            shared_border: false,
+        }
+    }
+
+    /// Noise space position of lattice index zero. 2d chunks use the first two coordinates.
+    pub fn with_origin(self, origin: Point3<f64>) -> ChunkSampler {
+        ChunkSampler { origin, ..self }
+    }
+    /// Extra samples on every side of a chunk, overlapping its neighbours, for operations that
+    /// look at neighbouring samples such as normals or erosion.
+    pub fn with_padding(self, padding: u32) -> ChunkSampler {
+        ChunkSampler { padding, ..self }
+    }
+    /// Whether chunks include the first samples of the next chunk along each axis, so that
+    /// neighbouring chunks meet on a common row of samples, as meshing needs.
+    pub fn with_shared_border(self, shared_border: bool) -> ChunkSampler {
+        ChunkSampler {
+            shared_border,
+            ..self
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+    pub fn spacing(&self) -> f64 {
+        self.spacing
+    }
+    pub fn padding(&self) -> u32 {
+        self.padding
+    }
+
+    /// Samples per axis of a filled chunk, including padding and the shared border.
+    pub fn samples_per_axis(&self) -> u32 {
+        self.size + 2 * self.padding + if self.shared_border { 1 } else { 0 }
+    }
+
+    /// Lattice index of the first sample of a chunk, including padding.
+    pub fn first_index(&self, chunk: i64) -> i64 {
+        chunk * i64::from(self.size) - i64::from(self.padding)
+    }
+
+    /// The chunk holding lattice index `index`, not counting padding or shared borders.
+    pub fn chunk_of(&self, index: i64) -> i64 {
+        index.div_euclid(i64::from(self.size))
+    }
+
+    fn position(&self, axis: usize, index: i64) -> f64 {
+        self.origin[axis] + index as f64 * self.spacing
+    }
+
+    /// The noise space area a 2d chunk is responsible for, without padding.
+    pub fn region(&self, chunk: (i64, i64)) -> Region {
+        let (x, y) = (self.first_index(chunk.0), self.first_index(chunk.1));
+        let (padding, size) = (i64::from(self.padding), i64::from(self.size));
+        Region::new(
+            [self.position(0, x + padding), self.position(1, y + padding)],
+            [
+                self.position(0, x + padding + size),
+                self.position(1, y + padding + size),
+            ],
+        )
+    }
+
+    /// The noise space volume a 3d chunk is responsible for, without padding.
+    pub fn volume(&self, chunk: (i64, i64, i64)) -> Volume {
+        let (padding, size) = (i64::from(self.padding), i64::from(self.size));
+        let mut volume = Volume::new([0.0; 3], [0.0; 3]);
+        for (axis, &c) in [chunk.0, chunk.1, chunk.2].iter().enumerate() {
+            let start = self.first_index(c) + padding;
+            volume.min[axis] = self.position(axis, start);
+            volume.max[axis] = self.position(axis, start + size);
+        }
+        volume
+    }
+
+    /// The position of lattice index `index` along `axis`, exact however far the index is from
+    /// the origin.
+    pub fn split_position(&self, axis: usize, index: i64) -> SplitCoord {
+        SplitCoord::new(index, 0.0)
+            .scaled(self.spacing)
+            .shifted(self.origin[axis])
+    }
+
+    pub fn sample_2d<N>(&self, noise: &N, chunk: (i64, i64)) -> Grid2d<f64>
+    where
+        N: Noise2d,
+    {
+        self.fill_2d(chunk, |x, y| {
+            let pos = [self.position(0, x), self.position(1, y)];
+            noise.value_at_lod(pos, self.spacing)
+        })
+    }
+
+    /// Like `sample_2d`, but samples at split coordinates, which keeps chunks billions of
+    /// samples from the origin as smooth as those near it.
+    pub fn sample_split_2d<N>(&self, noise: &N, chunk: (i64, i64)) -> Grid2d<f64>
+    where
+        N: SplitNoise<SplitIndex = Point2<SplitCoord>>,
+    {
+        self.fill_2d(chunk, |x, y| {
+            let pos = [self.split_position(0, x), self.split_position(1, y)];
+            noise.value_at_split_lod(pos, self.spacing)
+        })
+    }
+
+    pub fn sample_3d<N>(&self, noise: &N, chunk: (i64, i64, i64)) -> Grid3d<f64>
+    where
+        N: Noise3d,
+    {
+        self.fill_3d(chunk, |x, y, z| {
+            let pos = [
+                self.position(0, x),
+                self.position(1, y),
+                self.position(2, z),
+            ];
+            noise.value_at_lod(pos, self.spacing)
+        })
+    }
+
+    /// Whether each sample of `sample_3d` exceeds `threshold`, such as the solid voxels of a
+    /// density field.
+    pub fn sample_solid_3d<N>(&self, noise: &N, chunk: (i64, i64, i64), threshold: f64) -> Grid3d<bool>
+    where
+        N: Noise3d,
+    {
+        let n = self.samples_per_axis();
+        let values = self.sample_3d(noise, chunk);
+        Grid3d::with_data(n, n, n, values.data().iter().map(|&v| v > threshold).collect())
+    }
+
+    /// Like `sample_3d`, but samples at split coordinates.
+    pub fn sample_split_3d<N>(&self, noise: &N, chunk: (i64, i64, i64)) -> Grid3d<f64>
+    where
+        N: SplitNoise<SplitIndex = Point3<SplitCoord>>,
+    {
+        self.fill_3d(chunk, |x, y, z| {
+            let pos = [
+                self.split_position(0, x),
+                self.split_position(1, y),
+                self.split_position(2, z),
+            ];
+            noise.value_at_split_lod(pos, self.spacing)
+        })
+    }
+
+    fn fill_2d<F>(&self, chunk: (i64, i64), sample: F) -> Grid2d<f64>
+    where
+        F: Fn(i64, i64) -> f64,
+    {
+        let n = self.samples_per_axis();
+        let (x0, y0) = (self.first_index(chunk.0), self.first_index(chunk.1));
+        let mut grid = Grid2d::new(n, n);
+        for y in 0..n {
+            for x in 0..n {
+                grid[(x as usize, y as usize)] = sample(x0 + i64::from(x), y0 + i64::from(y));
+            }
+        }
+        grid
+    }
+
+    fn fill_3d<F>(&self, chunk: (i64, i64, i64), sample: F) -> Grid3d<f64>
+    where
+        F: Fn(i64, i64, i64) -> f64,
+    {
+        let n = self.samples_per_axis();
+        let first = (
+            self.first_index(chunk.0),
+            self.first_index(chunk.1),
+            self.first_index(chunk.2),
+        );
+        let mut grid = Grid3d::new(n, n, n);
+        for z in 0..n {
+            for y in 0..n {
+                for x in 0..n {
+                    grid[(x as usize, y as usize, z as usize)] = sample(
+                        first.0 + i64::from(x),
+                        first.1 + i64::from(y),
+                        first.2 + i64::from(z),
+                    );
+                }
+            }
+        }
+        grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noise::fbm::{Fbm2d, Fbm3d};
+    use noise::{seeded_rng, Noise};
+
+    #[test]
+    fn neighbouring_chunks_agree_bit_for_bit() {
+        let far = 1_000_000;
+        let fbm = Fbm2d::new(seeded_rng(8)).with_frequency((4.0, 4.0));
+        let sampler = ChunkSampler::new(32, 1.0 / 32.0)
+            .with_origin([0.5, 0.5, 0.5])
+            .with_padding(2)
+            .with_shared_border(true);
+        assert_eq!(sampler.samples_per_axis(), 37);
+        assert_eq!(sampler.chunk_of(sampler.first_index(far) + 2), far);
+        assert_eq!(sampler.chunk_of(-1), -1);
+
+        let a = sampler.sample_2d(&fbm, (far, 7));
+        let b = sampler.sample_2d(&fbm, (far + 1, 7));
+        let c = sampler.sample_2d(&fbm, (far, 8));
+        for i in 0..37 {
+            //Chunk b starts 32 samples to the right of chunk a.
+            for x in 32..37 {
+                assert_eq!(a[(x, i)].to_bits(), b[(x - 32, i)].to_bits());
+                assert_eq!(a[(i, x)].to_bits(), c[(i, x - 32)].to_bits());
+            }
+        }
+        let region = sampler.region((far + 1, 7));
+        assert_eq!(region.min[0], sampler.region((far, 7)).max[0]);
+
+        //Split sampling stays exact where f64 positions have lost most of their fraction. Each
+        //chunk spans one unit and the noise repeats every 64, so this chunk matches chunk zero.
+        let very_far = 64 * 50_000_000;
+        let a = sampler.sample_split_2d(&fbm, (very_far, 7));
+        let b = sampler.sample_split_2d(&fbm, (very_far + 1, 7));
+        let near = sampler.sample_2d(&fbm, (0, 7));
+        for i in 0..37 {
+            for x in 32..37 {
+                assert_eq!(a[(x, i)].to_bits(), b[(x - 32, i)].to_bits());
+            }
+            for x in 0..37 {
+                assert!((a[(x, i)] - near[(x, i)]).abs() < 1e-9);
+            }
+        }
+
+        let fbm = Fbm3d::new(seeded_rng(9)).with_frequency((2.0, 2.0, 2.0));
+        let sampler = ChunkSampler::new(8, 0.125).with_shared_border(true);
+        let a = sampler.sample_3d(&fbm, (4, 5, 6));
+        let b = sampler.sample_3d(&fbm, (4, 5, 7));
+        for y in 0..9 {
+            for x in 0..9 {
+                assert_eq!(a[(x, y, 8)].to_bits(), b[(x, y, 0)].to_bits());
+            }
+        }
+        assert_eq!(
+            sampler.volume((4, 5, 7)).min[2],
+            sampler.volume((4, 5, 6)).max[2]
+        );
+    }
+
+    #[test]
+    fn negative_chunks_tile_across_the_origin() {
+        let fbm = Fbm2d::new(seeded_rng(10)).with_frequency((4.0, 4.0));
+        let sampler = ChunkSampler::new(16, 1.0 / 16.0)
+            .with_padding(1)
+            .with_shared_border(true);
+        assert_eq!(sampler.first_index(-1), -17);
+        assert_eq!(sampler.chunk_of(-16), -1);
+        assert_eq!(sampler.chunk_of(-17), -2);
+        let region = sampler.region((-1, -2));
+        assert_eq!((region.min, region.max), ([-1.0, -2.0], [0.0, -1.0]));
+
+        //Chunk (-1, -1) ends where chunk (0, -1) and chunk (-1, 0) begin.
+        let a = sampler.sample_2d(&fbm, (-1, -1));
+        let b = sampler.sample_2d(&fbm, (0, -1));
+        let c = sampler.sample_2d(&fbm, (-1, 0));
+        for i in 0..19 {
+            for x in 16..19 {
+                assert_eq!(a[(x, i)].to_bits(), b[(x - 16, i)].to_bits());
+                assert_eq!(a[(i, x)].to_bits(), c[(i, x - 16)].to_bits());
+            }
+        }
+        assert!(a.data() != b.data());
+        assert_eq!(a[(1, 1)], fbm.value_at_lod([-1.0, -1.0], 1.0 / 16.0));
+    }
+}
@@ -0,0 +1,244 @@
+//! Sampling of 2d noise over a region, and export of the samples as images or raw floats.
+
+pub mod chunk;
+pub mod supersample;
+
+pub use self::chunk::ChunkSampler;
+pub use self::supersample::{ReconstructionFilter, SamplePattern, Supersampler};
+
+use std::io::{self, Write};
+
+use image::png::PNGEncoder;
+use image::ColorType;
+
+use graph::Tape2d;
+use grid::Grid2d;
+use noise::{Noise, Point2};
+
+/// An axis aligned rectangle of noise space.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Region {
+    pub min: Point2<f64>,
+    pub max: Point2<f64>,
+}
+
+impl Region {
+    pub fn new(min: Point2<f64>, max: Point2<f64>) -> Region {
+        Region { min, max }
+    }
+
+    /// Noise space position of the center of pixel `(x, y)` in a `width` x `height` raster.
+    pub fn pixel_center(&self, x: u32, y: u32, width: u32, height: u32) -> Point2<f64> {
+        [
+            self.min[0] + (f64::from(x) + 0.5) * (self.max[0] - self.min[0]) / f64::from(width),
+            self.min[1] + (f64::from(y) + 0.5) * (self.max[1] - self.min[1]) / f64::from(height),
+        ]
+    }
+
+    /// Size of the larger side of a pixel in a `width` x `height` raster, in noise space units.
+    pub fn pixel_footprint(&self, width: u32, height: u32) -> f64 {
+        f64::max(
+            (self.max[0] - self.min[0]).abs() / f64::from(width),
+            (self.max[1] - self.min[1]).abs() / f64::from(height),
+        )
+    }
+}
+
+/// Samples `noise` at the center of every pixel, leaving out detail finer than a pixel.
+pub fn sample_noise<N>(noise: &N, region: Region, width: u32, height: u32) -> Grid2d<f64>
+where
+    N: Noise<IndexType = Point2<f64>>,
+{
+    let footprint = region.pixel_footprint(width, height);
+    let mut grid = Grid2d::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let pos = region.pixel_center(x, y, width, height);
+            grid[(x as usize, y as usize)] = noise.value_at_lod(pos, footprint);
+        }
+    }
+    grid
+}
+
+/// Samples a compiled tape one row at a time, leaving out detail finer than a pixel.
+pub fn sample_tape(tape: &Tape2d, region: Region, width: u32, height: u32) -> Grid2d<f64> {
+    let footprint = region.pixel_footprint(width, height);
+    let mut data = vec![0.0; width as usize * height as usize];
+    let mut points = Vec::with_capacity(width as usize);
+    for (y, row) in data.chunks_mut(width as usize).enumerate() {
+        points.clear();
+        points.extend((0..width).map(|x| region.pixel_center(x, y as u32, width, height)));
+        tape.evaluate_lod(&points, footprint, row);
+    }
+    Grid2d::with_data(width, height, data)
+}
+
+/// Maps `value` from `range` to [0, 1], clamping values outside of it.
+fn normalize(value: f64, range: (f64, f64)) -> f64 {
+    ((value - range.0) / (range.1 - range.0)).clamp(0.0, 1.0)
+}
+
+/// A piecewise linear color map over [0, 1].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorGradient {
+    stops: Vec<(f64, [u8; 3])>,
+}
+
+impl ColorGradient {
+    pub fn new(mut stops: Vec<(f64, [u8; 3])>) -> ColorGradient {
+        assert!(!stops.is_empty(), "A gradient needs at least one stop");
+        stops.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("Gradient stops must not be NaN"));
+        ColorGradient { stops }
+    }
+
+    pub fn grayscale() -> ColorGradient {
+        ColorGradient::new(vec![(0.0, [0, 0, 0]), (1.0, [255, 255, 255])])
+    }
+
+    /// Deep water through beaches and grassland up to snow, with sea level at 0.5.
+    pub fn terrain() -> ColorGradient {
+        ColorGradient::new(vec![
+            (0.0, [0, 0, 128]),
+            (0.45, [0, 64, 192]),
+            (0.5, [64, 160, 224]),
+            (0.52, [224, 208, 144]),
+            (0.58, [64, 160, 48]),
+            (0.75, [32, 96, 32]),
+            (0.88, [128, 112, 96]),
+            (1.0, [255, 255, 255]),
+        ])
+    }
+
+    pub fn stops(&self) -> &[(f64, [u8; 3])] {
+        &self.stops
+    }
+
+    pub fn color_at(&self, t: f64) -> [u8; 3] {
+        let upper = self.stops.iter().position(|s| s.0 >= t);
+        let (a, b) = match upper {
+            Some(0) => return self.stops[0].1,
+            Some(i) => (self.stops[i - 1], self.stops[i]),
+            None => return self.stops[self.stops.len() - 1].1,
+        };
+        let f = (t - a.0) / (b.0 - a.0);
+        let mut color = [0; 3];
+        for (c, (&ca, &cb)) in color.iter_mut().zip(a.1.iter().zip(b.1.iter())) {
+            *c = (f64::from(ca) + f * (f64::from(cb) - f64::from(ca))).round() as u8;
+        }
+        color
+    }
+}
+
+pub fn to_gray8(grid: &Grid2d<f64>, range: (f64, f64)) -> Vec<u8> {
+    grid.data()
+        .iter()
+        .map(|&v| (normalize(v, range) * 255.0).round() as u8)
+        .collect()
+}
+
+/// 16-bit samples as big endian bytes, the layout PNG expects.
+pub fn to_gray16(grid: &Grid2d<f64>, range: (f64, f64)) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(grid.size() * 2);
+    for &v in grid.data() {
+        let sample = (normalize(v, range) * 65535.0).round() as u16;
+        bytes.extend_from_slice(&sample.to_be_bytes());
+    }
+    bytes
+}
+
+pub fn to_rgb8(grid: &Grid2d<f64>, range: (f64, f64), gradient: &ColorGradient) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(grid.size() * 3);
+    for &v in grid.data() {
+        bytes.extend_from_slice(&gradient.color_at(normalize(v, range)));
+    }
+    bytes
+}
+
+pub fn write_png_gray8<W: Write>(w: W, grid: &Grid2d<f64>, range: (f64, f64)) -> io::Result<()> {
+    PNGEncoder::new(w).encode(
+        &to_gray8(grid, range),
+        grid.width(),
+        grid.height(),
+        ColorType::Gray(8),
+    )
+}
+
+pub fn write_png_gray16<W: Write>(w: W, grid: &Grid2d<f64>, range: (f64, f64)) -> io::Result<()> {
+    PNGEncoder::new(w).encode(
+        &to_gray16(grid, range),
+        grid.width(),
+        grid.height(),
+        ColorType::Gray(16),
+    )
+}
+
+pub fn write_png_color<W: Write>(
+    w: W,
+    grid: &Grid2d<f64>,
+    range: (f64, f64),
+    gradient: &ColorGradient,
+) -> io::Result<()> {
+    PNGEncoder::new(w).encode(
+        &to_rgb8(grid, range, gradient),
+        grid.width(),
+        grid.height(),
+        ColorType::RGB(8),
+    )
+}
+
+/// Writes the unmapped samples as little endian `f32`s in row major order.
+pub fn write_raw_f32<W: Write>(mut w: W, grid: &Grid2d<f64>) -> io::Result<()> {
+    for &v in grid.data() {
+        w.write_all(&(v as f32).to_bits().to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::png::PNGDecoder;
+    use image::{DecodingResult, ImageDecoder};
+
+    fn decode(png: &[u8]) -> ((u32, u32), ColorType, Vec<u8>) {
+        let mut decoder = PNGDecoder::new(png);
+        let dimensions = decoder.dimensions().unwrap();
+        let color = decoder.colortype().unwrap();
+        match decoder.read_image().unwrap() {
+            DecodingResult::U8(bytes) => (dimensions, color, bytes),
+            DecodingResult::U16(_) => panic!("expected bytes"),
+        }
+    }
+
+    #[test]
+    fn exports_png_and_raw() {
+        let grid = Grid2d::with_data(3, 2, vec![-1.0, 0.0, 1.0, 0.5, -0.5, 2.0]);
+        let range = (-1.0, 1.0);
+
+        let mut png = Vec::new();
+        write_png_gray8(&mut png, &grid, range).unwrap();
+        assert_eq!(decode(&png), ((3, 2), ColorType::Gray(8), vec![0, 128, 255, 191, 64, 255]));
+
+        let mut png = Vec::new();
+        write_png_gray16(&mut png, &grid, range).unwrap();
+        //The header records 16-bit grayscale; the decoder reduces samples to their high bytes.
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!((png[24], png[25]), (16, 0));
+        let high: Vec<u8> = to_gray16(&grid, range).iter().step_by(2).cloned().collect();
+        assert_eq!(decode(&png), ((3, 2), ColorType::Gray(8), high));
+        assert_eq!(&to_gray16(&grid, range)[..4], &[0, 0, 0x80, 0x00]);
+
+        let mut png = Vec::new();
+        write_png_color(&mut png, &grid, range, &ColorGradient::grayscale()).unwrap();
+        let (dimensions, color, bytes) = decode(&png);
+        assert_eq!((dimensions, color), ((3, 2), ColorType::RGB(8)));
+        assert_eq!(&bytes[6..9], &[255, 255, 255]);
+
+        let mut raw = Vec::new();
+        write_raw_f32(&mut raw, &grid).unwrap();
+        let values: Vec<f32> = raw.chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(values, vec![-1.0, 0.0, 1.0, 0.5, -0.5, 2.0]);
+    }
+}
@@ -0,0 +1,220 @@
+use rand::{Rng, XorShiftRng};
+
+use grid::Grid2d;
+use noise::{seeded_rng, Noise2d, Noise3d, Point2};
+use render::Region;
+
+/// Where the samples of a pixel are placed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SamplePattern {
+    /// An axis aligned `n` x `n` grid.
+    Regular,
+    /// The regular grid rotated by `atan(1/2)`, so that no two samples share a row or column.
+    RotatedGrid,
+    /// One random sample in each cell of the regular grid, trading aliasing for noise.
+    Jittered { seed: u32 },
+}
+
+/// The weight given to a sample by its distance from the pixel center.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ReconstructionFilter {
+    /// Equal weights over the pixel.
+    Box,
+    /// Weights falling off linearly to zero one pixel from the center.
+    Tent,
+    /// A Gaussian with standard deviation `sigma` in pixels, cut off at three deviations.
+    Gaussian { sigma: f64 },
+}
+
+impl ReconstructionFilter {
+    /// Distance from the pixel center, in pixels, beyond which the weight is zero.
+    pub fn radius(&self) -> f64 {
+        match *self {
+            ReconstructionFilter::Box => 0.5,
+            ReconstructionFilter::Tent => 1.0,
+            ReconstructionFilter::Gaussian { sigma } => 3.0 * sigma,
+        }
+    }
+
+    /// Weight of a sample at offset `(dx, dy)` pixels from the pixel center.
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        match *self {
+            ReconstructionFilter::Box => 1.0,
+            ReconstructionFilter::Tent => (1.0 - dx.abs()).max(0.0) * (1.0 - dy.abs()).max(0.0),
+            ReconstructionFilter::Gaussian { sigma } => {
+                (-(dx * dx + dy * dy) / (2.0 * sigma * sigma)).exp()
+            }
+        }
+    }
+}
+
+/// Fills grids with noise averaged over several samples per pixel.
+///
+/// The samples of a pixel are spread over the support of the filter, which for everything but
+/// the box filter reaches into the neighbouring pixels.
+#[derive(Clone, Debug)]
+pub struct Supersampler {
+    samples_per_axis: u32,
+    pattern: SamplePattern,
+    filter: ReconstructionFilter,
+}
+
+impl Supersampler {
+    pub fn new(samples_per_axis: u32) -> Supersampler {
+        assert!(samples_per_axis > 0, "A supersampler needs at least one sample per axis");
+        Supersampler {
+            samples_per_axis,
+            pattern: SamplePattern::Regular,
+            filter: ReconstructionFilter::Box,
+        }
+    }
+
+    pub fn with_pattern(self, pattern: SamplePattern) -> Supersampler {
+        Supersampler { pattern, ..self }
+    }
+    pub fn with_filter(self, filter: ReconstructionFilter) -> Supersampler {
+        if let ReconstructionFilter::Gaussian { sigma } = filter {
+            assert!(sigma > 0.0, "A Gaussian filter needs a positive sigma");
+        }
+        Supersampler { filter, ..self }
+    }
+
+    pub fn samples_per_axis(&self) -> u32 {
+        self.samples_per_axis
+    }
+    pub fn pattern(&self) -> SamplePattern {
+        self.pattern
+    }
+    pub fn filter(&self) -> ReconstructionFilter {
+        self.filter
+    }
+
+    /// Offsets of the samples of a pixel within `[-0.5, 0.5]`, before scaling to the filter.
+    fn offsets(&self, rng: &mut XorShiftRng, offsets: &mut Vec<(f64, f64)>) {
+        let n = self.samples_per_axis;
+        let cell = 1.0 / f64::from(n);
+        offsets.clear();
+        for j in 0..n {
+            for i in 0..n {
+                let (x, y) = ((f64::from(i) + 0.5) * cell - 0.5, (f64::from(j) + 0.5) * cell - 0.5);
+                offsets.push(match self.pattern {
+                    SamplePattern::Regular => (x, y),
+                    SamplePattern::RotatedGrid => {
+                        //cos and sin of atan(1/2), shrunk so the rotated grid stays in the pixel.
+                        let (c, s) = (2.0 / 5f64.sqrt(), 1.0 / 5f64.sqrt());
+                        let shrink = 1.0 / (c + s);
+                        ((x * c - y * s) * shrink, (x * s + y * c) * shrink)
+                    }
+                    SamplePattern::Jittered { .. } => (
+                        x + (rng.gen::<f64>() - 0.5) * cell,
+                        y + (rng.gen::<f64>() - 0.5) * cell,
+                    ),
+                });
+            }
+        }
+    }
+
+    fn sample_with<F>(&self, region: Region, width: u32, height: u32, value_at: F) -> Grid2d<f64>
+    where
+        F: Fn(Point2<f64>, f64) -> f64,
+    {
+        let pixel = [
+            (region.max[0] - region.min[0]) / f64::from(width),
+            (region.max[1] - region.min[1]) / f64::from(height),
+        ];
+        let support = 2.0 * self.filter.radius();
+        //Each sample only needs to resolve detail down to the spacing between samples.
+        let footprint =
+            region.pixel_footprint(width, height) * support / f64::from(self.samples_per_axis);
+        let mut rng = seeded_rng(match self.pattern {
+            SamplePattern::Jittered { seed } => seed,
+            _ => 0,
+        });
+
+        let n = self.samples_per_axis as usize;
+        let mut offsets = Vec::with_capacity(n * n);
+        let mut grid = Grid2d::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let center = region.pixel_center(x, y, width, height);
+                self.offsets(&mut rng, &mut offsets);
+                let (mut total, mut weights) = (0.0, 0.0);
+                for &(dx, dy) in &offsets {
+                    let (dx, dy) = (dx * support, dy * support);
+                    let weight = self.filter.weight(dx, dy);
+                    let pos = [center[0] + dx * pixel[0], center[1] + dy * pixel[1]];
+                    total += value_at(pos, footprint) * weight;
+                    weights += weight;
+                }
+                grid[(x as usize, y as usize)] = total / weights;
+            }
+        }
+        grid
+    }
+
+    pub fn sample_2d<N>(&self, noise: &N, region: Region, width: u32, height: u32) -> Grid2d<f64>
+    where
+        N: Noise2d,
+    {
+        self.sample_with(region, width, height, |pos, footprint| {
+            noise.value_at_lod(pos, footprint)
+        })
+    }
+
+    /// Samples the slice of `noise` at `depth`.
+    pub fn sample_3d<N>(
+        &self,
+        noise: &N,
+        region: Region,
+        depth: f64,
+        width: u32,
+        height: u32,
+    ) -> Grid2d<f64>
+    where
+        N: Noise3d,
+    {
+        self.sample_with(region, width, height, |pos, footprint| {
+            noise.value_at_lod([pos[0], pos[1], depth], footprint)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use adapter::FunctionValue;
+    use render::sample_noise;
+
+    #[test]
+    fn supersampling_removes_aliasing() {
+        //A stripe pattern far above the Nyquist limit of one sample per unit, which point
+        //sampling turns into a false low frequency pattern of full amplitude.
+        let stripes: FunctionValue<Point2<f64>, (f64, f64), _> =
+            FunctionValue::new(|p: &Point2<f64>| (p[0] * 9.9 * 2.0 * PI).sin());
+        let region = Region::new([0.0, 0.0], [32.0, 4.0]);
+        let amplitude = |grid: &Grid2d<f64>| grid.data().iter().fold(0.0, |m: f64, v| m.max(v.abs()));
+
+        assert!(amplitude(&sample_noise(&stripes, region, 32, 4)) > 0.9);
+        let samplers = [
+            Supersampler::new(8),
+            Supersampler::new(8).with_pattern(SamplePattern::RotatedGrid),
+            Supersampler::new(8)
+                .with_pattern(SamplePattern::Jittered { seed: 1 })
+                .with_filter(ReconstructionFilter::Tent),
+            Supersampler::new(8).with_filter(ReconstructionFilter::Gaussian { sigma: 0.5 }),
+        ];
+        for sampler in &samplers {
+            let filtered = amplitude(&sampler.sample_2d(&stripes, region, 32, 4));
+            //Jittered samples leave some noise in place of the aliased pattern.
+            assert!(filtered < 0.3, "{:?} left an amplitude of {}", sampler, filtered);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "positive sigma")]
+    fn rejects_degenerate_gaussian() {
+        Supersampler::new(4).with_filter(ReconstructionFilter::Gaussian { sigma: 0.0 });
+    }
+}
@@ -0,0 +1,354 @@
+use std::f64;
+use std::io::{self, Write};
+
+use image::png::PNGEncoder;
+use image::ColorType;
+
+use grid::Grid2d;
+use noise::{Noise, Point2};
+use render::Region;
+
+/// Identifies a biome within a `BiomeTable`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BiomeId(pub u16);
+
+/// A biome and the climate it occurs in, as closed ranges of the noise layers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Biome {
+    pub id: BiomeId,
+    pub name: String,
+    pub color: [u8; 3],
+    pub temperature: (f64, f64),
+    pub moisture: (f64, f64),
+    pub elevation: (f64, f64),
+}
+
+impl Biome {
+    /// A biome occurring at any climate, to be narrowed down with the `with_*` methods.
+    pub fn new(id: BiomeId, name: &str, color: [u8; 3]) -> Biome {
+        let any = (f64::NEG_INFINITY, f64::INFINITY);
+        Biome {
+            id,
+            name: name.to_string(),
+            color,
+            temperature: any,
+            moisture: any,
+            elevation: any,
+        }
+    }
+
+    pub fn with_temperature(self, min: f64, max: f64) -> Biome {
+        Biome {
+            temperature: (min, max),
+            ..self
+        }
+    }
+    pub fn with_moisture(self, min: f64, max: f64) -> Biome {
+        Biome {
+            moisture: (min, max),
+            ..self
+        }
+    }
+    pub fn with_elevation(self, min: f64, max: f64) -> Biome {
+        Biome {
+            elevation: (min, max),
+            ..self
+        }
+    }
+
+    /// Distance from a climate to the closest climate this biome occurs in, zero inside.
+    pub fn distance(&self, temperature: f64, moisture: f64, elevation: f64) -> f64 {
+        let outside = |v: f64, range: (f64, f64)| (range.0 - v).max(v - range.1).max(0.0);
+        let t = outside(temperature, self.temperature);
+        let m = outside(moisture, self.moisture);
+        let e = outside(elevation, self.elevation);
+        (t * t + m * m + e * e).sqrt()
+    }
+}
+
+/// A Whittaker style lookup table from climate to biome.
+///
+/// A climate belongs to the first biome whose ranges contain it. Climates outside of every
+/// biome belong to the nearest one, so the table doesn't need to cover every climate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BiomeTable {
+    biomes: Vec<Biome>,
+}
+
+impl BiomeTable {
+    pub fn new(biomes: Vec<Biome>) -> BiomeTable {
+        assert!(!biomes.is_empty(), "A biome table needs at least one biome");
+        BiomeTable { biomes }
+    }
+
+    /// A table for layers in about [-1, 1], with sea level at an elevation of 0.
+    pub fn whittaker() -> BiomeTable {
+        let biome = |id, name, color| Biome::new(BiomeId(id), name, color);
+        let land = |b: Biome| b.with_elevation(0.0, 0.6);
+        BiomeTable::new(vec![
+            biome(0, "ocean", [32, 64, 160]).with_elevation(f64::NEG_INFINITY, 0.0),
+            biome(1, "snow", [240, 240, 250]).with_elevation(0.6, f64::INFINITY),
+            land(biome(2, "tundra", [190, 200, 190]).with_temperature(f64::NEG_INFINITY, -0.5)),
+            land(biome(3, "taiga", [60, 110, 80]))
+                .with_temperature(-0.5, 0.0)
+                .with_moisture(0.0, f64::INFINITY),
+            land(biome(4, "grassland", [150, 190, 90]))
+                .with_temperature(-0.5, 0.4)
+                .with_moisture(f64::NEG_INFINITY, 0.0),
+            land(biome(5, "temperate forest", [40, 130, 40]))
+                .with_temperature(0.0, 0.4)
+                .with_moisture(0.0, f64::INFINITY),
+            land(biome(6, "desert", [220, 200, 130]))
+                .with_temperature(0.4, f64::INFINITY)
+                .with_moisture(f64::NEG_INFINITY, -0.3),
+            land(biome(7, "savanna", [180, 170, 80]))
+                .with_temperature(0.4, f64::INFINITY)
+                .with_moisture(-0.3, 0.3),
+            land(biome(8, "rainforest", [20, 100, 30]))
+                .with_temperature(0.4, f64::INFINITY)
+                .with_moisture(0.3, f64::INFINITY),
+        ])
+    }
+
+    pub fn biomes(&self) -> &[Biome] {
+        &self.biomes
+    }
+
+    pub fn get(&self, id: BiomeId) -> Option<&Biome> {
+        self.biomes.iter().find(|b| b.id == id)
+    }
+
+    pub fn classify(&self, temperature: f64, moisture: f64, elevation: f64) -> &Biome {
+        let mut best = (&self.biomes[0], f64::INFINITY);
+        for biome in &self.biomes {
+            let distance = biome.distance(temperature, moisture, elevation);
+            if distance == 0.0 {
+                return biome;
+            }
+            if distance < best.1 {
+                best = (biome, distance);
+            }
+        }
+        best.0
+    }
+
+    /// Weights of the biomes a climate lies within `blend` of, relative to the nearest one,
+    /// summing to one. Without blending only the classified biome has a weight.
+    pub fn weights(
+        &self,
+        temperature: f64,
+        moisture: f64,
+        elevation: f64,
+        blend: f64,
+    ) -> Vec<(&Biome, f64)> {
+        let owner = self.classify(temperature, moisture, elevation);
+        if blend <= 0.0 {
+            return vec![(owner, 1.0)];
+        }
+        let nearest = owner.distance(temperature, moisture, elevation);
+        let mut weights: Vec<(&Biome, f64)> = self
+            .biomes
+            .iter()
+            .filter_map(|biome| {
+                let d = biome.distance(temperature, moisture, elevation) - nearest;
+                let t = 1.0 - d / blend;
+                if biome.id == owner.id {
+                    Some((biome, 1.0))
+                } else if t > 0.0 {
+                    Some((biome, t * t * (3.0 - 2.0 * t)))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let total: f64 = weights.iter().map(|w| w.1).sum();
+        for w in &mut weights {
+            w.1 /= total;
+        }
+        weights
+    }
+}
+
+/// Classifies biomes from temperature, moisture and elevation noise layers.
+#[derive(Clone, Debug)]
+pub struct BiomeMap<T, M, E> {
+    temperature: T,
+    moisture: M,
+    elevation: E,
+    table: BiomeTable,
+    blend: f64,
+}
+
+impl<T, M, E> BiomeMap<T, M, E>
+where
+    T: Noise<IndexType = Point2<f64>>,
+    M: Noise<IndexType = Point2<f64>>,
+    E: Noise<IndexType = Point2<f64>>,
+{
+    pub fn new(temperature: T, moisture: M, elevation: E, table: BiomeTable) -> BiomeMap<T, M, E> {
+        BiomeMap {
+            temperature,
+            moisture,
+            elevation,
+            table,
+            blend: 0.0,
+        }
+    }
+
+    /// Width of the blended border between biomes, in the units of the noise layers.
+    pub fn with_blend(self, blend: f64) -> BiomeMap<T, M, E> {
+        assert!(blend >= 0.0, "Blend width must not be negative");
+        BiomeMap { blend, ..self }
+    }
+
+    pub fn table(&self) -> &BiomeTable {
+        &self.table
+    }
+    pub fn blend(&self) -> f64 {
+        self.blend
+    }
+
+    fn climate_at(&self, pos: Point2<f64>, footprint: f64) -> (f64, f64, f64) {
+        (
+            self.temperature.value_at_lod(pos, footprint),
+            self.moisture.value_at_lod(pos, footprint),
+            self.elevation.value_at_lod(pos, footprint),
+        )
+    }
+
+    /// The biome at `pos`, from the layers at full detail.
+    pub fn biome_at(&self, pos: Point2<f64>) -> BiomeId {
+        self.biome_at_lod(pos, 0.0)
+    }
+
+    /// The biome at `pos` for a sample covering `footprint` units, as `generate` classifies
+    /// pixels. Layer detail finer than the footprint is left out, so borders are smoother than
+    /// with `biome_at`.
+    pub fn biome_at_lod(&self, pos: Point2<f64>, footprint: f64) -> BiomeId {
+        let (t, m, e) = self.climate_at(pos, footprint);
+        self.table.classify(t, m, e).id
+    }
+
+    /// The biomes blending together at a position, with weights summing to one.
+    pub fn weights_at(&self, pos: Point2<f64>) -> Vec<(BiomeId, f64)> {
+        self.weights_at_lod(pos, 0.0)
+    }
+
+    /// The blend weights for a sample covering `footprint` units, as `preview` colors pixels.
+    pub fn weights_at_lod(&self, pos: Point2<f64>, footprint: f64) -> Vec<(BiomeId, f64)> {
+        let (t, m, e) = self.climate_at(pos, footprint);
+        self.table
+            .weights(t, m, e, self.blend)
+            .into_iter()
+            .map(|(biome, w)| (biome.id, w))
+            .collect()
+    }
+
+    /// Classifies the center of every pixel.
+    pub fn generate(&self, region: Region, width: u32, height: u32) -> Grid2d<BiomeId> {
+        let footprint = region.pixel_footprint(width, height);
+        let mut grid = Grid2d::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let pos = region.pixel_center(x, y, width, height);
+                grid[(x as usize, y as usize)] = self.biome_at_lod(pos, footprint);
+            }
+        }
+        grid
+    }
+
+    /// The colors of the biomes, blended across borders, as RGB bytes in row major order.
+    pub fn preview(&self, region: Region, width: u32, height: u32) -> Vec<u8> {
+        let footprint = region.pixel_footprint(width, height);
+        let mut bytes = Vec::with_capacity(width as usize * height as usize * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let (t, m, e) =
+                    self.climate_at(region.pixel_center(x, y, width, height), footprint);
+                let mut color = [0.0; 3];
+                for (biome, w) in self.table.weights(t, m, e, self.blend) {
+                    for (c, &b) in color.iter_mut().zip(&biome.color) {
+                        *c += w * f64::from(b);
+                    }
+                }
+                bytes.extend(color.iter().map(|c| c.round() as u8));
+            }
+        }
+        bytes
+    }
+
+    pub fn write_preview_png<W: Write>(
+        &self,
+        w: W,
+        region: Region,
+        width: u32,
+        height: u32,
+    ) -> io::Result<()> {
+        PNGEncoder::new(w).encode(
+            &self.preview(region, width, height),
+            width,
+            height,
+            ColorType::RGB(8),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use noise::fbm::Fbm2d;
+    use noise::seeded_rng;
+
+    #[test]
+    fn map_matches_table_and_blends_borders() {
+        let layer = |seed| Fbm2d::new(seeded_rng(seed)).with_frequency((2.0, 2.0));
+        let map = BiomeMap::new(layer(1), layer(2), layer(3), BiomeTable::whittaker());
+        let region = Region::new([0.0, 0.0], [4.0, 4.0]);
+        let grid = map.generate(region, 64, 64);
+        let table = map.table();
+
+        let mut seen: Vec<BiomeId> = grid.data().to_vec();
+        seen.sort();
+        seen.dedup();
+        assert!(seen.len() >= 4);
+        assert!(seen.iter().all(|&id| table.get(id).is_some()));
+
+        //Without blending the preview is made of the pure biome colors.
+        let preview = map.preview(region, 64, 64);
+        for (id, color) in grid.data().iter().zip(preview.chunks(3)) {
+            assert_eq!(table.get(*id).unwrap().color, color);
+        }
+
+        let map = map.with_blend(0.1);
+        let mut blended = 0;
+        for y in 0..64 {
+            for x in 0..64 {
+                let pos = region.pixel_center(x, y, 64, 64);
+                let weights = map.weights_at(pos);
+                assert!((weights.iter().map(|w| w.1).sum::<f64>() - 1.0).abs() < 1e-12);
+                let owner = weights.iter().find(|w| w.0 == map.biome_at(pos)).unwrap();
+                assert!(weights.iter().all(|w| w.1 <= owner.1));
+                if weights.len() > 1 {
+                    blended += 1;
+                }
+            }
+        }
+        assert!(blended > 0 && blended < 64 * 64);
+
+        //Pixels are classified with their footprint. Dropping the finest octaves moves borders, so
+        //some pixels differ from full detail, but most agree.
+        let footprint = region.pixel_footprint(64, 64);
+        let grid = map.generate(region, 64, 64);
+        let mut differing = 0;
+        for y in 0..64 {
+            for x in 0..64 {
+                let pos = region.pixel_center(x, y, 64, 64);
+                assert_eq!(grid[(x as usize, y as usize)], map.biome_at_lod(pos, footprint));
+                if map.biome_at(pos) != map.biome_at_lod(pos, footprint) {
+                    differing += 1;
+                }
+            }
+        }
+        assert!(differing > 0 && differing < 64 * 64 / 4, "{} pixels differ", differing);
+    }
+}
@@ -0,0 +1,184 @@
+use rand::XorShiftRng;
+
+use noise::fbm::{DefaultInterpolator, Fbm3d};
+use noise::{seeded_rng, Noise, Point3};
+
+type Layer = Fbm3d<DefaultInterpolator, XorShiftRng>;
+
+/// Rock density for voxel terrain, hollowed out by winding tunnels and larger chambers.
+///
+/// The density is positive in solid rock and zero or negative in air, with the z axis pointing
+/// up. Tunnels run along the curves where two independent fbm fields both cross zero, the
+/// intersection of two ridged fields, and chambers open where a third, lower frequency field
+/// exceeds a threshold. Caves shrink away towards the surface over the depth scale, so they
+/// rarely break through it.
+#[derive(Clone, Debug)]
+pub struct CaveGenerator {
+    tunnels: (Layer, Layer),
+    chambers: Layer,
+    tunnel_radius: f64,
+    chamber_threshold: f64,
+    surface: f64,
+    depth_scale: f64,
+}
+
+impl CaveGenerator {
+    pub fn new(seed: u32) -> CaveGenerator {
+        let layer = |n: u32, frequency: f64| {
+            Fbm3d::new(seeded_rng(seed.wrapping_add(n)))
+                .with_frequency((frequency, frequency, frequency))
+                .with_num_octaves(3)
+        };
+        CaveGenerator {
+            tunnels: (layer(0, 1.0), layer(1, 1.0)),
+            chambers: layer(2, 0.5),
+            tunnel_radius: 0.1,
+            chamber_threshold: 0.3,
+            surface: 0.0,
+            depth_scale: 1.0,
+        }
+    }
+
+    pub fn with_tunnel_frequency(self, frequency: f64) -> CaveGenerator {
+        let frequency = (frequency, frequency, frequency);
+        let (a, b) = self.tunnels;
+        CaveGenerator {
+            tunnels: (a.with_frequency(frequency), b.with_frequency(frequency)),
+            ..self
+        }
+    }
+    /// Width of the tunnels, in units of the ridged fields rather than of noise space.
+    pub fn with_tunnel_radius(self, tunnel_radius: f64) -> CaveGenerator {
+        CaveGenerator {
+            tunnel_radius,
+            ..self
+        }
+    }
+    pub fn with_chamber_frequency(self, frequency: f64) -> CaveGenerator {
+        CaveGenerator {
+            chambers: self
+                .chambers
+                .with_frequency((frequency, frequency, frequency)),
+            ..self
+        }
+    }
+    /// Chamber noise level above which rock is hollowed out; higher values mean fewer chambers.
+    pub fn with_chamber_threshold(self, chamber_threshold: f64) -> CaveGenerator {
+        CaveGenerator {
+            chamber_threshold,
+            ..self
+        }
+    }
+    /// Height of the ground surface, above which everything is air.
+    pub fn with_surface(self, surface: f64) -> CaveGenerator {
+        CaveGenerator { surface, ..self }
+    }
+    /// Depth below the surface at which caves reach their full size.
+    pub fn with_depth_scale(self, depth_scale: f64) -> CaveGenerator {
+        assert!(depth_scale > 0.0, "Depth scale must be positive");
+        CaveGenerator {
+            depth_scale,
+            ..self
+        }
+    }
+
+    pub fn surface(&self) -> f64 {
+        self.surface
+    }
+
+    pub fn is_solid(&self, pos: Point3<f64>) -> bool {
+        self.value_at(pos) > 0.0
+    }
+}
+
+impl Noise for CaveGenerator {
+    type IndexType = Point3<f64>;
+    type DimType = (f64, f64, f64);
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.value_at_lod(pos, 0.0)
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        let ground = self.surface - pos[2];
+        if ground <= 0.0 {
+            return ground;
+        }
+        let depth = (ground / self.depth_scale).min(1.0);
+
+        //Positive inside the caves, so subtracting them from the ground carves them out.
+        let worm = f64::max(
+            self.tunnels.0.value_at_lod(pos, footprint).abs(),
+            self.tunnels.1.value_at_lod(pos, footprint).abs(),
+        );
+        let tunnel = self.tunnel_radius * depth - worm;
+        let chamber =
+            self.chambers.value_at_lod(pos, footprint) - self.chamber_threshold - (1.0 - depth);
+        ground.min(-tunnel).min(-chamber)
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.tunnels.0.frequency()
+    }
+}
+
+impl_noise_ops!(CaveGenerator);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use render::ChunkSampler;
+
+    #[test]
+    fn caves_stay_below_surface_and_chunks_agree() {
+        let caves = CaveGenerator::new(3).with_surface(4.0);
+        let (size, voxel) = (16, 0.125);
+        let sampler = ChunkSampler::new(size, voxel).with_origin([voxel / 2.0; 3]);
+        let mut air = 0;
+        for chunk in 0..2 {
+            let grid = sampler.sample_solid_3d(&caves, (chunk, 1, 0), 0.0);
+            for z in 0..size as usize {
+                for y in 0..size as usize {
+                    for x in 0..size as usize {
+                        let pos = [
+                            (chunk as usize * 16 + x) as f64 * voxel + voxel / 2.0,
+                            (16 + y) as f64 * voxel + voxel / 2.0,
+                            z as f64 * voxel + voxel / 2.0,
+                        ];
+                        assert_eq!(grid[(x, y, z)], caves.value_at_lod(pos, voxel) > 0.0);
+                        if !grid[(x, y, z)] {
+                            air += 1;
+                        }
+                    }
+                }
+            }
+        }
+        //Well below the surface caves exist, but most of the rock is solid.
+        assert!(air > 0 && air < 2 * 16 * 16 * 16 / 2);
+
+        let above = ChunkSampler::new(8, 0.25)
+            .with_origin([0.125; 3])
+            .sample_solid_3d(&caves, (0, 0, 2), 0.0);
+        assert!(above.data().iter().all(|&solid| !solid));
+        let shallow = CaveGenerator::new(3)
+            .with_surface(4.0)
+            .with_depth_scale(8.0);
+        assert!(!shallow.is_solid([1.0, 1.0, 4.5]));
+        assert!(shallow.is_solid([1.0, 1.0, 3.99]));
+    }
+
+    #[test]
+    fn default_caves_vary_below_the_origin() {
+        //The default surface is at zero, so every cave lies at negative z, and often at negative
+        //x and y. Lattice cells there must stay distinct rather than collapsing onto one cell.
+        let caves = CaveGenerator::new(3);
+        let sampler = ChunkSampler::new(16, 0.125);
+        let a = sampler.sample_solid_3d(&caves, (-1, -1, -2), 0.0);
+        let b = sampler.sample_solid_3d(&caves, (-2, -1, -2), 0.0);
+        let c = sampler.sample_solid_3d(&caves, (-1, -1, -3), 0.0);
+        assert!(a.data() != b.data() && a.data() != c.data());
+        for grid in &[a, b, c] {
+            let air = grid.data().iter().filter(|&&solid| !solid).count();
+            assert!(air > 0 && air < grid.size() / 2, "{} air voxels", air);
+        }
+        assert!(caves.value_at([-3.3, -7.1, -2.2]) != caves.value_at([-4.3, -7.1, -2.2]));
+    }
+}
@@ -0,0 +1,295 @@
+use rand::XorShiftRng;
+
+use analysis::NoiseStats;
+use noise::fbm::{DefaultInterpolator, Fbm2d};
+use noise::{bounds, seeded_rng, Noise, Point2, WithFrequency, Worley2d};
+use render::Region;
+
+type Layer = Fbm2d<DefaultInterpolator, XorShiftRng>;
+
+/// Builds planet scale elevation from tectonic plates, with sea level placed to cover a given
+/// fraction of the map with land.
+///
+/// Every cell of a `Worley2d` is a plate with a random base elevation. Plates blend into each
+/// other across their borders, and mountain ranges rise where two high plates meet. The plates
+/// are sampled through a domain warp made of two `Fbm2d`s, which breaks up the straight plate
+/// borders into ragged coastlines, and a third `Fbm2d` adds detail on top.
+#[derive(Clone, Debug)]
+pub struct ContinentGenerator {
+    seed: u32,
+    plate_frequency: f64,
+    warp_frequency: f64,
+    warp_strength: f64,
+    border_width: f64,
+    mountain_height: f64,
+    detail_frequency: f64,
+    detail_amplitude: f64,
+    land_ratio: f64,
+    sample_region: Region,
+    sample_resolution: u32,
+}
+
+impl Default for ContinentGenerator {
+    fn default() -> ContinentGenerator {
+        ContinentGenerator::new(0)
+    }
+}
+
+impl ContinentGenerator {
+    pub fn new(seed: u32) -> ContinentGenerator {
+        ContinentGenerator {
+            seed,
+            plate_frequency: 4.0,
+            warp_frequency: 2.0,
+            warp_strength: 0.3,
+            border_width: 0.3,
+            mountain_height: 0.6,
+            detail_frequency: 8.0,
+            detail_amplitude: 0.15,
+            land_ratio: 0.3,
+            sample_region: Region::new([0.0, 0.0], [1.0, 1.0]),
+            sample_resolution: 128,
+        }
+    }
+
+    /// Plates per unit of noise space along each axis.
+    pub fn with_plate_frequency(self, plate_frequency: f64) -> ContinentGenerator {
+        ContinentGenerator {
+            plate_frequency,
+            ..self
+        }
+    }
+    /// Frequency of the domain warp and how far, in noise space units, it displaces positions.
+    pub fn with_warp(self, warp_frequency: f64, warp_strength: f64) -> ContinentGenerator {
+        ContinentGenerator {
+            warp_frequency,
+            warp_strength,
+            ..self
+        }
+    }
+    /// Width of the blend between plates, in plate cells.
+    pub fn with_border_width(self, border_width: f64) -> ContinentGenerator {
+        assert!(border_width > 0.0, "Border width must be positive");
+        ContinentGenerator {
+            border_width,
+            ..self
+        }
+    }
+    pub fn with_mountain_height(self, mountain_height: f64) -> ContinentGenerator {
+        ContinentGenerator {
+            mountain_height,
+            ..self
+        }
+    }
+    pub fn with_detail(self, detail_frequency: f64, detail_amplitude: f64) -> ContinentGenerator {
+        ContinentGenerator {
+            detail_frequency,
+            detail_amplitude,
+            ..self
+        }
+    }
+    /// Fraction of the sample region above sea level, within [0, 1].
+    pub fn with_land_ratio(self, land_ratio: f64) -> ContinentGenerator {
+        assert!(
+            (0.0..=1.0).contains(&land_ratio),
+            "Land ratio must be within [0, 1]"
+        );
+        ContinentGenerator { land_ratio, ..self }
+    }
+    /// The region sampled to place sea level, at `resolution` x `resolution` samples.
+    pub fn with_sample_region(self, sample_region: Region, resolution: u32) -> ContinentGenerator {
+        assert!(resolution > 0, "Sea level needs at least one sample");
+        ContinentGenerator {
+            sample_region,
+            sample_resolution: resolution,
+            ..self
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+    pub fn land_ratio(&self) -> f64 {
+        self.land_ratio
+    }
+
+    /// Builds the elevation noise, solving for the sea level by sampling the sample region.
+    pub fn build(&self) -> Continents {
+        let layer = |n: u32, frequency: f64| {
+            Fbm2d::new(seeded_rng(self.seed.wrapping_add(n))).with_frequency((frequency, frequency))
+        };
+        let mut continents = Continents {
+            plates: Worley2d::new(self.seed)
+                .with_frequency((self.plate_frequency, self.plate_frequency)),
+            warp: (layer(1, self.warp_frequency), layer(2, self.warp_frequency)),
+            detail: layer(3, self.detail_frequency),
+            warp_strength: self.warp_strength,
+            border_width: self.border_width,
+            mountain_height: self.mountain_height,
+            detail_amplitude: self.detail_amplitude,
+            sea_level: 0.0,
+        };
+
+        //Sampled at full detail, so the ratio holds for `value_at` rather than for a rendering
+        //at the sample resolution.
+        let size = self.sample_resolution;
+        let mut samples = Vec::with_capacity(size as usize * size as usize);
+        for y in 0..size {
+            for x in 0..size {
+                let pos = self.sample_region.pixel_center(x, y, size, size);
+                samples.push(continents.value_at(pos));
+            }
+        }
+        let stats = NoiseStats::from_values(samples);
+        continents.sea_level = stats.percentile(100.0 * (1.0 - self.land_ratio));
+        continents
+    }
+}
+
+/// Elevation built by a `ContinentGenerator`, with sea level at zero.
+#[derive(Clone, Debug)]
+pub struct Continents {
+    plates: Worley2d,
+    warp: (Layer, Layer),
+    detail: Layer,
+    warp_strength: f64,
+    border_width: f64,
+    mountain_height: f64,
+    detail_amplitude: f64,
+    sea_level: f64,
+}
+
+impl Continents {
+    /// The elevation of the sea, relative to the unshifted plates and detail.
+    pub fn sea_level(&self) -> f64 {
+        self.sea_level
+    }
+
+    fn warped(&self, pos: Point2<f64>, footprint: f64) -> Point2<f64> {
+        [
+            pos[0] + self.warp_strength * self.warp.0.value_at_lod(pos, footprint),
+            pos[1] + self.warp_strength * self.warp.1.value_at_lod(pos, footprint),
+        ]
+    }
+
+    /// The cell of the plate `pos` lies on.
+    pub fn plate_at(&self, pos: Point2<f64>) -> (i64, i64) {
+        self.plates.nearest_cells(self.warped(pos, 0.0)).0.cell
+    }
+
+    fn plate_elevation(&self, pos: Point2<f64>) -> f64 {
+        let (first, second) = self.plates.nearest_cells(pos);
+        //Every plate within `border_width` of the nearest one contributes, with a weight that
+        //falls to zero at that distance. The weights, and with them the blend, change
+        //continuously even where the second nearest plate changes near a triple junction.
+        let (mut total, mut weights) = (0.0, 0.0);
+        let radius = first.distance + self.border_width;
+        self.plates.for_each_cell_within(pos, radius, |plate| {
+            let weight = 1.0 - (plate.distance - first.distance) / self.border_width;
+            if weight > 0.0 {
+                total += weight * self.plates.cell_value(plate.cell);
+                weights += weight;
+            }
+        });
+        let base = total / weights;
+        let border = (1.0 - (second.distance - first.distance) / self.border_width).max(0.0);
+        let ridge = self.mountain_height * border * border * base.max(0.0);
+        base + ridge
+    }
+}
+
+impl Noise for Continents {
+    type IndexType = Point2<f64>;
+    type DimType = (f64, f64);
+
+    fn value_at(&self, pos: Self::IndexType) -> f64 {
+        self.value_at_lod(pos, 0.0)
+    }
+    fn value_at_lod(&self, pos: Self::IndexType, footprint: f64) -> f64 {
+        let plates = self.plate_elevation(self.warped(pos, footprint));
+        let detail = self.detail_amplitude * self.detail.value_at_lod(pos, footprint);
+        plates + detail - self.sea_level
+    }
+    fn output_bounds(&self) -> (f64, f64) {
+        let plates = (
+            -1.0 + self.mountain_height.min(0.0),
+            1.0 + self.mountain_height.max(0.0),
+        );
+        let detail = bounds::affine(self.detail.output_bounds(), self.detail_amplitude, 0.0);
+        bounds::affine(bounds::add(plates, detail), 1.0, -self.sea_level)
+    }
+    fn frequency(&self) -> Self::DimType {
+        self.plates.frequency()
+    }
+}
+
+impl_noise_ops!(Continents);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sea_level_gives_requested_land_ratio() {
+        let region = Region::new([0.0, 0.0], [2.0, 1.0]);
+        for &ratio in &[0.3, 0.6] {
+            let generator = ContinentGenerator::new(7)
+                .with_land_ratio(ratio)
+                .with_sample_region(region, 96);
+            let continents = generator.build();
+            let heights: Vec<f64> = (0..96 * 96)
+                .map(|i| continents.value_at(region.pixel_center(i % 96, i / 96, 96, 96)))
+                .collect();
+            let land = heights.iter().filter(|&&h| h > 0.0).count();
+            assert!((land as f64 / heights.len() as f64 - ratio).abs() < 0.01);
+
+            let (low, high) = continents.output_bounds();
+            assert!(heights.iter().all(|&h| low <= h && h <= high));
+            assert_eq!(
+                generator.build().value_at([0.3, 0.4]),
+                continents.value_at([0.3, 0.4])
+            );
+        }
+
+        //Plates span many samples, so neighbouring samples mostly share one.
+        let continents = ContinentGenerator::new(7).build();
+        let same = (0..100)
+            .filter(|&i| {
+                let pos = [i as f64 * 0.01, 0.5];
+                continents.plate_at(pos) == continents.plate_at([pos[0] + 0.01, 0.5])
+            })
+            .count();
+        assert!(same > 80);
+    }
+
+    #[test]
+    fn elevation_is_continuous_across_plate_borders() {
+        //Without warp and detail, any jump comes from the plates alone. The scan crosses the
+        //borders around a triple junction.
+        let continents = ContinentGenerator::new(7)
+            .with_warp(2.0, 0.0)
+            .with_detail(8.0, 0.0)
+            .build();
+        let step = 1e-5;
+        let mut borders = 0;
+        for &(start, direction) in &[([0.55, 0.48], [1.0, 0.0]), ([0.56785, 0.45], [0.0, 1.0])] {
+            let at = |i: i32| {
+                let t = f64::from(i) * step;
+                [start[0] + direction[0] * t, start[1] + direction[1] * t]
+            };
+            for i in 0..5000 {
+                let (a, b) = (at(i), at(i + 1));
+                assert!(
+                    (continents.value_at(a) - continents.value_at(b)).abs() < 2e-3,
+                    "jump between {:?} and {:?}",
+                    a,
+                    b
+                );
+                if continents.plate_at(a) != continents.plate_at(b) {
+                    borders += 1;
+                }
+            }
+        }
+        assert!(borders >= 2);
+    }
+}
@@ -0,0 +1,253 @@
+use rand::Rng;
+
+use grid::Grid2d;
+use noise::seeded_rng;
+
+/// Height and gradient at a position between lattice points, by bilinear interpolation.
+fn height_and_gradient(grid: &Grid2d<f64>, x: f64, y: f64) -> (f64, f64, f64) {
+    let (cx, cy) = (x.floor() as usize, y.floor() as usize);
+    let (tx, ty) = (x - cx as f64, y - cy as f64);
+    let h00 = grid[(cx, cy)];
+    let h10 = grid[(cx + 1, cy)];
+    let h01 = grid[(cx, cy + 1)];
+    let h11 = grid[(cx + 1, cy + 1)];
+    let gx = (h10 - h00) * (1.0 - ty) + (h11 - h01) * ty;
+    let gy = (h01 - h00) * (1.0 - tx) + (h11 - h10) * tx;
+    let height = h00 * (1.0 - tx) * (1.0 - ty) + h10 * tx * (1.0 - ty) + h01 * (1.0 - tx) * ty
+        + h11 * tx * ty;
+    (height, gx, gy)
+}
+
+/// Droplet based hydraulic erosion.
+///
+/// Each droplet starts at a random position and runs downhill, picking up sediment while it is
+/// below its carrying capacity and depositing it when it slows down or climbs. Capacity grows
+/// with the speed, the amount of water and the steepness of the descent, and the water slowly
+/// evaporates. Heights are measured in the same units as the spacing between lattice points.
+#[derive(Clone, Debug)]
+pub struct HydraulicErosion {
+    droplets: usize,
+    seed: u32,
+    inertia: f64,
+    capacity: f64,
+    min_capacity: f64,
+    deposition: f64,
+    erosion: f64,
+    evaporation: f64,
+    gravity: f64,
+    radius: usize,
+    max_lifetime: usize,
+}
+
+impl Default for HydraulicErosion {
+    fn default() -> HydraulicErosion {
+        HydraulicErosion::new(50_000)
+    }
+}
+
+impl HydraulicErosion {
+    pub fn new(droplets: usize) -> HydraulicErosion {
+        HydraulicErosion {
+            droplets,
+            seed: 0,
+            inertia: 0.05,
+            capacity: 4.0,
+            min_capacity: 0.01,
+            deposition: 0.3,
+            erosion: 0.3,
+            evaporation: 0.01,
+            gravity: 4.0,
+            radius: 3,
+            max_lifetime: 30,
+        }
+    }
+
+    pub fn with_seed(self, seed: u32) -> HydraulicErosion {
+        HydraulicErosion { seed, ..self }
+    }
+    /// How much a droplet keeps its direction rather than following the slope, in `[0, 1]`.
+    pub fn with_inertia(self, inertia: f64) -> HydraulicErosion {
+        assert!((0.0..=1.0).contains(&inertia), "Inertia must be within [0, 1]");
+        HydraulicErosion { inertia, ..self }
+    }
+    /// Sediment carried per unit of speed, water and height lost.
+    pub fn with_capacity(self, capacity: f64) -> HydraulicErosion {
+        HydraulicErosion { capacity, ..self }
+    }
+    /// The least capacity of a droplet, so that it keeps eroding on flat ground.
+    pub fn with_min_capacity(self, min_capacity: f64) -> HydraulicErosion {
+        HydraulicErosion {
+            min_capacity,
+            ..self
+        }
+    }
+    /// Fraction of the sediment above capacity deposited per step.
+    pub fn with_deposition(self, deposition: f64) -> HydraulicErosion {
+        HydraulicErosion { deposition, ..self }
+    }
+    /// Fraction of the free capacity filled by erosion per step.
+    pub fn with_erosion(self, erosion: f64) -> HydraulicErosion {
+        HydraulicErosion { erosion, ..self }
+    }
+    /// Fraction of the water evaporating per step.
+    pub fn with_evaporation(self, evaporation: f64) -> HydraulicErosion {
+        HydraulicErosion {
+            evaporation,
+            ..self
+        }
+    }
+    pub fn with_gravity(self, gravity: f64) -> HydraulicErosion {
+        HydraulicErosion { gravity, ..self }
+    }
+    /// Radius, in lattice cells, of the area a droplet erodes at once.
+    pub fn with_radius(self, radius: usize) -> HydraulicErosion {
+        HydraulicErosion { radius, ..self }
+    }
+    /// Most steps a droplet takes before it is dropped.
+    pub fn with_max_lifetime(self, max_lifetime: usize) -> HydraulicErosion {
+        HydraulicErosion {
+            max_lifetime,
+            ..self
+        }
+    }
+
+    pub fn droplets(&self) -> usize {
+        self.droplets
+    }
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Offsets and weights of the lattice points eroded around a point, heaviest at the center.
+    fn brush(&self) -> Vec<(isize, isize, f64)> {
+        let r = self.radius as isize;
+        let mut brush = Vec::new();
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let weight = self.radius as f64 + 0.5 - ((dx * dx + dy * dy) as f64).sqrt();
+                if weight > 0.0 {
+                    brush.push((dx, dy, weight));
+                }
+            }
+        }
+        brush
+    }
+
+    /// Erodes `grid` in place. The same seed always gives the same result.
+    pub fn erode(&self, grid: &mut Grid2d<f64>) {
+        let (width, height) = (grid.width() as usize, grid.height() as usize);
+        assert!(width > 1 && height > 1, "Erosion needs two points per axis");
+        let (max_x, max_y) = ((width - 1) as f64, (height - 1) as f64);
+        let brush = self.brush();
+        let mut rng = seeded_rng(self.seed);
+        let mut targets = Vec::with_capacity(brush.len());
+
+        for _ in 0..self.droplets {
+            let (mut x, mut y) = (rng.gen::<f64>() * max_x, rng.gen::<f64>() * max_y);
+            let (mut dx, mut dy) = (0.0, 0.0);
+            let (mut speed, mut water, mut sediment) = (1.0, 1.0, 0.0);
+
+            for _ in 0..self.max_lifetime {
+                let (cx, cy) = (x.floor() as usize, y.floor() as usize);
+                let (tx, ty) = (x - cx as f64, y - cy as f64);
+                let (h, gx, gy) = height_and_gradient(grid, x, y);
+
+                dx = dx * self.inertia - gx * (1.0 - self.inertia);
+                dy = dy * self.inertia - gy * (1.0 - self.inertia);
+                let length = (dx * dx + dy * dy).sqrt();
+                if length == 0.0 {
+                    break;
+                }
+                dx /= length;
+                dy /= length;
+                x += dx;
+                y += dy;
+                if x < 0.0 || x >= max_x || y < 0.0 || y >= max_y {
+                    break;
+                }
+
+                let dh = height_and_gradient(grid, x, y).0 - h;
+                let capacity = (-dh).max(self.min_capacity) * speed * water * self.capacity;
+                if sediment > capacity || dh > 0.0 {
+                    //Uphill the droplet fills the pit it came from, otherwise it sheds a part of
+                    //the excess. Either way the sediment lands on the corners of its old cell.
+                    let deposit = if dh > 0.0 {
+                        dh.min(sediment)
+                    } else {
+                        (sediment - capacity) * self.deposition
+                    };
+                    sediment -= deposit;
+                    grid[(cx, cy)] += deposit * (1.0 - tx) * (1.0 - ty);
+                    grid[(cx + 1, cy)] += deposit * tx * (1.0 - ty);
+                    grid[(cx, cy + 1)] += deposit * (1.0 - tx) * ty;
+                    grid[(cx + 1, cy + 1)] += deposit * tx * ty;
+                } else {
+                    //Never dig deeper than the height lost, which would carve a pit behind.
+                    let amount = ((capacity - sediment) * self.erosion).min(-dh);
+                    targets.clear();
+                    targets.extend(brush.iter().filter_map(|&(ox, oy, w)| {
+                        let (bx, by) = (cx as isize + ox, cy as isize + oy);
+                        if bx >= 0 && by >= 0 && (bx as usize) < width && (by as usize) < height {
+                            Some((bx as usize, by as usize, w))
+                        } else {
+                            None
+                        }
+                    }));
+                    let total: f64 = targets.iter().map(|t| t.2).sum();
+                    for &(bx, by, w) in &targets {
+                        grid[(bx, by)] -= amount * w / total;
+                    }
+                    sediment += amount;
+                }
+
+                speed = (speed * speed - dh * self.gravity).max(0.0).sqrt();
+                water *= 1.0 - self.evaporation;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use adapter::GridNoise;
+    use noise::Noise;
+    use render::Region;
+    use terrain::test_support::fbm_heightmap;
+
+    #[test]
+    fn erosion_is_deterministic_and_usable_as_noise() {
+        let region = Region::new([0.0, 0.0], [1.0, 1.0]);
+        let grid = fbm_heightmap(4, 65, 20.0);
+        let erosion = HydraulicErosion::new(2000).with_seed(9);
+        let (mut a, mut b) = (grid.clone(), grid.clone());
+        erosion.erode(&mut a);
+        erosion.erode(&mut b);
+        assert_eq!(a.data(), b.data());
+        assert!(a.data() != grid.data());
+
+        //Droplets only move material downhill or carry it off the map, so the total height can't
+        //grow, peaks are worn down and the terrain becomes smoother.
+        let total = |g: &Grid2d<f64>| g.data().iter().sum::<f64>();
+        let peak = |g: &Grid2d<f64>| g.data().iter().cloned().fold(f64::MIN, f64::max);
+        let roughness = |g: &Grid2d<f64>| {
+            let mut sum = 0.0;
+            for y in 0..65 {
+                for x in 0..64 {
+                    sum += (g[(x + 1, y)] - g[(x, y)]).powi(2);
+                    sum += (g[(y, x + 1)] - g[(y, x)]).powi(2);
+                }
+            }
+            sum
+        };
+        assert!(total(&a) <= total(&grid) + 1e-9, "{} > {}", total(&a), total(&grid));
+        assert!(peak(&a) < peak(&grid));
+        assert!(roughness(&a) < roughness(&grid));
+
+        let noise = GridNoise::new(a, region.min, region.max);
+        assert!((noise.value_at([0.5, 0.25]) - noise.grid()[(32, 16)]).abs() < 1e-12);
+        assert!((noise.value_at([2.0, -1.0]) - noise.grid()[(64, 0)]).abs() < 1e-12);
+        let (low, high) = noise.output_bounds();
+        assert!(noise.grid().data().iter().all(|&v| low <= v && v <= high));
+    }
+}
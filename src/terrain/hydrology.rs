@@ -0,0 +1,327 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f64::consts::{FRAC_PI_4, SQRT_2};
+
+use grid::Grid2d;
+use noise::Point2;
+
+//Neighbours in counter clockwise order starting east, cardinal on even and diagonal on odd
+//indices.
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+fn neighbour(grid: &Grid2d<f64>, x: usize, y: usize, k: usize) -> Option<(usize, usize)> {
+    let (dx, dy) = NEIGHBOURS[k % 8];
+    let (nx, ny) = (x as isize + dx, y as isize + dy);
+    if nx < 0
+        || ny < 0
+        || nx as usize >= grid.width() as usize
+        || ny as usize >= grid.height() as usize
+    {
+        None
+    } else {
+        Some((nx as usize, ny as usize))
+    }
+}
+
+/// A lattice point waiting in the flood, lowest first.
+#[derive(PartialEq)]
+struct Flooded(f64, usize);
+
+impl Eq for Flooded {}
+
+impl Ord for Flooded {
+    fn cmp(&self, other: &Flooded) -> Ordering {
+        other
+            .0
+            .partial_cmp(&self.0)
+            .expect("Heights must not be NaN")
+            .then(other.1.cmp(&self.1))
+    }
+}
+
+impl PartialOrd for Flooded {
+    fn partial_cmp(&self, other: &Flooded) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Fills every depression of `grid` up to its spill point, by flooding inwards from the edges in
+/// order of height.
+///
+/// With an `epsilon` of zero filled depressions become flat lakes. A positive `epsilon` raises
+/// every filled point that much above the one it was flooded from, leaving a slight slope
+/// towards the spill point so that every point drains off the edge of the map. It needs to be
+/// large enough to change the heights at their magnitude.
+pub fn fill_pits(grid: &mut Grid2d<f64>, epsilon: f64) {
+    let (width, height) = (grid.width() as usize, grid.height() as usize);
+    let mut flooded = vec![false; grid.size()];
+    let mut queue = BinaryHeap::new();
+    for y in 0..height {
+        for x in 0..width {
+            if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                let i = grid.index_from_coords((x, y));
+                flooded[i] = true;
+                queue.push(Flooded(grid[i], i));
+            }
+        }
+    }
+
+    while let Some(Flooded(h, i)) = queue.pop() {
+        let (x, y) = (i % width, i / width);
+        for k in 0..8 {
+            if let Some(n) = neighbour(grid, x, y, k) {
+                let n = grid.index_from_coords(n);
+                if flooded[n] {
+                    continue;
+                }
+                flooded[n] = true;
+                if grid[n] <= h {
+                    grid[n] = h + epsilon;
+                }
+                queue.push(Flooded(grid[n], n));
+            }
+        }
+    }
+}
+
+/// How water leaving a lattice point is distributed among its lower neighbours.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FlowRouting {
+    /// All of it flows to the neighbour with the steepest descent.
+    D8,
+    /// It flows in the direction of steepest descent over the eight triangular facets around
+    /// the point, shared between the two neighbours spanning that facet.
+    DInfinity,
+}
+
+/// Where the water of every lattice point of a heightmap flows to.
+#[derive(Clone, Debug)]
+pub struct FlowField {
+    width: u32,
+    height: u32,
+    receivers: Vec<Vec<(usize, f64)>>,
+    //Indices from highest to lowest, so every point comes before the points it drains into.
+    order: Vec<usize>,
+}
+
+impl FlowField {
+    /// Routes water over `grid`, which should have its pits filled to drain completely. Water
+    /// reaching a point without lower neighbours stops there.
+    pub fn new(grid: &Grid2d<f64>, routing: FlowRouting) -> FlowField {
+        let (width, height) = (grid.width() as usize, grid.height() as usize);
+        let mut receivers = Vec::with_capacity(grid.size());
+        for y in 0..height {
+            for x in 0..width {
+                receivers.push(match routing {
+                    FlowRouting::D8 => Self::d8(grid, x, y),
+                    FlowRouting::DInfinity => Self::d_infinity(grid, x, y),
+                });
+            }
+        }
+        let mut order: Vec<usize> = (0..grid.size()).collect();
+        order.sort_by(|&a, &b| {
+            grid[b]
+                .partial_cmp(&grid[a])
+                .expect("Heights must not be NaN")
+        });
+        FlowField {
+            width: grid.width(),
+            height: grid.height(),
+            receivers,
+            order,
+        }
+    }
+
+    fn d8(grid: &Grid2d<f64>, x: usize, y: usize) -> Vec<(usize, f64)> {
+        let h = grid[(x, y)];
+        let mut steepest = None;
+        let mut steepest_slope = 0.0;
+        for k in 0..8 {
+            if let Some(n) = neighbour(grid, x, y, k) {
+                let distance = if k % 2 == 0 { 1.0 } else { SQRT_2 };
+                let slope = (h - grid[n]) / distance;
+                if slope > steepest_slope {
+                    steepest = Some(grid.index_from_coords(n));
+                    steepest_slope = slope;
+                }
+            }
+        }
+        steepest.into_iter().map(|n| (n, 1.0)).collect()
+    }
+
+    fn d_infinity(grid: &Grid2d<f64>, x: usize, y: usize) -> Vec<(usize, f64)> {
+        let h = grid[(x, y)];
+        let mut best = Vec::new();
+        let mut best_slope = 0.0;
+        //Each facet spans a cardinal neighbour and the diagonal one next to it, on either side.
+        for cardinal in (0..8).step_by(2) {
+            for &diagonal in &[cardinal + 1, cardinal + 7] {
+                let (c, d) = match (
+                    neighbour(grid, x, y, cardinal),
+                    neighbour(grid, x, y, diagonal),
+                ) {
+                    (Some(c), Some(d)) => (c, d),
+                    _ => continue,
+                };
+                let s1 = h - grid[c];
+                let s2 = grid[c] - grid[d];
+                let (angle, slope) = if s2 <= 0.0 {
+                    (0.0, s1)
+                } else if s2 >= s1 {
+                    (FRAC_PI_4, (h - grid[d]) / SQRT_2)
+                } else {
+                    (s2.atan2(s1), (s1 * s1 + s2 * s2).sqrt())
+                };
+                if slope > best_slope {
+                    let share = angle / FRAC_PI_4;
+                    best = vec![
+                        (grid.index_from_coords(c), 1.0 - share),
+                        (grid.index_from_coords(d), share),
+                    ];
+                    best.retain(|r| r.1 > 0.0);
+                    best_slope = slope;
+                }
+            }
+        }
+        best
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Grid indices of the points the water of `(x, y)` flows to, with the fraction each of them
+    /// receives. Empty for points the water leaves the map from, or gets stuck at.
+    pub fn receivers(&self, x: usize, y: usize) -> &[(usize, f64)] {
+        &self.receivers[x + y * self.width as usize]
+    }
+
+    /// The number of lattice points draining through every point, including itself.
+    pub fn accumulation(&self) -> Grid2d<f64> {
+        let mut accumulation = vec![1.0; self.receivers.len()];
+        for &i in &self.order {
+            for &(r, fraction) in &self.receivers[i] {
+                accumulation[r] += accumulation[i] * fraction;
+            }
+        }
+        Grid2d::with_data(self.width, self.height, accumulation)
+    }
+}
+
+/// The rivers of a heightmap, where the accumulated flow exceeds a threshold.
+#[derive(Clone, Debug)]
+pub struct RiverNetwork {
+    mask: Grid2d<bool>,
+    paths: Vec<Vec<Point2<f64>>>,
+}
+
+impl RiverNetwork {
+    /// Marks every point with an accumulation of at least `threshold` as river and traces the
+    /// rivers downstream along their main receivers.
+    pub fn extract(flow: &FlowField, accumulation: &Grid2d<f64>, threshold: f64) -> RiverNetwork {
+        let width = flow.width() as usize;
+        let mask = Grid2d::with_data(
+            flow.width(),
+            flow.height(),
+            accumulation
+                .data()
+                .iter()
+                .map(|&a| a >= threshold)
+                .collect(),
+        );
+        let point = |i: usize| [(i % width) as f64, (i / width) as f64];
+
+        //Sources come before the points they drain into, so every path starts at the head of
+        //a river and runs until it leaves the network or joins a river traced before.
+        let mut traced = vec![false; mask.size()];
+        let mut paths = Vec::new();
+        for &start in &flow.order {
+            if !mask[start] || traced[start] {
+                continue;
+            }
+            let mut path = Vec::new();
+            let mut current = Some(start);
+            while let Some(i) = current {
+                path.push(point(i));
+                if traced[i] {
+                    break;
+                }
+                traced[i] = true;
+                current = flow.receivers[i]
+                    .iter()
+                    .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                    .map(|r| r.0)
+                    .filter(|&r| mask[r]);
+            }
+            if path.len() > 1 {
+                paths.push(path);
+            }
+        }
+        RiverNetwork { mask, paths }
+    }
+
+    pub fn mask(&self) -> &Grid2d<bool> {
+        &self.mask
+    }
+
+    /// Polylines in lattice coordinates, running downstream. Tributaries end on the point
+    /// where they join another path.
+    pub fn paths(&self) -> &[Vec<Point2<f64>>] {
+        &self.paths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use terrain::test_support::fbm_heightmap;
+
+    #[test]
+    fn filled_terrain_drains_completely_into_rivers() {
+        let mut grid = fbm_heightmap(21, 48, 10.0);
+        let original = grid.clone();
+        fill_pits(&mut grid, 1e-6);
+        assert!(grid.data().iter().zip(original.data()).all(|(f, o)| f >= o));
+
+        for &routing in &[FlowRouting::D8, FlowRouting::DInfinity] {
+            let flow = FlowField::new(&grid, routing);
+            for y in 1..47 {
+                for x in 1..47 {
+                    assert!(!flow.receivers(x, y).is_empty());
+                    let total: f64 = flow.receivers(x, y).iter().map(|r| r.1).sum();
+                    assert!((total - 1.0).abs() < 1e-12);
+                }
+            }
+
+            //All water ends up at the points it leaves the map from.
+            let accumulation = flow.accumulation();
+            let drained: f64 = (0..grid.size())
+                .filter(|&i| flow.receivers[i].is_empty())
+                .map(|i| accumulation[i])
+                .sum();
+            assert!((drained - grid.size() as f64).abs() < 1e-6);
+
+            let rivers = RiverNetwork::extract(&flow, &accumulation, 30.0);
+            assert!(!rivers.paths().is_empty());
+            for path in rivers.paths() {
+                for pair in path.windows(2) {
+                    let (dx, dy) = (pair[1][0] - pair[0][0], pair[1][1] - pair[0][1]);
+                    assert!(dx.abs() <= 1.0 && dy.abs() <= 1.0);
+                    assert!(rivers.mask()[(pair[0][0] as usize, pair[0][1] as usize)]);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! Simulations and generators that shape sampled noise into terrain.
+
+pub mod biome;
+pub mod cave;
+pub mod continent;
+pub mod hydraulic;
+pub mod hydrology;
+pub mod thermal;
+
+pub use self::biome::{Biome, BiomeId, BiomeMap, BiomeTable};
+pub use self::cave::CaveGenerator;
+pub use self::continent::{ContinentGenerator, Continents};
+pub use self::hydraulic::HydraulicErosion;
+pub use self::hydrology::{fill_pits, FlowField, FlowRouting, RiverNetwork};
+pub use self::thermal::{Neighbourhood, ThermalErosion};
+
+#[cfg(test)]
+mod test_support {
+    use grid::Grid2d;
+    use noise::fbm::Fbm2d;
+    use noise::{seeded_rng, Noise};
+
+    /// A `size` x `size` heightmap of fbm at frequency 4 over the unit square, edges included,
+    /// scaled by `height`.
+    pub fn fbm_heightmap(seed: u32, size: u32, height: f64) -> Grid2d<f64> {
+        let fbm = Fbm2d::new(seeded_rng(seed)).with_frequency((4.0, 4.0));
+        let spacing = 1.0 / f64::from(size - 1);
+        let mut grid = Grid2d::new(size, size);
+        for y in 0..size {
+            for x in 0..size {
+                let pos = [f64::from(x) * spacing, f64::from(y) * spacing];
+                grid[(x as usize, y as usize)] = fbm.value_at(pos) * height;
+            }
+        }
+        grid
+    }
+}
@@ -0,0 +1,180 @@
+use grid::Grid2d;
+
+/// Which lattice points count as neighbours when material slides downhill.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The four points sharing an edge.
+    VonNeumann,
+    /// The eight points sharing an edge or a corner.
+    Moore,
+}
+
+impl Neighbourhood {
+    /// Offsets of the neighbours along with their distance.
+    fn offsets(self) -> &'static [(isize, isize, f64)] {
+        const D: f64 = ::std::f64::consts::SQRT_2;
+        match self {
+            Neighbourhood::VonNeumann => &[(1, 0, 1.0), (-1, 0, 1.0), (0, 1, 1.0), (0, -1, 1.0)],
+            Neighbourhood::Moore => &[
+                (1, 0, 1.0),
+                (-1, 0, 1.0),
+                (0, 1, 1.0),
+                (0, -1, 1.0),
+                (1, 1, D),
+                (-1, 1, D),
+                (1, -1, D),
+                (-1, -1, D),
+            ],
+        }
+    }
+}
+
+/// Thermal weathering, which lets material slide downhill wherever the slope is steeper than the
+/// talus angle.
+///
+/// Each iteration moves a fraction of the excess height of every lattice point to its lower
+/// neighbours, in proportion to how far each of them exceeds the talus slope. All points are
+/// updated at once, so the result doesn't depend on the order of traversal, and every bit of
+/// material removed from one point is added to another, so the total height is preserved.
+/// Heights are measured in the same units as the spacing between lattice points.
+#[derive(Clone, Debug)]
+pub struct ThermalErosion {
+    iterations: usize,
+    talus: f64,
+    rate: f64,
+    neighbourhood: Neighbourhood,
+}
+
+impl Default for ThermalErosion {
+    fn default() -> ThermalErosion {
+        ThermalErosion::new(50)
+    }
+}
+
+impl ThermalErosion {
+    pub fn new(iterations: usize) -> ThermalErosion {
+        ThermalErosion {
+            iterations,
+            talus: 1.0,
+            rate: 0.5,
+            neighbourhood: Neighbourhood::Moore,
+        }
+    }
+
+    /// The steepest stable slope, as height per unit of distance.
+    pub fn with_talus(self, talus: f64) -> ThermalErosion {
+        assert!(talus >= 0.0, "Talus slope must not be negative");
+        ThermalErosion { talus, ..self }
+    }
+    /// The steepest stable slope, as an angle in radians.
+    pub fn with_talus_angle(self, angle: f64) -> ThermalErosion {
+        self.with_talus(angle.tan())
+    }
+    /// Fraction of the excess height moved per iteration, within `(0, 1]`.
+    pub fn with_rate(self, rate: f64) -> ThermalErosion {
+        assert!(rate > 0.0 && rate <= 1.0, "Rate must be within (0, 1]");
+        ThermalErosion { rate, ..self }
+    }
+    pub fn with_neighbourhood(self, neighbourhood: Neighbourhood) -> ThermalErosion {
+        ThermalErosion {
+            neighbourhood,
+            ..self
+        }
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+    pub fn talus(&self) -> f64 {
+        self.talus
+    }
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+    pub fn neighbourhood(&self) -> Neighbourhood {
+        self.neighbourhood
+    }
+
+    /// Erodes `grid` in place.
+    pub fn erode(&self, grid: &mut Grid2d<f64>) {
+        let (width, height) = (grid.width() as usize, grid.height() as usize);
+        let offsets = self.neighbourhood.offsets();
+        let mut changes = vec![0.0; grid.size()];
+        let mut excess = Vec::with_capacity(offsets.len());
+
+        for _ in 0..self.iterations {
+            for c in changes.iter_mut() {
+                *c = 0.0;
+            }
+            for y in 0..height {
+                for x in 0..width {
+                    let h = grid[(x, y)];
+                    excess.clear();
+                    excess.extend(offsets.iter().filter_map(|&(dx, dy, distance)| {
+                        let (nx, ny) = (x as isize + dx, y as isize + dy);
+                        if nx < 0 || ny < 0 || nx as usize >= width || ny as usize >= height {
+                            return None;
+                        }
+                        let (nx, ny) = (nx as usize, ny as usize);
+                        let e = h - grid[(nx, ny)] - self.talus * distance;
+                        if e > 0.0 {
+                            Some((nx, ny, e))
+                        } else {
+                            None
+                        }
+                    }));
+                    if excess.is_empty() {
+                        continue;
+                    }
+
+                    //Moving half of the largest excess at most levels the steepest pair without
+                    //overshooting, the rate slows that down further.
+                    let total: f64 = excess.iter().map(|e| e.2).sum();
+                    let largest = excess.iter().map(|e| e.2).fold(0.0, f64::max);
+                    let moved = 0.5 * self.rate * largest;
+                    changes[grid.index_from_coords((x, y))] -= moved;
+                    for &(nx, ny, e) in &excess {
+                        changes[grid.index_from_coords((nx, ny))] += moved * e / total;
+                    }
+                }
+            }
+            for (h, c) in grid.data_mut().iter_mut().zip(&changes) {
+                *h += c;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use terrain::test_support::fbm_heightmap;
+
+    fn steepest_slope(grid: &Grid2d<f64>) -> f64 {
+        let mut steepest: f64 = 0.0;
+        for y in 0..grid.height() as usize {
+            for x in 1..grid.width() as usize {
+                steepest = steepest.max((grid[(x, y)] - grid[(x - 1, y)]).abs());
+            }
+        }
+        steepest
+    }
+
+    #[test]
+    fn erosion_preserves_mass_and_flattens_slopes() {
+        let grid = fbm_heightmap(12, 64, 40.0);
+        let mass: f64 = grid.data().iter().sum();
+        assert!(steepest_slope(&grid) > 5.0);
+
+        for &neighbourhood in &[Neighbourhood::VonNeumann, Neighbourhood::Moore] {
+            let mut eroded = grid.clone();
+            ThermalErosion::new(200)
+                .with_talus(0.5)
+                .with_neighbourhood(neighbourhood)
+                .erode(&mut eroded);
+            let eroded_mass: f64 = eroded.data().iter().sum();
+            assert!((eroded_mass - mass).abs() < 1e-9 * mass.abs().max(1.0));
+            assert!(steepest_slope(&eroded) < 1.0);
+        }
+    }
+}